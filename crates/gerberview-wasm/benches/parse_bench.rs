@@ -27,8 +27,35 @@ fn parse_bench(c: &mut Criterion) {
         b.iter(|| black_box(geometry::convert(black_box(&doc))))
     });
 
+    // Round endcaps dominate boards made of many short traces.
+    let traces = short_traces_source(5000);
+    let reader = BufReader::new(Cursor::new(traces.as_bytes()));
+    let traces_doc = match gerber_parser::parse(reader) {
+        Ok(d) => d,
+        Err((d, _)) => d,
+    };
+    group.bench_function("short_traces_convert", |b| {
+        b.iter(|| black_box(geometry::convert(black_box(&traces_doc))))
+    });
+
     group.finish();
 }
 
+/// Builds a Gerber image of `count` short round-aperture traces.
+fn short_traces_source(count: u32) -> String {
+    let mut source = String::from("%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,0.2*%\nD10*\n");
+    for i in 0..count {
+        let x = (i % 100) * 1_000_000;
+        let y = (i / 100) * 1_000_000;
+        source.push_str(&format!(
+            "X{x}Y{y}D02*\nX{}Y{}D01*\n",
+            x + 500_000,
+            y + 250_000
+        ));
+    }
+    source.push_str("M02*\n");
+    source
+}
+
 criterion_group!(benches, parse_bench);
 criterion_main!(benches);
@@ -0,0 +1,212 @@
+//! ZIP archives of fabrication files.
+//!
+//! Board houses exchange a single ZIP of every Gerber and drill file. Only
+//! what such archives use is read: stored and deflated entries, without
+//! encryption or ZIP64 extensions.
+
+use std::io::Read;
+
+use serde::Serialize;
+
+use crate::error::GeometryError;
+use crate::geometry::LayerMeta;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const END_RECORD_LEN: usize = 22;
+const MAX_ARCHIVE_COMMENT_LEN: usize = 0xffff;
+const CENTRAL_HEADER_LEN: usize = 46;
+const LOCAL_HEADER_LEN: usize = 30;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+const FLAG_ENCRYPTED: u16 = 1;
+/// Largest uncompressed entry accepted, so a forged size field or a
+/// decompression bomb cannot exhaust WASM memory.
+pub const MAX_ENTRY_SIZE: usize = 256 * 1024 * 1024;
+
+/// A file extracted from an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file inside the archive.
+    pub name: String,
+    /// Uncompressed file contents.
+    pub data: Vec<u8>,
+}
+
+/// Kind of fabrication file, detected by [`detect_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LayerKind {
+    /// Gerber RS-274X image.
+    Gerber,
+    /// Excellon drill file.
+    Excellon,
+}
+
+/// A layer converted from one archive entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveLayer {
+    /// Path of the source file inside the archive.
+    pub name: String,
+    /// Detected file kind.
+    pub kind: LayerKind,
+    /// Metadata of the converted layer.
+    pub meta: LayerMeta,
+}
+
+/// Extracts every file in the ZIP archive `data`, in central directory order.
+///
+/// Directory entries are skipped.
+///
+/// # Errors
+///
+/// Returns [`GeometryError::ParseError`] if the archive is truncated or
+/// malformed, an entry is encrypted or uses a compression method other than
+/// stored or deflate, an entry is larger than [`MAX_ENTRY_SIZE`] or
+/// inflates to a size other than the one declared, or an entry fails its
+/// CRC check.
+pub fn read_zip(data: &[u8]) -> Result<Vec<ArchiveEntry>, GeometryError> {
+    let end = find_end_record(data).ok_or_else(|| invalid("end of central directory not found"))?;
+    let count = read_u16(data, end.saturating_add(10))?;
+    let mut offset = read_u32(data, end.saturating_add(16))? as usize;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if read_u32(data, offset)? != CENTRAL_DIRECTORY_HEADER {
+            return Err(invalid("bad central directory header"));
+        }
+        let flags = read_u16(data, offset.saturating_add(8))?;
+        let method = read_u16(data, offset.saturating_add(10))?;
+        let crc = read_u32(data, offset.saturating_add(16))?;
+        let compressed_size = read_u32(data, offset.saturating_add(20))? as usize;
+        let size = read_u32(data, offset.saturating_add(24))? as usize;
+        let name_len = usize::from(read_u16(data, offset.saturating_add(28))?);
+        let extra_len = usize::from(read_u16(data, offset.saturating_add(30))?);
+        let comment_len = usize::from(read_u16(data, offset.saturating_add(32))?);
+        let local_offset = read_u32(data, offset.saturating_add(42))? as usize;
+        let name_start = offset.saturating_add(CENTRAL_HEADER_LEN);
+        let name_end = name_start.saturating_add(name_len);
+        let name = data
+            .get(name_start..name_end)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .ok_or_else(truncated)?;
+        offset = name_end.saturating_add(extra_len + comment_len);
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err(invalid(&format!("entry `{name}` is encrypted")));
+        }
+        let body = entry_body(data, local_offset, compressed_size)?;
+        let contents = match method {
+            METHOD_STORED => body.to_vec(),
+            METHOD_DEFLATED => inflate(&name, body, size)?,
+            other => {
+                return Err(invalid(&format!(
+                    "entry `{name}` uses unsupported compression method {other}"
+                )))
+            }
+        };
+        let mut checksum = flate2::Crc::new();
+        checksum.update(&contents);
+        if checksum.sum() != crc {
+            return Err(invalid(&format!("entry `{name}` fails its CRC check")));
+        }
+        entries.push(ArchiveEntry {
+            name,
+            data: contents,
+        });
+    }
+    Ok(entries)
+}
+
+/// Inflates the deflated entry `name`, reading at most one byte past its
+/// declared `size` so that an entry lying about its size is caught without
+/// inflating all of it. The declared size is not trusted for allocation.
+fn inflate(name: &str, body: &[u8], size: usize) -> Result<Vec<u8>, GeometryError> {
+    if size > MAX_ENTRY_SIZE {
+        return Err(invalid(&format!(
+            "entry `{name}` declares {size} bytes, over the {MAX_ENTRY_SIZE} byte limit"
+        )));
+    }
+    let mut inflated = Vec::new();
+    flate2::read::DeflateDecoder::new(body)
+        .take(size as u64 + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|err| invalid(&format!("entry `{name}` is corrupt: {err}")))?;
+    if inflated.len() != size {
+        return Err(invalid(&format!(
+            "entry `{name}` does not inflate to its declared {size} bytes"
+        )));
+    }
+    Ok(inflated)
+}
+
+/// Detects whether `data`, stored as `name`, is a Gerber or Excellon file.
+///
+/// An `M48` header line or a `.drl`/`.xln` extension marks Excellon; `%FS`,
+/// `%MO` or `G04` marks Gerber. Returns `None` for anything else, such as a
+/// readme or a pick-and-place file.
+pub fn detect_kind(name: &str, data: &[u8]) -> Option<LayerKind> {
+    let text = String::from_utf8_lossy(data);
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    if text
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case("M48"))
+        || matches!(extension.as_deref(), Some("drl" | "xln"))
+    {
+        return Some(LayerKind::Excellon);
+    }
+    ["%FS", "%MO", "G04"]
+        .iter()
+        .any(|marker| text.contains(marker))
+        .then_some(LayerKind::Gerber)
+}
+
+/// Returns the offset of the end of central directory record, searching
+/// back over a trailing archive comment.
+fn find_end_record(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(END_RECORD_LEN)?;
+    let first = last.saturating_sub(MAX_ARCHIVE_COMMENT_LEN);
+    (first..=last)
+        .rev()
+        .find(|&offset| read_u32(data, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+}
+
+/// Returns the compressed bytes of the entry whose local header is at
+/// `offset`.
+fn entry_body(data: &[u8], offset: usize, len: usize) -> Result<&[u8], GeometryError> {
+    if read_u32(data, offset)? != LOCAL_FILE_HEADER {
+        return Err(invalid("bad local file header"));
+    }
+    let name_len = usize::from(read_u16(data, offset.saturating_add(26))?);
+    let extra_len = usize::from(read_u16(data, offset.saturating_add(28))?);
+    let start = offset.saturating_add(LOCAL_HEADER_LEN + name_len + extra_len);
+    data.get(start..start.saturating_add(len))
+        .ok_or_else(truncated)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, GeometryError> {
+    match data.get(offset..offset.saturating_add(2)) {
+        Some(&[a, b]) => Ok(u16::from_le_bytes([a, b])),
+        _ => Err(truncated()),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, GeometryError> {
+    match data.get(offset..offset.saturating_add(4)) {
+        Some(&[a, b, c, d]) => Ok(u32::from_le_bytes([a, b, c, d])),
+        _ => Err(truncated()),
+    }
+}
+
+fn truncated() -> GeometryError {
+    invalid("archive is truncated")
+}
+
+fn invalid(message: &str) -> GeometryError {
+    GeometryError::ParseError(format!("invalid ZIP archive: {message}"))
+}
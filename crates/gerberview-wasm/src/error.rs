@@ -32,4 +32,33 @@ pub enum GeometryError {
     /// A Gerber file could not be parsed.
     #[error("parse error: {0}")]
     ParseError(String),
+
+    /// A triangle or time budget ran out before conversion finished.
+    #[error("conversion truncated: {0}")]
+    Truncated(String),
+
+    /// Strict mode promoted one or more warnings to errors.
+    #[error("strict mode violations: {}", .0.join("; "))]
+    StrictViolation(Vec<String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_and_strict_errors_display_their_details() {
+        assert_eq!(
+            GeometryError::Truncated("triangle budget of 10 reached".to_string()).to_string(),
+            "conversion truncated: triangle budget of 10 reached"
+        );
+        assert_eq!(
+            GeometryError::StrictViolation(vec![
+                "aperture D11 not defined; skipping flash".to_string(),
+                "region boundary is not closed".to_string(),
+            ])
+            .to_string(),
+            "strict mode violations: aperture D11 not defined; skipping flash; region boundary is not closed"
+        );
+    }
 }
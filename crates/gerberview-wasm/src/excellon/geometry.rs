@@ -0,0 +1,183 @@
+//! Drill hole tessellation.
+//!
+//! [`ExcellonGeometryBuilder`] turns holes into the same [`LayerGeometry`]
+//! as a parsed drill file, whether they come from the Excellon parser or
+//! are generated programmatically.
+
+use crate::geometry::types::saturate_u32;
+use crate::geometry::{GeometryBuilder, LayerGeometry, LayerRole, WarningCategory};
+
+use super::types::{DrillHole, DrillMirror, DrillRotation};
+
+/// Number of segments used to tessellate each hole.
+pub const HOLE_SEGMENTS: u32 = 32;
+
+/// Accumulates drill holes into drill layer geometry.
+///
+/// Holes are mirrored, then rotated, then tessellated as filled circles of
+/// [`HOLE_SEGMENTS`] segments, or as rings when a ring width is set.
+#[derive(Debug, Default)]
+pub struct ExcellonGeometryBuilder {
+    builder: GeometryBuilder,
+    mirror: DrillMirror,
+    rotation: DrillRotation,
+    ring_width: f64,
+    hole_count: usize,
+    tool_ranges: Vec<(u32, u32, u32)>,
+}
+
+impl ExcellonGeometryBuilder {
+    /// Creates a builder with no holes and no transform.
+    pub const fn new() -> Self {
+        Self {
+            builder: GeometryBuilder::new(),
+            mirror: DrillMirror::new(),
+            rotation: DrillRotation::new(),
+            ring_width: 0.0,
+            hole_count: 0,
+            tool_ranges: Vec::new(),
+        }
+    }
+
+    /// Mirrors holes added afterwards.
+    #[must_use]
+    pub const fn with_mirror(mut self, mirror: DrillMirror) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    /// Rotates holes added afterwards, after any mirroring.
+    #[must_use]
+    pub const fn with_rotation(mut self, rotation: DrillRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Draws holes added afterwards as annular rings of `width`. Holes whose
+    /// radius is not larger than `width` stay filled; `0.0` fills every hole.
+    #[must_use]
+    pub const fn with_ring_width(mut self, width: f64) -> Self {
+        self.ring_width = width;
+        self
+    }
+
+    /// Adds a hole drilled by tool 0.
+    pub fn add_hole(&mut self, x: f64, y: f64, diameter: f64) {
+        self.add_drill_hole(&DrillHole {
+            x,
+            y,
+            diameter,
+            tool: 0,
+        });
+    }
+
+    /// Adds a parsed hole, extending the index range of its tool when the
+    /// previous hole used the same tool.
+    pub fn add_drill_hole(&mut self, hole: &DrillHole) {
+        let start = self.builder.index_count();
+        let hole = self.rotation.apply(&self.mirror.apply(hole));
+        let radius = hole.diameter / 2.0;
+        if self.ring_width > 0.0 && self.ring_width < radius {
+            self.builder.push_ring(
+                hole.x,
+                hole.y,
+                radius,
+                radius - self.ring_width,
+                HOLE_SEGMENTS,
+            );
+        } else {
+            self.builder
+                .push_ngon(hole.x, hole.y, radius, HOLE_SEGMENTS);
+        }
+        let end = self.builder.index_count();
+        match self.tool_ranges.last_mut() {
+            Some((tool, _, tool_end)) if *tool == hole.tool => *tool_end = end,
+            _ => self.tool_ranges.push((hole.tool, start, end)),
+        }
+        self.hole_count += 1;
+    }
+
+    /// Records a warning message.
+    pub fn warn(&mut self, message: String) {
+        self.builder.warn(message);
+    }
+
+    /// Records a parser warning with its strict-mode category.
+    pub fn record_warning(&mut self, message: String, category: Option<WarningCategory>) {
+        self.builder.record_warning(message, None, category);
+    }
+
+    /// Returns the `(tool, start, end)` index range of each run of holes
+    /// with the same tool, in the order added.
+    pub fn tool_ranges(&self) -> &[(u32, u32, u32)] {
+        &self.tool_ranges
+    }
+
+    /// Consumes the builder and returns the drill layer, with one command
+    /// counted per hole.
+    pub fn finish(self) -> LayerGeometry {
+        let mut geom = self.builder.build();
+        geom.command_count = saturate_u32(self.hole_count);
+        geom.layer_role = LayerRole::Drill;
+        geom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn programmatic_holes_match_ngon_tessellation() {
+        let mut holes = ExcellonGeometryBuilder::new();
+        holes.add_hole(0.0, 0.0, 0.8);
+        holes.add_hole(2.54, 0.0, 0.8);
+        holes.add_hole(5.08, 0.0, 1.0);
+        let geom = holes.finish();
+
+        let mut reference = GeometryBuilder::new();
+        for x in [0.0, 2.54, 5.08] {
+            reference.push_ngon(x, 0.0, 0.4, HOLE_SEGMENTS);
+        }
+        let reference = reference.build();
+
+        assert_eq!(geom.vertex_count, reference.vertex_count);
+        assert_eq!(geom.indices.len(), reference.indices.len());
+        assert_eq!(geom.command_count, 3);
+        assert_eq!(geom.layer_role, LayerRole::Drill);
+        assert!((geom.bounds.max_x - 5.58).abs() < 1e-6);
+
+        let expected = [(0.0, 0.0, 0.8), (2.54, 0.0, 0.8), (5.08, 0.0, 1.0)];
+        let hole_floats = HOLE_SEGMENTS as usize * 2;
+        let rims: Vec<&[f32]> = geom.positions.chunks_exact(hole_floats).collect();
+        assert_eq!(rims.len(), expected.len());
+        for (rim, (cx, cy, diameter)) in rims.into_iter().zip(expected) {
+            let vertices: Vec<(f64, f64)> = rim
+                .chunks_exact(2)
+                .filter_map(|xy| match *xy {
+                    [x, y] => Some((f64::from(x), f64::from(y))),
+                    _ => None,
+                })
+                .collect();
+            let count = f64::from(HOLE_SEGMENTS);
+            let centroid_x = vertices.iter().map(|&(x, _)| x).sum::<f64>() / count;
+            let centroid_y = vertices.iter().map(|&(_, y)| y).sum::<f64>() / count;
+            assert!(
+                (centroid_x - cx).abs() < 1e-5,
+                "centroid x {centroid_x} != {cx}"
+            );
+            assert!(
+                (centroid_y - cy).abs() < 1e-5,
+                "centroid y {centroid_y} != {cy}"
+            );
+            for (x, y) in vertices {
+                let radius = (x - cx).hypot(y - cy);
+                assert!(
+                    (radius - diameter / 2.0).abs() < 1e-5,
+                    "rim vertex at radius {radius}, expected {}",
+                    diameter / 2.0
+                );
+            }
+        }
+    }
+}
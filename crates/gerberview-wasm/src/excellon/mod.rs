@@ -1,6 +1,8 @@
 //! Excellon drill file types and parsing.
 
+pub mod geometry;
 pub mod parser;
 pub mod types;
 
+pub use geometry::*;
 pub use types::*;
@@ -1,10 +1,13 @@
 //! Excellon drill parser.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::ops::Range;
 
 use crate::error::GeometryError;
+use crate::geometry::WarningCategory;
+use crate::preprocess::decode_text;
 
-use super::types::{DrillHole, ExcellonResult, ExcellonUnits, ToolDefinition};
+use super::types::{DrillHole, ExcellonHeader, ExcellonResult, ExcellonUnits, ToolDefinition};
 
 const DEFAULT_INTEGER_DIGITS: u8 = 2;
 const DEFAULT_DECIMAL_DIGITS: u8 = 4;
@@ -21,12 +24,18 @@ struct ParserState {
     integer_digits: u8,
     decimal_digits: u8,
     suppression: ZeroSuppression,
-    tools: HashMap<u32, f64>,
+    tools: BTreeMap<u32, f64>,
     current_tool: Option<u32>,
     holes: Vec<DrillHole>,
     warnings: Vec<String>,
+    warning_categories: Vec<Option<WarningCategory>>,
     declared_units: bool,
     in_header: bool,
+    warned_incremental: bool,
+    /// Index of the first hole of the pattern being recorded after `M25`.
+    pattern_start: Option<usize>,
+    /// Holes of the last pattern closed by `M01`, replayed by `M02X#Y#`.
+    pattern: Option<Range<usize>>,
 }
 
 impl Default for ParserState {
@@ -36,40 +45,44 @@ impl Default for ParserState {
             integer_digits: DEFAULT_INTEGER_DIGITS,
             decimal_digits: DEFAULT_DECIMAL_DIGITS,
             suppression: ZeroSuppression::Leading,
-            tools: HashMap::new(),
+            tools: BTreeMap::new(),
             current_tool: None,
             holes: Vec::new(),
             warnings: Vec::new(),
+            warning_categories: Vec::new(),
             declared_units: false,
             in_header: false,
+            warned_incremental: false,
+            pattern_start: None,
+            pattern: None,
         }
     }
 }
 
+impl ParserState {
+    /// Records a warning in a category that strict mode can promote.
+    fn warn_in(&mut self, category: WarningCategory, message: String) {
+        self.warning_categories.resize(self.warnings.len(), None);
+        self.warnings.push(message);
+        self.warning_categories.push(Some(category));
+    }
+}
+
 /// Parse an Excellon drill file and return extracted holes, tools, and metadata.
 ///
 /// # Errors
 ///
 /// Returns [`GeometryError::ParseError`] if the input is empty, not valid UTF-8,
-/// or contains invalid numeric fields in commands that must be parsed.
+/// or contains invalid numeric fields in commands that must be parsed. A
+/// leading byte-order mark is skipped and UTF-16 input is transcoded, see
+/// [`crate::preprocess::decode_text`].
 pub fn parse(data: &[u8]) -> Result<ExcellonResult, GeometryError> {
-    if data.is_empty() {
-        return Err(GeometryError::ParseError("empty input".to_string()));
-    }
-
-    let content = std::str::from_utf8(data)
-        .map_err(|err| GeometryError::ParseError(format!("invalid UTF-8 input: {err}")))?;
+    let data = decode_text(data);
+    let content = decode_content(&data)?;
 
     let mut state = ParserState::default();
 
-    for raw_line in content.lines() {
-        let line = raw_line.trim();
-        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
-            continue;
-        }
-
-        let normalized = line.to_ascii_uppercase();
-
+    for normalized in commands(content) {
         if normalized == "M48" {
             state.in_header = true;
             continue;
@@ -80,7 +93,7 @@ pub fn parse(data: &[u8]) -> Result<ExcellonResult, GeometryError> {
             continue;
         }
 
-        if normalized == "M30" {
+        if is_end_of_program(&normalized) {
             break;
         }
 
@@ -91,18 +104,83 @@ pub fn parse(data: &[u8]) -> Result<ExcellonResult, GeometryError> {
         }
     }
 
-    let mut tools: Vec<ToolDefinition> = state
+    let tools: Vec<ToolDefinition> = state
         .tools
         .into_iter()
         .map(|(number, diameter)| ToolDefinition { number, diameter })
         .collect();
-    tools.sort_by_key(|tool| tool.number);
 
+    state.warning_categories.resize(state.warnings.len(), None);
     Ok(ExcellonResult {
         holes: state.holes,
         tools,
         units: state.units,
         warnings: state.warnings,
+        warning_categories: state.warning_categories,
+    })
+}
+
+/// Parse only the `M48` header of an Excellon drill file.
+///
+/// Stops at the `%` that ends the header, or at the first line that is not a
+/// unit declaration when the file has no header, so the drill body is never
+/// read.
+///
+/// # Errors
+///
+/// Returns [`GeometryError::ParseError`] as [`parse`] does for the header.
+pub fn parse_header(data: &[u8]) -> Result<ExcellonHeader, GeometryError> {
+    let data = decode_text(data);
+    let content = decode_content(&data)?;
+
+    let mut state = ParserState::default();
+
+    for normalized in commands(content) {
+        if normalized == "M48" {
+            state.in_header = true;
+        } else if state.in_header {
+            if normalized == "%" || is_end_of_program(&normalized) {
+                break;
+            }
+            parse_header_line(&normalized, &mut state)?;
+        } else if !apply_units_directive(&normalized, &mut state) {
+            break;
+        }
+    }
+
+    Ok(ExcellonHeader {
+        tools: state
+            .tools
+            .into_iter()
+            .map(|(number, diameter)| ToolDefinition { number, diameter })
+            .collect(),
+        units: state.units,
+        declared_units: state.declared_units,
+        format: (state.integer_digits, state.decimal_digits),
+    })
+}
+
+/// Checks that decoded input is non-empty UTF-8.
+fn decode_content(data: &[u8]) -> Result<&str, GeometryError> {
+    if data.is_empty() {
+        return Err(GeometryError::ParseError("empty input".to_string()));
+    }
+    std::str::from_utf8(data)
+        .map_err(|err| GeometryError::ParseError(format!("invalid UTF-8 input: {err}")))
+}
+
+/// Splits `content` into upper-cased commands, one per line, accepting
+/// `\n`, `\r\n` and bare `\r` line endings. Whitespace and control
+/// characters inside a command are dropped, so `T1 C0.8` reads as `T1C0.8`.
+/// Blank and comment lines are skipped.
+fn commands(content: &str) -> impl Iterator<Item = String> + '_ {
+    content.split(['\r', '\n']).filter_map(|line| {
+        let command: String = line
+            .chars()
+            .filter(|ch| !ch.is_whitespace() && !ch.is_control())
+            .map(|ch| ch.to_ascii_uppercase())
+            .collect();
+        (!command.is_empty() && !command.starts_with([';', '#'])).then_some(command)
     })
 }
 
@@ -127,31 +205,67 @@ fn parse_body_line(line: &str, state: &mut ParserState) -> Result<(), GeometryEr
         return Ok(());
     }
 
+    if apply_pattern_command(line, state) {
+        return Ok(());
+    }
+
+    if is_control_command(line) {
+        if line == "G91" && !state.warned_incremental {
+            state.warned_incremental = true;
+            state.warnings.push(
+                "G91 incremental mode not supported; coordinates treated as absolute".to_string(),
+            );
+        }
+        return Ok(());
+    }
+
     if let Some((tool_number, diameter)) = parse_tool_definition(line)? {
         register_tool(state, tool_number, diameter);
         return Ok(());
     }
 
-    if let Some(tool_number) = parse_tool_selection(line)? {
+    let mut line = line;
+    if let Some((tool_number, rest)) = parse_tool_selection(line)? {
         if state.tools.contains_key(&tool_number) {
             state.current_tool = Some(tool_number);
         } else {
             state.current_tool = None;
-            state
-                .warnings
-                .push(format!("tool T{tool_number} selected but not defined"));
+            state.warn_in(
+                WarningCategory::UndefinedTool,
+                format!("tool T{tool_number} selected but not defined"),
+            );
         }
-        return Ok(());
+        if rest.is_empty() {
+            return Ok(());
+        }
+        // Some dialects place the first hole on the tool selection line.
+        line = rest;
     }
 
-    if let Some((x, y)) = parse_xy_coordinates(line, state)? {
+    let coordinates = match parse_xy_coordinates(line, state) {
+        Ok(coordinates) => coordinates,
+        Err(err) => {
+            state.warnings.push(format!("hole `{line}` skipped: {err}"));
+            return Ok(());
+        }
+    };
+
+    if let Some((x, y)) = coordinates {
         if let Some(tool_number) = state.current_tool {
             if let Some(diameter) = state.tools.get(&tool_number).copied() {
-                state.holes.push(DrillHole { x, y, diameter });
+                state.holes.push(DrillHole {
+                    x,
+                    y,
+                    diameter,
+                    tool: tool_number,
+                });
             } else {
-                state.warnings.push(format!(
-                    "hole at ({x}, {y}) skipped: selected tool T{tool_number} is undefined"
-                ));
+                state.warn_in(
+                    WarningCategory::UndefinedTool,
+                    format!(
+                        "hole at ({x}, {y}) skipped: selected tool T{tool_number} is undefined"
+                    ),
+                );
             }
         } else {
             state
@@ -173,9 +287,10 @@ fn apply_units_directive(line: &str, state: &mut ParserState) -> bool {
     };
 
     if state.declared_units && state.units != units {
-        state
-            .warnings
-            .push("mixed unit declarations detected; last declaration wins".to_string());
+        state.warn_in(
+            WarningCategory::MixedUnits,
+            "mixed unit declarations detected; last declaration wins".to_string(),
+        );
     }
 
     state.units = units;
@@ -190,6 +305,63 @@ fn apply_units_directive(line: &str, state: &mut ParserState) -> bool {
     true
 }
 
+/// Handles the pattern commands: `M25` starts recording holes, `M01` ends
+/// the pattern, `M02X#Y#` replays it offset from where it was drilled and
+/// `M08` ends the step and repeat. The `M70`/`M80`/`M90` axis swap and
+/// mirror modifiers are not supported and only warn.
+///
+/// Returns `false` if `line` is not a pattern command.
+fn apply_pattern_command(line: &str, state: &mut ParserState) -> bool {
+    match line {
+        "M25" => state.pattern_start = Some(state.holes.len()),
+        "M01" => {
+            if let Some(start) = state.pattern_start.take() {
+                state.pattern = Some(start..state.holes.len());
+            }
+        }
+        "M08" => state.pattern = None,
+        "M70" | "M80" | "M90" => state.warnings.push(format!(
+            "pattern modifier {line} not supported; repeats are not swapped or mirrored"
+        )),
+        _ => {
+            let Some(offset) = line.strip_prefix("M02").filter(|rest| !rest.is_empty()) else {
+                return false;
+            };
+            repeat_pattern(offset, state);
+        }
+    }
+    true
+}
+
+/// Appends a copy of the recorded pattern moved by the `X#Y#` `offset`.
+fn repeat_pattern(offset: &str, state: &mut ParserState) {
+    let Ok(Some((dx, dy))) = parse_xy_coordinates(offset, state) else {
+        state.warnings.push(format!(
+            "pattern repeat `M02{offset}` skipped: invalid offset"
+        ));
+        return;
+    };
+    let Some(pattern) = state.pattern.clone() else {
+        state.warnings.push(format!(
+            "pattern repeat `M02{offset}` skipped: no pattern defined"
+        ));
+        return;
+    };
+    let copies: Vec<DrillHole> = state
+        .holes
+        .get(pattern)
+        .unwrap_or_default()
+        .iter()
+        .map(|hole| DrillHole {
+            x: hole.x + dx,
+            y: hole.y + dy,
+            diameter: hole.diameter,
+            tool: hole.tool,
+        })
+        .collect();
+    state.holes.extend(copies);
+}
+
 fn register_tool(state: &mut ParserState, tool_number: u32, diameter: f64) {
     if diameter <= 0.0 {
         state.warnings.push(format!(
@@ -234,24 +406,31 @@ fn parse_tool_definition(line: &str) -> Result<Option<(u32, f64)>, GeometryError
     Ok(Some((tool_number, diameter)))
 }
 
-fn parse_tool_selection(line: &str) -> Result<Option<u32>, GeometryError> {
+/// Parses `T<digits>`, optionally followed by the coordinates of a hole
+/// (`T01X015Y015`). Returns the tool number and the remaining text.
+fn parse_tool_selection(line: &str) -> Result<Option<(u32, &str)>, GeometryError> {
     if !line.starts_with('T') || line.contains('C') {
         return Ok(None);
     }
 
-    let Some(tool_raw) = line.strip_prefix('T') else {
+    let Some(after_t) = line.strip_prefix('T') else {
         return Ok(None);
     };
 
+    let digits_end = after_t
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(after_t.len());
+    let (tool_raw, rest) = after_t.split_at(digits_end);
+
     if tool_raw.is_empty() {
         return Ok(None);
     }
 
-    if !tool_raw.chars().all(|ch| ch.is_ascii_digit()) {
+    if !rest.is_empty() && !rest.starts_with('X') {
         return Ok(None);
     }
 
-    parse_u32(tool_raw, "selected tool number").map(Some)
+    parse_u32(tool_raw, "selected tool number").map(|tool| Some((tool, rest)))
 }
 
 fn parse_xy_coordinates(
@@ -294,10 +473,6 @@ fn parse_coordinate(
     decimal_digits: u8,
     suppression: ZeroSuppression,
 ) -> Result<f64, GeometryError> {
-    if raw.contains('.') {
-        return parse_f64(raw, "coordinate");
-    }
-
     let (sign, digits) = split_sign(raw);
     if digits.is_empty() {
         return Err(GeometryError::ParseError(
@@ -305,6 +480,10 @@ fn parse_coordinate(
         ));
     }
 
+    if digits.contains('.') || digits.contains('E') {
+        return parse_explicit_decimal(raw, digits).map(|value| sign * value);
+    }
+
     if !digits.chars().all(|ch| ch.is_ascii_digit()) {
         return Err(GeometryError::ParseError(format!(
             "invalid coordinate value `{raw}`"
@@ -313,7 +492,9 @@ fn parse_coordinate(
 
     // Many modern drill files use integer coordinates (e.g., X5Y10) in TZ mode.
     // Preserve those values directly when they fit within the integer precision.
-    if digits.len() <= usize::from(integer_digits) {
+    // With leading zeros suppressed the digits are the low-order ones, so a
+    // short field such as `X-15` is a small fraction and must be scaled.
+    if suppression == ZeroSuppression::Trailing && digits.len() <= usize::from(integer_digits) {
         let value = parse_f64(digits, "coordinate")?;
         return Ok(sign * value);
     }
@@ -324,6 +505,31 @@ fn parse_coordinate(
     Ok(sign * value)
 }
 
+/// Parses a coordinate written with an explicit decimal point or exponent,
+/// such as `1.5`, `.5`, `5.` or `1.5E1`. The sign has already been stripped.
+fn parse_explicit_decimal(raw: &str, digits: &str) -> Result<f64, GeometryError> {
+    let (mantissa, exponent) = digits.split_once('E').unwrap_or((digits, "0"));
+    let (_, exponent_digits) = split_sign(exponent);
+    let mantissa_valid = mantissa.chars().any(|ch| ch.is_ascii_digit())
+        && mantissa.chars().all(|ch| ch.is_ascii_digit() || ch == '.')
+        && mantissa.matches('.').count() <= 1;
+    let exponent_valid =
+        !exponent_digits.is_empty() && exponent_digits.chars().all(|ch| ch.is_ascii_digit());
+    if !mantissa_valid || !exponent_valid {
+        return Err(GeometryError::ParseError(format!(
+            "invalid coordinate value `{raw}`"
+        )));
+    }
+
+    let value = parse_f64(digits, "coordinate")?;
+    if !value.is_finite() {
+        return Err(GeometryError::ParseError(format!(
+            "coordinate value `{raw}` is out of range"
+        )));
+    }
+    Ok(value)
+}
+
 fn normalize_implicit_decimal(
     digits: &str,
     integer_digits: u8,
@@ -368,6 +574,22 @@ fn parse_f64(raw: &str, label: &str) -> Result<f64, GeometryError> {
         .map_err(|err| GeometryError::ParseError(format!("invalid {label} `{raw}`: {err}")))
 }
 
+/// `M30` (end of program), `M00` (program stop) and `M02` (end of program
+/// on older exporters) all terminate drilling.
+fn is_end_of_program(line: &str) -> bool {
+    matches!(line, "M30" | "M00" | "M02")
+}
+
+/// Bare M-codes (`M06`, `M47`, ...) and mode G-codes
+/// (`G05`, `G90`, `G91`) carry no hole coordinates and are skipped.
+fn is_control_command(line: &str) -> bool {
+    if let Some(code) = line.strip_prefix('M') {
+        return !code.is_empty() && code.chars().all(|ch| ch.is_ascii_digit());
+    }
+
+    matches!(line, "G05" | "G90" | "G91")
+}
+
 fn is_routing_command(line: &str) -> bool {
     line.starts_with("G00")
         || line.starts_with("G01")
@@ -595,4 +817,174 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn ut_exc_007_signed_decimal_coordinates() {
+        let input = b"M48\nMETRIC\nT1C0.8\n%\nT1\nX-1.5Y+2.25\nX-1.5E1Y+.5\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 2);
+            assert!(parsed.warnings.is_empty());
+            if let [first, second] = parsed.holes.as_slice() {
+                assert!((first.x + 1.5).abs() < EPSILON);
+                assert!((first.y - 2.25).abs() < EPSILON);
+                assert!((second.x + 15.0).abs() < EPSILON);
+                assert!((second.y - 0.5).abs() < EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn bc_exc_009_malformed_coordinate_skips_hole_with_warning() {
+        let input = b"M48\nMETRIC\nT1C0.8\n%\nT1\nX1.2.3Y4.0\nX--1Y2\nX2.0Y3.0\nM30\n";
+        let result = parse(input);
+        assert!(
+            result.is_ok(),
+            "malformed coordinates must not abort the file"
+        );
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 1);
+            let hole = parsed.holes.first();
+            assert!(hole.is_some(), "hole missing");
+            if let Some(hole) = hole {
+                assert!((hole.x - 2.0).abs() < EPSILON);
+                assert!((hole.y - 3.0).abs() < EPSILON);
+            }
+            assert_eq!(
+                parsed
+                    .warnings
+                    .iter()
+                    .filter(|warning| warning.contains("invalid coordinate value"))
+                    .count(),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn bc_exc_010_m02_mid_file_stops_processing() {
+        let input = b"M48\nMETRIC\nT1C0.8\n%\nT1\nX1.0Y1.0\nM02\nX2.0Y2.0\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 1);
+        }
+    }
+
+    #[test]
+    fn bc_exc_011_control_codes_are_skipped() {
+        let input =
+            b"M48\nMETRIC\nT1C0.8\n%\nG90\nG05\nT1\nM25\nX1.0Y1.0\nM01\nM06\nX2.0Y2.0\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 2);
+            assert!(parsed.warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn bc_exc_012_tool_selection_and_hole_on_one_line() {
+        let input = b"M48\nMETRIC\nT1C0.8\nT2C1.2\n%\nT1X1.0Y1.0\nT2\nX2.0Y2.0\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 2);
+            assert!(parsed.warnings.is_empty());
+            let hole = parsed.holes.first();
+            assert!(hole.is_some(), "hole missing");
+            if let Some(hole) = hole {
+                assert!((hole.x - 1.0).abs() < EPSILON);
+                assert!((hole.y - 1.0).abs() < EPSILON);
+                assert!((hole.diameter - 0.8).abs() < EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn bc_exc_013_bare_and_trailing_decimal_points() {
+        let input = b"M48\nMETRIC\nT1C0.8\n%\nT1\nX.5Y.5\nX5.Y10.\nX-.5Y+.25\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+            let coordinates: Vec<(f64, f64)> =
+                parsed.holes.iter().map(|hole| (hole.x, hole.y)).collect();
+            assert_eq!(coordinates.len(), 3);
+            let expected = [(0.5, 0.5), (5.0, 10.0), (-0.5, 0.25)];
+            for ((x, y), (ex, ey)) in coordinates.iter().zip(expected) {
+                assert!((x - ex).abs() < EPSILON, "x {x} != {ex}");
+                assert!((y - ey).abs() < EPSILON, "y {y} != {ey}");
+            }
+        }
+    }
+
+    #[test]
+    fn bc_exc_014_pattern_modifiers_and_orphan_repeats_warn() {
+        let input = b"M48\nMETRIC\nT1C0.8\n%\nT1\nM02X1.0Y1.0\nM25\nX1.0Y1.0\nM01\nM80\nM02X1.0Y0\nM08\nM02X1.0Y1.0\nM30\n";
+        let result = parse(input);
+        assert!(result.is_ok(), "input should parse");
+
+        if let Ok(parsed) = result {
+            assert_eq!(parsed.holes.len(), 2);
+            assert_eq!(parsed.warnings.len(), 3, "{:?}", parsed.warnings);
+            assert!(parsed
+                .warnings
+                .get(1)
+                .is_some_and(|warning| warning.contains("M80 not supported")));
+        }
+    }
+
+    #[test]
+    fn bc_exc_015_negative_implicit_decimal_coordinates_scale_by_suppression() {
+        let trailing = parse(b"M48\nINCH,TZ\nT1C0.035\n%\nT1\nX-150Y-250\nX-15Y-2\nM30\n");
+        let leading = parse(b"M48\nINCH,LZ\nT1C0.035\n%\nT1\nX-150Y-250\nX-15Y-2\nM30\n");
+        assert!(trailing.is_ok() && leading.is_ok(), "input should parse");
+
+        let cases = [
+            (trailing, [(-15.0, -25.0), (-15.0, -2.0)]),
+            (leading, [(-0.015, -0.025), (-0.0015, -0.0002)]),
+        ];
+        for (result, expected) in cases {
+            if let Ok(parsed) = result {
+                assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+                assert_eq!(parsed.holes.len(), 2);
+                for (hole, (ex, ey)) in parsed.holes.iter().zip(expected) {
+                    assert!((hole.x - ex).abs() < EPSILON, "x {} != {ex}", hole.x);
+                    assert!((hole.y - ey).abs() < EPSILON, "y {} != {ey}", hole.y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bc_exc_016_bare_cr_endings_and_spaced_commands() {
+        let bare_cr = parse(b"M48\rMETRIC\rT1C0.8\r%\rT1\rX1.0Y2.0\rX3.0Y4.0\rM30\r");
+        let spaced = parse(b"M48\r\nMETRIC , TZ \r\nT1 C0.8\r\n%\nT1\nX 1.0 Y2.0\t\nM30\n");
+        assert!(bare_cr.is_ok() && spaced.is_ok(), "input should parse");
+
+        if let Ok(parsed) = bare_cr {
+            assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+            assert_eq!(parsed.holes.len(), 2);
+            assert_eq!(parsed.tools.len(), 1);
+        }
+        if let Ok(parsed) = spaced {
+            assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+            assert_eq!(parsed.units, ExcellonUnits::Metric);
+            assert!(parsed
+                .tools
+                .first()
+                .is_some_and(|tool| tool.number == 1 && (tool.diameter - 0.8).abs() < EPSILON));
+            assert!(parsed.holes.first().is_some_and(
+                |hole| (hole.x - 1.0).abs() < EPSILON && (hole.y - 2.0).abs() < EPSILON
+            ));
+        }
+    }
 }
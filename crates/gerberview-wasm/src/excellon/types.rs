@@ -1,5 +1,7 @@
 //! Excellon drill file types.
 
+use crate::geometry::WarningCategory;
+
 /// A single drill hole from Excellon parsing.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DrillHole {
@@ -9,6 +11,99 @@ pub struct DrillHole {
     pub y: f64,
     /// Diameter of the drill hole.
     pub diameter: f64,
+    /// Number of the tool that drilled the hole.
+    pub tool: u32,
+}
+
+/// Optional mirroring applied to drill holes before tessellation.
+///
+/// Bottom-side drill files overlaid on a mirrored copper view need their
+/// holes reflected about the same axis. Diameters are unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DrillMirror {
+    /// Reflect X about `origin_x`.
+    pub mirror_x: bool,
+    /// Reflect Y about `origin_y`.
+    pub mirror_y: bool,
+    /// X coordinate of the vertical mirror axis.
+    pub origin_x: f64,
+    /// Y coordinate of the horizontal mirror axis.
+    pub origin_y: f64,
+}
+
+impl DrillMirror {
+    /// Creates a mirror that leaves holes unchanged.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            mirror_x: false,
+            mirror_y: false,
+            origin_x: 0.0,
+            origin_y: 0.0,
+        }
+    }
+
+    /// Returns `hole` reflected by the enabled axes.
+    #[must_use]
+    pub fn apply(&self, hole: &DrillHole) -> DrillHole {
+        let x = if self.mirror_x {
+            2.0f64.mul_add(self.origin_x, -hole.x)
+        } else {
+            hole.x
+        };
+        let y = if self.mirror_y {
+            2.0f64.mul_add(self.origin_y, -hole.y)
+        } else {
+            hole.y
+        };
+        DrillHole {
+            x,
+            y,
+            diameter: hole.diameter,
+            tool: hole.tool,
+        }
+    }
+}
+
+/// Optional rotation applied to drill holes before tessellation.
+///
+/// Aligns a drill file with a rotated board view. When combined with a
+/// [`DrillMirror`], holes are mirrored first and then rotated. Diameters are
+/// unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DrillRotation {
+    /// Counter-clockwise rotation in degrees.
+    pub angle_deg: f64,
+    /// X coordinate of the rotation center.
+    pub origin_x: f64,
+    /// Y coordinate of the rotation center.
+    pub origin_y: f64,
+}
+
+impl DrillRotation {
+    /// Creates a rotation that leaves holes unchanged.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            angle_deg: 0.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+        }
+    }
+
+    /// Returns `hole` rotated about the origin.
+    #[must_use]
+    pub fn apply(&self, hole: &DrillHole) -> DrillHole {
+        let (sin, cos) = self.angle_deg.to_radians().sin_cos();
+        let dx = hole.x - self.origin_x;
+        let dy = hole.y - self.origin_y;
+        DrillHole {
+            x: dx.mul_add(cos, -(dy * sin)) + self.origin_x,
+            y: dx.mul_add(sin, dy * cos) + self.origin_y,
+            diameter: hole.diameter,
+            tool: hole.tool,
+        }
+    }
 }
 
 /// Excellon tool definition from the file header.
@@ -29,6 +124,19 @@ pub enum ExcellonUnits {
     Imperial,
 }
 
+/// Header of an Excellon file, read without its drill body.
+#[derive(Debug, Clone)]
+pub struct ExcellonHeader {
+    /// Tool definitions from the header.
+    pub tools: Vec<ToolDefinition>,
+    /// Unit system specified in the header, or the imperial default.
+    pub units: ExcellonUnits,
+    /// Whether the header declared its units.
+    pub declared_units: bool,
+    /// Integer and decimal digits used to read coordinates.
+    pub format: (u8, u8),
+}
+
 /// Result of Excellon parsing for a single file.
 #[derive(Debug, Clone)]
 pub struct ExcellonResult {
@@ -40,4 +148,6 @@ pub struct ExcellonResult {
     pub units: ExcellonUnits,
     /// Parser warnings encountered while processing the file.
     pub warnings: Vec<String>,
+    /// Strict-mode category of each warning, parallel to `warnings`.
+    pub warning_categories: Vec<Option<WarningCategory>>,
 }
@@ -14,6 +14,9 @@ use super::types::{GeometryBuilder, Point};
 const CIRCLE_SEGMENTS: u32 = 32;
 const OBROUND_ENDCAP_SEGMENTS: u32 = 16;
 
+/// Rotation applied to polygon apertures that omit the rotation parameter.
+pub const DEFAULT_POLYGON_ROTATION_DEGREES: f64 = 0.0;
+
 /// Expand a flashed aperture at `position` into renderable triangles.
 ///
 /// Supports standard Gerber apertures: circle, rectangle, obround, and polygon.
@@ -38,6 +41,19 @@ pub fn flash_aperture(
     }
 }
 
+/// Returns the largest dimension of a standard aperture, or `None` for macros.
+#[must_use]
+pub fn aperture_extent(aperture: &Aperture) -> Option<f64> {
+    match aperture {
+        Aperture::Circle(circle) => Some(circle.diameter.abs()),
+        Aperture::Rectangle(rectangle) | Aperture::Obround(rectangle) => {
+            Some(rectangle.x.abs().max(rectangle.y.abs()))
+        }
+        Aperture::Polygon(polygon) => Some(polygon.diameter.abs()),
+        Aperture::Macro(..) => None,
+    }
+}
+
 fn normalize_dimension(
     builder: &mut GeometryBuilder,
     value: f64,
@@ -58,7 +74,7 @@ fn normalize_dimension(
     }
 
     if normalized <= f64::EPSILON {
-        builder.warn(format!("{label} is zero; skipping aperture flash"));
+        builder.warn_zero_dimension(label);
         return Ok(None);
     }
 
@@ -75,7 +91,8 @@ fn flash_circle(
     };
 
     let radius = diameter / 2.0;
-    builder.push_ngon(position.x, position.y, radius, CIRCLE_SEGMENTS);
+    let segments = builder.options().curve_segments(CIRCLE_SEGMENTS);
+    builder.push_ngon(position.x, position.y, radius, segments);
     Ok(())
 }
 
@@ -108,10 +125,13 @@ fn flash_obround(
     };
 
     if (width - height).abs() <= f64::EPSILON {
-        builder.push_ngon(position.x, position.y, width / 2.0, CIRCLE_SEGMENTS);
+        let segments = builder.options().curve_segments(CIRCLE_SEGMENTS);
+        builder.push_ngon(position.x, position.y, width / 2.0, segments);
         return Ok(());
     }
 
+    let endcap_segments = builder.options().curve_segments(OBROUND_ENDCAP_SEGMENTS);
+
     if width > height {
         let radius = height / 2.0;
         let body_width = width - height;
@@ -130,7 +150,7 @@ fn flash_obround(
             radius,
             FRAC_PI_2,
             3.0 * FRAC_PI_2,
-            OBROUND_ENDCAP_SEGMENTS,
+            endcap_segments,
         );
 
         push_semi_circle(
@@ -142,7 +162,7 @@ fn flash_obround(
             radius,
             -FRAC_PI_2,
             FRAC_PI_2,
-            OBROUND_ENDCAP_SEGMENTS,
+            endcap_segments,
         );
     } else {
         let radius = width / 2.0;
@@ -162,7 +182,7 @@ fn flash_obround(
             radius,
             0.0,
             PI,
-            OBROUND_ENDCAP_SEGMENTS,
+            endcap_segments,
         );
 
         push_semi_circle(
@@ -174,7 +194,7 @@ fn flash_obround(
             radius,
             PI,
             TAU,
-            OBROUND_ENDCAP_SEGMENTS,
+            endcap_segments,
         );
     }
 
@@ -186,28 +206,10 @@ fn flash_polygon(
     polygon: &Polygon,
     position: Point,
 ) -> Result<(), GeometryError> {
-    let Some(diameter) = normalize_dimension(builder, polygon.diameter, "polygon diameter")? else {
+    let Some((sides, radius, rotation)) = validate_polygon(builder, polygon)? else {
         return Ok(());
     };
 
-    if polygon.vertices < 3 {
-        return Err(GeometryError::InvalidAperture(format!(
-            "polygon has {} vertices; expected at least 3",
-            polygon.vertices
-        )));
-    }
-
-    let rotation_degrees = polygon.rotation.unwrap_or(0.0);
-    if !rotation_degrees.is_finite() {
-        return Err(GeometryError::InvalidAperture(format!(
-            "polygon rotation must be finite, got {rotation_degrees}"
-        )));
-    }
-
-    let sides = u32::from(polygon.vertices);
-    let radius = diameter / 2.0;
-    let rotation = rotation_degrees.to_radians();
-
     let mut first_index: Option<u32> = None;
     for i in 0..sides {
         let angle = rotation + TAU * f64::from(i) / f64::from(sides);
@@ -234,6 +236,44 @@ fn flash_polygon(
     Ok(())
 }
 
+/// Validates a polygon aperture for both flash and stroke use.
+///
+/// Returns `(sides, radius, rotation_radians)`, or `None` with a warning when
+/// the diameter is zero. A negative diameter is used as its absolute value.
+///
+/// # Errors
+///
+/// Returns an error when the polygon has fewer than 3 vertices or when the
+/// diameter or rotation is not finite.
+pub(crate) fn validate_polygon(
+    builder: &mut GeometryBuilder,
+    polygon: &Polygon,
+) -> Result<Option<(u32, f64, f64)>, GeometryError> {
+    if polygon.vertices < 3 {
+        return Err(GeometryError::InvalidAperture(format!(
+            "polygon has {} vertices; expected at least 3",
+            polygon.vertices
+        )));
+    }
+
+    let rotation_degrees = polygon.rotation.unwrap_or(DEFAULT_POLYGON_ROTATION_DEGREES);
+    if !rotation_degrees.is_finite() {
+        return Err(GeometryError::InvalidAperture(format!(
+            "polygon rotation must be finite, got {rotation_degrees}"
+        )));
+    }
+
+    let Some(diameter) = normalize_dimension(builder, polygon.diameter, "polygon diameter")? else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        u32::from(polygon.vertices),
+        diameter / 2.0,
+        rotation_degrees.to_radians(),
+    )))
+}
+
 fn add_index(base: u32, offset: u32) -> Result<u32, GeometryError> {
     base.checked_add(offset).ok_or_else(|| {
         GeometryError::DegenerateGeometry("vertex index overflow while triangulating".to_string())
@@ -263,17 +303,13 @@ fn push_semi_circle(
     let segment_count = segments.max(1);
     let step = (end_angle - start_angle) / f64::from(segment_count);
 
-    let mut previous_index: Option<u32> = None;
     for i in 0..=segment_count {
         let angle = start_angle + step * f64::from(i);
         let x = radius.mul_add(angle.cos(), center.x);
         let y = radius.mul_add(angle.sin(), center.y);
-        let index = builder.push_vertex(x, y);
-        if let Some(previous) = previous_index {
-            builder.push_triangle(center_index, previous, index);
-        }
-        previous_index = Some(index);
+        builder.push_vertex(x, y);
     }
+    builder.push_fan_range(center_index, center_index + 1, segment_count + 1);
 }
 
 #[cfg(test)]
@@ -407,4 +443,27 @@ mod tests {
         assert!((geom.bounds.max_y - 0.5).abs() < EPSILON);
         assert!(geom.warnings.iter().any(|msg| msg.contains("negative")));
     }
+
+    #[test]
+    fn ut_apr_010_two_vertex_polygon_errors_identically_for_flash_and_draw() {
+        let aperture = Aperture::Polygon(Polygon::new(1.0, 2));
+        let origin = Point { x: 0.0, y: 0.0 };
+
+        let mut flash_builder = GeometryBuilder::new();
+        let flash_err = flash_aperture(&mut flash_builder, &aperture, origin).err();
+        let mut draw_builder = GeometryBuilder::new();
+        let draw_err = crate::geometry::draw_linear(
+            &mut draw_builder,
+            origin,
+            Point { x: 1.0, y: 0.0 },
+            &aperture,
+        )
+        .err();
+
+        assert!(matches!(flash_err, Some(GeometryError::InvalidAperture(_))));
+        assert_eq!(
+            flash_err.map(|e| e.to_string()),
+            draw_err.map(|e| e.to_string())
+        );
+    }
 }
@@ -0,0 +1,244 @@
+//! Aperture table summary for fabrication review.
+
+use std::collections::BTreeMap;
+
+use gerber_parser::GerberDoc;
+use gerber_types::{Aperture, ApertureMacro, MacroContent, MacroDecimal};
+use serde::Serialize;
+
+use super::operations::{operations, predefined_macros, sorted_apertures, ResolvedOp};
+
+/// Shape of an aperture definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ApertureKind {
+    /// `C` standard circle.
+    Circle,
+    /// `R` standard rectangle.
+    Rectangle,
+    /// `O` standard obround.
+    Obround,
+    /// `P` standard regular polygon.
+    Polygon,
+    /// Aperture macro instance.
+    Macro,
+}
+
+/// One `%AD` aperture definition and how often it is used.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApertureInfo {
+    /// D-code number.
+    pub dcode: i32,
+    /// Aperture shape.
+    pub kind: ApertureKind,
+    /// Size parameters in file units, as written in the definition:
+    /// `[diameter]` for circles, `[x, y]` for rectangles and obrounds,
+    /// `[diameter, vertices, rotation]` for polygons and the macro
+    /// parameters for macros.
+    pub parameters: Vec<f64>,
+    /// Hole diameter of a standard aperture, in file units.
+    pub hole_diameter: Option<f64>,
+    /// Macro name for [`ApertureKind::Macro`].
+    pub macro_name: Option<String>,
+    /// Text of the macro's comment primitives (code `0`), in definition
+    /// order, with runs of whitespace collapsed to single spaces. `$n`
+    /// references are kept verbatim. Empty for standard apertures.
+    pub macro_comments: Vec<String>,
+    /// Number of D03 flashes with this aperture.
+    pub flash_count: u32,
+    /// Number of D01 linear and circular draws with this aperture.
+    pub draw_count: u32,
+}
+
+/// Lists every aperture defined in `doc`, ordered by D-code.
+///
+/// Usage counts come from the same interpreter that drives
+/// [`super::convert`], so operations skipped there (undefined apertures,
+/// stroked macros) are not counted. Operations inside a step-repeat block
+/// are counted once, not once per repetition. A conversion collects the
+/// same table in [`super::LayerGeometry::apertures`] without a second pass.
+pub fn aperture_report(doc: &GerberDoc) -> Vec<ApertureInfo> {
+    let mut report = aperture_table(doc);
+    for op in operations(doc) {
+        count_aperture_use(&mut report, &op);
+    }
+    report
+}
+
+/// Lists every aperture defined in `doc`, ordered by D-code, with zero
+/// usage counts.
+pub(crate) fn aperture_table(doc: &GerberDoc) -> Vec<ApertureInfo> {
+    let macros = predefined_macros(doc);
+    sorted_apertures(doc)
+        .into_iter()
+        .map(|(dcode, aperture)| describe(dcode, aperture, &macros))
+        .collect()
+}
+
+/// Counts `op` as a flash or draw of its aperture in `table`, which must be
+/// ordered by D-code.
+pub(crate) fn count_aperture_use(table: &mut [ApertureInfo], op: &ResolvedOp<'_>) {
+    let (dcode, flash) = match *op {
+        ResolvedOp::Flash { dcode, .. } | ResolvedOp::MacroFlash { dcode, .. } => (dcode, true),
+        ResolvedOp::Draw { dcode, .. } | ResolvedOp::Arc { dcode, .. } => (dcode, false),
+        ResolvedOp::RegionFill { .. } => return,
+    };
+    let Ok(position) = table.binary_search_by_key(&dcode, |info| info.dcode) else {
+        return;
+    };
+    if let Some(info) = table.get_mut(position) {
+        let count = if flash {
+            &mut info.flash_count
+        } else {
+            &mut info.draw_count
+        };
+        *count = count.saturating_add(1);
+    }
+}
+
+fn describe(
+    dcode: i32,
+    aperture: &Aperture,
+    macros: &BTreeMap<&str, &ApertureMacro>,
+) -> ApertureInfo {
+    let (kind, parameters, hole_diameter, macro_name) = match aperture {
+        Aperture::Circle(circle) => (
+            ApertureKind::Circle,
+            vec![circle.diameter],
+            circle.hole_diameter,
+            None,
+        ),
+        Aperture::Rectangle(rect) => (
+            ApertureKind::Rectangle,
+            vec![rect.x, rect.y],
+            rect.hole_diameter,
+            None,
+        ),
+        Aperture::Obround(rect) => (
+            ApertureKind::Obround,
+            vec![rect.x, rect.y],
+            rect.hole_diameter,
+            None,
+        ),
+        Aperture::Polygon(polygon) => (
+            ApertureKind::Polygon,
+            vec![
+                polygon.diameter,
+                f64::from(polygon.vertices),
+                polygon.rotation.unwrap_or(0.0),
+            ],
+            polygon.hole_diameter,
+            None,
+        ),
+        Aperture::Macro(name, params) => (
+            ApertureKind::Macro,
+            params
+                .iter()
+                .flatten()
+                .filter_map(|param| match param {
+                    MacroDecimal::Value(value) => Some(*value),
+                    _ => None,
+                })
+                .collect(),
+            None,
+            Some(name.clone()),
+        ),
+    };
+    ApertureInfo {
+        dcode,
+        kind,
+        parameters,
+        hole_diameter,
+        macro_comments: macro_name
+            .as_deref()
+            .and_then(|name| macros.get(name))
+            .map_or_else(Vec::new, |macro_def| macro_comments(macro_def)),
+        macro_name,
+        flash_count: 0,
+        draw_count: 0,
+    }
+}
+
+/// Returns the comment primitives of `macro_def`. Comments produce no
+/// geometry; they are only collected for display.
+fn macro_comments(macro_def: &ApertureMacro) -> Vec<String> {
+    macro_def
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MacroContent::Comment(text) => {
+                Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Cursor};
+
+    use super::*;
+
+    fn parse(source: &[u8]) -> GerberDoc {
+        match gerber_parser::parse(BufReader::new(Cursor::new(source))) {
+            Ok(doc) | Err((doc, _)) => doc,
+        }
+    }
+
+    #[test]
+    fn macro_comments_are_reported_without_geometry() {
+        let commented = parse(
+            b"%FSLAX26Y26*%\n%MOMM*%\n%AMPAD*0 Pad  with \n$1 hole*1,1,$1,0,0*%\n\
+%ADD10PAD,0.8*%\nD10*\nX0Y0D03*\nM02*\n",
+        );
+        let plain = parse(
+            b"%FSLAX26Y26*%\n%MOMM*%\n%AMPAD*1,1,$1,0,0*%\n\
+%ADD10PAD,0.8*%\nD10*\nX0Y0D03*\nM02*\n",
+        );
+
+        let report = aperture_report(&commented);
+        let comments: Vec<&[String]> = report
+            .iter()
+            .map(|info| info.macro_comments.as_slice())
+            .collect();
+        assert_eq!(comments, [["Pad with $1 hole".to_string()].as_slice()]);
+        assert!(aperture_report(&plain)
+            .iter()
+            .all(|info| info.macro_comments.is_empty()));
+
+        let with_comment = crate::geometry::convert(&commented);
+        assert!(with_comment
+            .as_ref()
+            .is_ok_and(|geom| geom.vertex_count > 0 && geom.warnings.is_empty()));
+        let mesh = |geom: crate::geometry::LayerGeometry| (geom.positions, geom.indices);
+        assert_eq!(
+            with_comment.ok().map(mesh),
+            crate::geometry::convert(&plain).ok().map(mesh)
+        );
+    }
+
+    #[test]
+    fn flashes_and_draws_are_counted_per_dcode() {
+        let source = b"%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,0.5*%\n%ADD11R,1.0X2.0*%\n%ADD12C,0.1*%\n\
+D10*\nX0Y0D03*\nX1000000Y0D03*\nD11*\nX0Y0D02*\nX2000000Y0D01*\nM02*\n";
+        let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&source[..]))) {
+            Ok(doc) | Err((doc, _)) => doc,
+        };
+        let report = aperture_report(&doc);
+        let summary: Vec<(i32, ApertureKind, u32, u32)> = report
+            .iter()
+            .map(|info| (info.dcode, info.kind, info.flash_count, info.draw_count))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (10, ApertureKind::Circle, 2, 0),
+                (11, ApertureKind::Rectangle, 0, 1),
+                (12, ApertureKind::Circle, 0, 0),
+            ]
+        );
+        assert!(report
+            .iter()
+            .any(|info| info.dcode == 11 && info.parameters == [1.0, 2.0]));
+    }
+}
@@ -9,13 +9,24 @@ use gerber_types::{Aperture, Polygon, Rectangular};
 
 use crate::error::GeometryError;
 
-use super::stroke::draw_linear;
+use super::aperture::validate_polygon;
+use super::options::CapStyle;
+use super::stroke::draw_linear_with_caps;
 use super::types::{GeometryBuilder, Point};
 
 const MIN_ARC_SEGMENTS: u32 = 16;
 const MIN_SEGMENT_LENGTH_FLOOR: f64 = 0.01;
 const RADIUS_MISMATCH_TOLERANCE: f64 = 1e-4;
 const POINT_EQUALITY_EPSILON: f64 = 1e-9;
+/// Sweeps shorter than this (about 0.06°) between endpoints closer than
+/// [`NEAR_COINCIDENT_CHORD`] are taken as a full circle whose endpoints
+/// drifted apart, since coordinate rounding can otherwise turn a near-360°
+/// arc into a sliver.
+const NEAR_FULL_CIRCLE_SWEEP: f64 = 1e-3;
+/// Largest endpoint distance, in millimeters, still treated as the rounding
+/// of coincident endpoints. A short arc on a large radius has a longer chord
+/// and keeps its sweep.
+const NEAR_COINCIDENT_CHORD: f64 = 1e-3;
 
 /// Default max segment length for arc tessellation in region boundaries,
 /// where no stroke width is available to derive segment density.
@@ -42,7 +53,7 @@ pub enum ArcQuadrantMode {
 /// Expand a circular interpolation command into widened triangle geometry.
 ///
 /// The function computes arc center/sweep, tessellates the centerline into
-/// multiple points, then widens each segment using [`draw_linear`].
+/// multiple points, then widens each segment using [`super::stroke::draw_linear`].
 ///
 /// # Errors
 ///
@@ -131,12 +142,31 @@ pub(crate) fn arc_centerline_points(
         }
 
         let end_angle = (to.y - center.y).atan2(to.x - center.x);
-        let sweep = compute_sweep(start_angle, end_angle, direction);
+        let mut sweep = compute_sweep(start_angle, end_angle, direction);
+        if distance(from, to) < NEAR_COINCIDENT_CHORD && sweep.abs() < NEAR_FULL_CIRCLE_SWEEP {
+            builder.warn(format!(
+                "arc sweep of {:.4} degrees between nearly coincident endpoints; drawing full circle",
+                sweep.abs().to_degrees()
+            ));
+            sweep = TAU.copysign(sweep);
+        }
         (radius, sweep)
     };
 
     let arc_length = sweep.abs() * radius;
-    let segments = segment_count_for_arc(arc_length, max_segment_length);
+    let options = builder.options();
+    let max_segments = options.max_arc_segments.max(1);
+    let mut segments = segment_count_for_arc(
+        arc_length,
+        options.arc_segment_length(max_segment_length),
+        options.curve_segments(MIN_ARC_SEGMENTS),
+    );
+    if segments > max_segments {
+        builder.warn(format!(
+            "arc of radius {radius} needs {segments} segments; clamping to {max_segments}"
+        ));
+        segments = max_segments;
+    }
     let points = tessellate_centerline(center, radius, start_angle, sweep, segments);
     Some(points)
 }
@@ -146,14 +176,27 @@ fn emit_stroked_polyline(
     points: &[Point],
     aperture: &Aperture,
 ) -> Result<(), GeometryError> {
-    let mut iter = points.iter().copied();
-    let Some(mut previous) = iter.next() else {
+    let Some(last_segment) = points.len().checked_sub(2) else {
         return Ok(());
     };
+    let cap_style = builder.options().cap_style;
 
-    for current in iter {
-        draw_linear(builder, previous, current, aperture)?;
-        previous = current;
+    for (index, pair) in points.windows(2).enumerate() {
+        let (Some(&previous), Some(&current)) = (pair.first(), pair.get(1)) else {
+            continue;
+        };
+        // Interior joints stay round so consecutive segments meet without gaps.
+        let start_cap = if index == 0 {
+            cap_style
+        } else {
+            CapStyle::Round
+        };
+        let end_cap = if index == last_segment {
+            cap_style
+        } else {
+            CapStyle::Round
+        };
+        draw_linear_with_caps(builder, previous, current, aperture, (start_cap, end_cap))?;
     }
 
     Ok(())
@@ -210,14 +253,15 @@ fn max_segment_length_from_stroke(stroke_width: f64) -> f64 {
 }
 
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-fn segment_count_for_arc(arc_length: f64, max_segment_length: f64) -> u32 {
+fn segment_count_for_arc(arc_length: f64, max_segment_length: f64, min_segments: u32) -> u32 {
     let raw = (arc_length / max_segment_length).ceil();
     if !raw.is_finite() || raw <= 0.0 {
-        return MIN_ARC_SEGMENTS;
+        return min_segments;
     }
 
+    // Saturates at `u32::MAX`; the caller clamps to the configured cap.
     let estimated = raw as u32;
-    estimated.max(MIN_ARC_SEGMENTS)
+    estimated.max(min_segments)
 }
 
 fn tessellate_centerline(
@@ -263,14 +307,7 @@ fn resolve_polygon_width(
     builder: &mut GeometryBuilder,
     polygon: &Polygon,
 ) -> Result<Option<f64>, GeometryError> {
-    if polygon.vertices < 3 {
-        return Err(GeometryError::InvalidAperture(format!(
-            "polygon has {} vertices; expected at least 3",
-            polygon.vertices
-        )));
-    }
-
-    normalize_dimension(builder, polygon.diameter, "polygon diameter")
+    Ok(validate_polygon(builder, polygon)?.map(|(_, radius, _)| radius * 2.0))
 }
 
 fn normalize_rect_like_width(
@@ -311,7 +348,7 @@ fn normalize_dimension(
     }
 
     if normalized <= f64::EPSILON {
-        builder.warn(format!("{label} is zero; skipping arc tessellation"));
+        builder.warn_zero_dimension(label);
         return Ok(None);
     }
 
@@ -326,6 +363,7 @@ mod tests {
     use gerber_types::{Circle, Rectangular};
 
     use super::*;
+    use crate::geometry::options::TessellationOptions;
 
     const EPSILON: f64 = 1e-6;
 
@@ -544,4 +582,100 @@ mod tests {
         );
         assert!(multi_geom.vertex_count > 0, "expected geometry in G75 mode");
     }
+
+    #[test]
+    fn ut_arc_008_near_coincident_endpoints_sweep_full_circle() {
+        let angle = (0.01_f64).to_radians();
+        let mut builder = GeometryBuilder::new();
+        let to = Point {
+            x: angle.cos(),
+            y: angle.sin(),
+        };
+        assert!(distance(Point { x: 1.0, y: 0.0 }, to) < NEAR_COINCIDENT_CHORD);
+        let points = arc_centerline_points(
+            &mut builder,
+            Point { x: 1.0, y: 0.0 },
+            to,
+            Point { x: -1.0, y: 0.0 },
+            ArcDirection::CounterClockwise,
+            ArcQuadrantMode::MultiQuadrant,
+            0.05,
+        )
+        .unwrap_or_default();
+
+        let min_x = points
+            .iter()
+            .fold(f64::INFINITY, |min, point| min.min(point.x));
+        let min_y = points
+            .iter()
+            .fold(f64::INFINITY, |min, point| min.min(point.y));
+        assert!(min_x <= -0.99, "expected the sweep to reach the far side");
+        assert!(
+            min_y <= -0.99,
+            "expected the sweep to pass below the center"
+        );
+        assert_eq!(builder.warnings().len(), 1);
+    }
+
+    #[test]
+    fn ut_arc_009_huge_arc_is_clamped_to_segment_cap() {
+        let options = TessellationOptions::new().with_max_arc_segments(1024);
+        let mut builder = GeometryBuilder::with_options(options);
+        let radius = 10_000.0;
+        let points = arc_centerline_points(
+            &mut builder,
+            Point { x: radius, y: 0.0 },
+            Point { x: 0.0, y: radius },
+            Point { x: -radius, y: 0.0 },
+            ArcDirection::CounterClockwise,
+            ArcQuadrantMode::MultiQuadrant,
+            MIN_SEGMENT_LENGTH_FLOOR,
+        )
+        .unwrap_or_default();
+
+        assert_eq!(points.len(), 1025);
+        assert_eq!(builder.warnings().len(), 1);
+        assert!(builder
+            .warnings()
+            .first()
+            .is_some_and(|warning| warning.contains("clamping to 1024")));
+        let end = points.last().copied().unwrap_or(Point { x: 0.0, y: 0.0 });
+        assert_close(end.x, 0.0, 1e-6);
+        assert_close(end.y, radius, 1e-6);
+    }
+
+    #[test]
+    fn ut_arc_010_short_arc_on_large_radius_keeps_its_sweep() {
+        for (radius, degrees) in [(1000.0, 0.01_f64), (100.0, 0.05)] {
+            let angle = degrees.to_radians();
+            let from = Point { x: radius, y: 0.0 };
+            let to = Point {
+                x: radius * angle.cos(),
+                y: radius * angle.sin(),
+            };
+            assert!(distance(from, to) > NEAR_COINCIDENT_CHORD);
+            let mut builder = GeometryBuilder::new();
+            let points = arc_centerline_points(
+                &mut builder,
+                from,
+                to,
+                Point { x: -radius, y: 0.0 },
+                ArcDirection::CounterClockwise,
+                ArcQuadrantMode::MultiQuadrant,
+                0.25,
+            )
+            .unwrap_or_default();
+
+            assert!(!points.is_empty());
+            assert!(builder.warnings().is_empty(), "{:?}", builder.warnings());
+            for point in &points {
+                let point_angle = point.y.atan2(point.x);
+                assert!(
+                    (-1e-9..=angle + 1e-9).contains(&point_angle),
+                    "r={radius}: point at {} degrees outside a {degrees} degree sweep",
+                    point_angle.to_degrees()
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,209 @@
+//! Gerber X2 object attribute scoping.
+//!
+//! `%TO` adds an attribute to the current attribute dictionary and `%TD`
+//! removes one (or all, when no name is given). Every object created while an
+//! attribute is in the dictionary carries it; [`AttributeTracker`] records the
+//! triangle index ranges each dictionary state applies to.
+
+use std::collections::BTreeMap;
+
+use gerber_types::{ComponentCharacteristics, Net, ObjectAttribute};
+use serde::Serialize;
+
+use super::types::GeometryBuilder;
+
+/// Active object attributes keyed by name (e.g. `.N`, `.C`), in name order.
+pub type ObjectAttributes = BTreeMap<String, Vec<String>>;
+
+/// Index range of triangles created under a set of object attributes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttributeRange {
+    /// Start index (inclusive) in the triangle index buffer.
+    pub index_start: u32,
+    /// End index (exclusive) in the triangle index buffer.
+    pub index_end: u32,
+    /// Object attributes in effect for the range.
+    pub attributes: ObjectAttributes,
+}
+
+/// Returns the attribute name and field values of a `%TO` command.
+pub fn object_attribute_entry(attribute: &ObjectAttribute) -> (String, Vec<String>) {
+    match attribute {
+        ObjectAttribute::Net(Net::None) => (".N".to_string(), vec![String::new()]),
+        ObjectAttribute::Net(Net::NotConnected) => (".N".to_string(), vec!["N/C".to_string()]),
+        ObjectAttribute::Net(Net::Connected(names)) => (".N".to_string(), names.clone()),
+        ObjectAttribute::Pin(pin) => {
+            let mut values = vec![pin.refdes.clone(), pin.name.clone()];
+            values.extend(pin.function.clone());
+            (".P".to_string(), values)
+        }
+        ObjectAttribute::Component(refdes) => (".C".to_string(), vec![refdes.clone()]),
+        ObjectAttribute::ComponentCharacteristics(characteristics) => {
+            component_characteristics_entry(characteristics)
+        }
+        ObjectAttribute::UserDefined { name, values } => (name.clone(), values.clone()),
+    }
+}
+
+fn component_characteristics_entry(
+    characteristics: &ComponentCharacteristics,
+) -> (String, Vec<String>) {
+    let (name, values) = match characteristics {
+        ComponentCharacteristics::Rotation(v) => (".CRot", vec![v.to_string()]),
+        ComponentCharacteristics::Manufacturer(v) => (".CMfr", vec![v.clone()]),
+        ComponentCharacteristics::MPN(v) => (".CMPN", vec![v.clone()]),
+        ComponentCharacteristics::Value(v) => (".CVal", vec![v.clone()]),
+        ComponentCharacteristics::Mount(mount) => {
+            (".CMnt", vec![<&'static str>::from(mount).to_string()])
+        }
+        ComponentCharacteristics::Footprint(v) => (".CFtp", vec![v.clone()]),
+        ComponentCharacteristics::PackageName(v) => (".CPgN", vec![v.clone()]),
+        ComponentCharacteristics::PackageDescription(v) => (".CPgD", vec![v.clone()]),
+        ComponentCharacteristics::Height(v) => (".CHgt", vec![v.to_string()]),
+        ComponentCharacteristics::LibraryName(v) => (".CLbN", vec![v.clone()]),
+        ComponentCharacteristics::LibraryDescription(v) => (".CLbD", vec![v.clone()]),
+        ComponentCharacteristics::Supplier(parts) => (
+            ".CSup",
+            parts
+                .iter()
+                .flat_map(|part| {
+                    [
+                        part.supplier_name.clone(),
+                        part.supplier_part_reference.clone(),
+                    ]
+                })
+                .collect(),
+        ),
+    };
+    (name.to_string(), values)
+}
+
+/// Records which triangle index ranges were created under which attributes.
+#[derive(Debug, Default)]
+pub struct AttributeTracker {
+    /// Attributes currently in effect.
+    active: ObjectAttributes,
+    /// Index count when `active` took effect.
+    range_start: u32,
+    /// Accumulated non-empty attribute ranges.
+    ranges: Vec<AttributeRange>,
+}
+
+impl AttributeTracker {
+    /// Creates a tracker with an empty attribute dictionary.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            active: BTreeMap::new(),
+            range_start: 0,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Returns the attributes currently in effect.
+    #[must_use]
+    pub const fn current(&self) -> &ObjectAttributes {
+        &self.active
+    }
+
+    /// Replaces the active attributes, closing the range of triangles emitted
+    /// under the previous dictionary.
+    pub fn set_attributes(&mut self, attributes: ObjectAttributes, builder: &GeometryBuilder) {
+        if attributes == self.active {
+            return;
+        }
+        self.close_range(builder);
+        self.active = attributes;
+    }
+
+    /// Hands the ranges recorded so far to `builder`, the builder they index
+    /// into, before conversion switches to another builder at a step-repeat
+    /// boundary. The active range is closed at the builder's current end;
+    /// call [`Self::resume`] with the next builder to reopen it there.
+    pub fn suspend(&mut self, builder: &mut GeometryBuilder) {
+        self.close_range(builder);
+        for range in self.ranges.drain(..) {
+            builder.record_attribute_range(range);
+        }
+    }
+
+    /// Continues tracking against `builder` after [`Self::suspend`].
+    pub fn resume(&mut self, builder: &GeometryBuilder) {
+        self.range_start = builder.index_count();
+    }
+
+    /// Finishes tracking and returns the attribute ranges recorded since the
+    /// last [`Self::resume`], in index order.
+    #[must_use]
+    pub fn finish(mut self, builder: &GeometryBuilder) -> Vec<AttributeRange> {
+        self.close_range(builder);
+        self.ranges
+    }
+
+    fn close_range(&mut self, builder: &GeometryBuilder) {
+        let idx = builder.index_count();
+        if !self.active.is_empty() && idx > self.range_start {
+            self.ranges.push(AttributeRange {
+                index_start: self.range_start,
+                index_end: idx,
+                attributes: self.active.clone(),
+            });
+        }
+        self.range_start = idx;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gerber_types::Pin;
+
+    use super::*;
+
+    fn push_triangle(builder: &mut GeometryBuilder) {
+        let a = builder.push_vertex(0.0, 0.0);
+        let b = builder.push_vertex(1.0, 0.0);
+        let c = builder.push_vertex(0.0, 1.0);
+        builder.push_triangle(a, b, c);
+    }
+
+    #[test]
+    fn ut_att_001_entries_use_standard_names() {
+        let net = ObjectAttribute::Net(Net::Connected(vec!["GND".to_string()]));
+        assert_eq!(
+            object_attribute_entry(&net),
+            (".N".to_string(), vec!["GND".to_string()])
+        );
+        let pin = ObjectAttribute::Pin(Pin {
+            refdes: "U1".to_string(),
+            name: "3".to_string(),
+            function: None,
+        });
+        assert_eq!(
+            object_attribute_entry(&pin),
+            (".P".to_string(), vec!["U1".to_string(), "3".to_string()])
+        );
+    }
+
+    #[test]
+    fn ut_att_002_ranges_cover_only_attributed_triangles() {
+        let mut builder = GeometryBuilder::new();
+        let mut tracker = AttributeTracker::new();
+        push_triangle(&mut builder);
+
+        let attributes = ObjectAttributes::from([(".N".to_string(), vec!["GND".to_string()])]);
+        tracker.set_attributes(attributes.clone(), &builder);
+        push_triangle(&mut builder);
+        tracker.set_attributes(ObjectAttributes::new(), &builder);
+        push_triangle(&mut builder);
+
+        let ranges = tracker.finish(&builder);
+        assert_eq!(
+            ranges,
+            vec![AttributeRange {
+                index_start: 3,
+                index_end: 6,
+                attributes,
+            }]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Regenerating Gerber RS-274X source from converted geometry.
+//!
+//! The layer is merged into outline polygons by [`to_polygons`] and written
+//! back as G36/G37 regions, so the output carries the copper shape but none
+//! of the original apertures, strokes or attributes.
+
+use std::fmt::Write as _;
+
+use super::flatten::{to_polygons, Polygon};
+use super::types::{LayerGeometry, Point};
+
+/// Coordinate units per millimeter in the emitted `%FSLAX46Y46*%` format,
+/// matching the 1e-6 grid [`to_polygons`] snaps outlines to.
+const UNITS_PER_MM: f64 = 1e6;
+
+/// Writes `geometry` as a minimal RS-274X file in millimeters.
+///
+/// Every outline polygon becomes a dark region and each of its holes a
+/// clear (`%LPC*%`) region drawn right after it. Polygons are written from
+/// the largest outer area down, so an island inside another polygon's hole
+/// is drawn after that hole has been cleared.
+pub fn layer_to_gerber(geometry: &LayerGeometry) -> String {
+    let mut polygons = to_polygons(geometry);
+    polygons.sort_by(|a, b| ring_area(&b.outer).total_cmp(&ring_area(&a.outer)));
+
+    let mut out =
+        String::from("G04 Regenerated by gerberview*\n%FSLAX46Y46*%\n%MOMM*%\n%LPD*%\nG01*\n");
+    for polygon in &polygons {
+        write_polygon(&mut out, polygon);
+    }
+    out.push_str("M02*\n");
+    out
+}
+
+fn write_polygon(out: &mut String, polygon: &Polygon) {
+    write_region(out, &polygon.outer);
+    if polygon.holes.is_empty() {
+        return;
+    }
+    out.push_str("%LPC*%\n");
+    for hole in &polygon.holes {
+        write_region(out, hole);
+    }
+    out.push_str("%LPD*%\n");
+}
+
+/// Writes one closed G36/G37 contour through `ring`.
+fn write_region(out: &mut String, ring: &[Point]) {
+    let Some(&first) = ring.first() else {
+        return;
+    };
+    out.push_str("G36*\n");
+    write_point(out, first, "D02");
+    for &point in ring.iter().skip(1) {
+        write_point(out, point, "D01");
+    }
+    write_point(out, first, "D01");
+    out.push_str("G37*\n");
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn write_point(out: &mut String, point: Point, operation: &str) {
+    let x = (point.x * UNITS_PER_MM).round() as i64;
+    let y = (point.y * UNITS_PER_MM).round() as i64;
+    let _ = writeln!(out, "X{x}Y{y}{operation}*");
+}
+
+fn ring_area(ring: &[Point]) -> f64 {
+    let Some(&last) = ring.last() else {
+        return 0.0;
+    };
+    let mut previous = last;
+    let mut twice_area = 0.0;
+    for &point in ring {
+        twice_area += previous.x.mul_add(point.y, -(point.x * previous.y));
+        previous = point;
+    }
+    twice_area.abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryBuilder;
+
+    #[test]
+    fn square_is_written_as_one_dark_region() {
+        let mut builder = GeometryBuilder::new();
+        let corners = [(0.0, 0.0), (1.5, 0.0), (1.5, 1.0), (0.0, 1.0)]
+            .map(|(x, y)| builder.push_vertex(x, y));
+        let [a, b, c, d] = corners;
+        builder.push_quad(a, b, c, d);
+        let gerber = layer_to_gerber(&builder.build());
+
+        assert!(gerber.starts_with("G04 Regenerated by gerberview*\n%FSLAX46Y46*%\n%MOMM*%\n"));
+        assert_eq!(gerber.matches("G36*").count(), 1);
+        assert!(gerber.contains("X1500000Y0D01*\n"));
+        assert!(!gerber.contains("%LPC*%"));
+        assert!(gerber.ends_with("G37*\nM02*\n"));
+    }
+}
@@ -0,0 +1,769 @@
+//! Polarity flattening by boolean subtraction.
+//!
+//! Clear-polarity triangles only erase what was drawn before them, so
+//! triangles are replayed in index order: dark triangles are kept as convex
+//! pieces and every clear triangle is subtracted from the pieces emitted so
+//! far. The result is a dark-only mesh with no clear ranges, suitable for
+//! consumers that cannot honor clear ranges (software rasterizers, exports).
+//!
+//! [`to_polygons`] goes further for CAM round-tripping: it also subtracts
+//! earlier pieces from each dark triangle so the pieces tile the copper
+//! without overlap, then traces the edges not shared by two pieces into
+//! outline polygons with holes. The same disjoint pieces give the net copper
+//! area reported by [`copper_area`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::{index_in_ranges, GeometryBuilder, LayerGeometry, Point};
+
+/// Pieces with a smaller area are discarded as numerical slivers.
+const AREA_EPSILON: f64 = 1e-12;
+
+/// Grid spacing, in layer units, that outline vertices are snapped to.
+const OUTLINE_QUANTUM: f64 = 1e-6;
+
+/// Distance, in grid units, within which a vertex counts as lying on an edge.
+const OUTLINE_TOLERANCE: f64 = 2.0;
+
+/// Filled outline with optional holes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    /// Counter-clockwise outer boundary.
+    pub outer: Vec<Point>,
+    /// Clockwise hole boundaries inside `outer`.
+    pub holes: Vec<Vec<Point>>,
+}
+
+/// Returns a copy of `geometry` with clear-polarity triangles subtracted from
+/// the dark geometry that precedes them.
+///
+/// Dark geometry drawn after a clear range is not affected, matching the
+/// Gerber rule that polarity applies to previously drawn objects. This also
+/// covers aperture macros, whose exposure-off primitives cut the earlier
+/// primitives of the same flash. Warnings and `command_count` are carried
+/// over; bounds are recomputed from the remaining geometry.
+pub fn flatten_polarity(geometry: &LayerGeometry) -> LayerGeometry {
+    let pieces = replay_pieces(geometry, false);
+
+    let mut builder = GeometryBuilder::new();
+    for piece in &pieces {
+        let outline = piece.stored_outline();
+        if outline.len() < 3 {
+            continue;
+        }
+        let rim: Vec<u32> = outline
+            .iter()
+            .map(|&(x, y)| builder.push_vertex(f64::from(x), f64::from(y)))
+            .collect();
+        if let Some((&first, rest)) = rim.split_first() {
+            builder.push_fan(first, rest);
+        }
+    }
+
+    let mut flattened = builder.build();
+    flattened.command_count = geometry.command_count;
+    flattened.warnings.clone_from(&geometry.warnings);
+    flattened
+        .warning_commands
+        .clone_from(&geometry.warning_commands);
+    flattened
+        .warning_categories
+        .clone_from(&geometry.warning_categories);
+    flattened
+}
+
+/// Merges the dark geometry of `geometry` into outline polygons with holes.
+///
+/// Overlapping dark triangles are unioned and clear-polarity triangles are
+/// subtracted with the same ordering rules as [`flatten_polarity`].
+/// Vertices are snapped to a 1e-6 grid and collinear outline points are
+/// removed, so a rectangle flash yields a single four-point polygon.
+pub fn to_polygons(geometry: &LayerGeometry) -> Vec<Polygon> {
+    let pieces = replay_pieces(geometry, true);
+    let edges = boundary_edges(&pieces);
+    let mut outers: Vec<(f64, Vec<GridPoint>)> = Vec::new();
+    let mut holes: Vec<Vec<GridPoint>> = Vec::new();
+    for ring in trace_rings(edges) {
+        let ring = simplify_ring(ring);
+        if ring.len() < 3 {
+            continue;
+        }
+        let area = grid_area(&ring);
+        if area > 0.0 {
+            outers.push((area, ring));
+        } else if area < 0.0 {
+            holes.push(ring);
+        }
+    }
+
+    let mut polygons: Vec<Polygon> = outers
+        .iter()
+        .map(|(_, ring)| Polygon {
+            outer: ring.iter().map(|&p| grid_to_point(p)).collect(),
+            holes: Vec::new(),
+        })
+        .collect();
+    for hole in holes {
+        let Some(&probe) = hole.first() else {
+            continue;
+        };
+        let container = outers
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, ring))| ring_contains(ring, probe))
+            .min_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+            .map(|(i, _)| i);
+        if let Some(polygon) = container.and_then(|i| polygons.get_mut(i)) {
+            polygon
+                .holes
+                .push(hole.iter().map(|&p| grid_to_point(p)).collect());
+        }
+    }
+    polygons
+}
+
+/// Returns the net dark area of `geometry`: the union of dark triangles minus
+/// what clear-polarity triangles erase, in squared layer units.
+///
+/// Overlapping dark geometry is counted once, and clear geometry only
+/// reduces the area of dark geometry drawn before it, as for
+/// [`flatten_polarity`].
+pub fn copper_area(geometry: &LayerGeometry) -> f64 {
+    replay_pieces(geometry, true)
+        .iter()
+        .map(|piece| signed_area(&piece.points))
+        .sum()
+}
+
+/// Replays triangles in index order into convex dark pieces.
+///
+/// Clear triangles are subtracted from the pieces emitted so far. With
+/// `disjoint`, each dark triangle also has the existing pieces subtracted
+/// from it, so the result tiles the dark area without overlap.
+fn replay_pieces(geometry: &LayerGeometry, disjoint: bool) -> Vec<Piece> {
+    let mut clear_ranges = geometry.clear_ranges.clone();
+    clear_ranges.sort_unstable();
+
+    let mut index = PieceIndex::new(geometry);
+    for (triangle_index, triangle) in geometry.indices.chunks_exact(3).enumerate() {
+        let Some(corners) = triangle_corners(geometry, triangle) else {
+            continue;
+        };
+        let Some(piece) = Piece::new(corners.to_vec()) else {
+            continue;
+        };
+
+        let nearby = index.overlapping(&piece);
+        let offset = triangle_index.saturating_mul(3);
+        if index_in_ranges(&clear_ranges, offset) {
+            for id in nearby {
+                if let Some(dark) = index.remove(id) {
+                    for fragment in dark.subtract(&piece) {
+                        index.insert(fragment);
+                    }
+                }
+            }
+        } else if disjoint {
+            let mut fragments = vec![piece];
+            for id in nearby {
+                let Some(existing) = index.get(id) else {
+                    continue;
+                };
+                fragments = fragments
+                    .into_iter()
+                    .flat_map(|fragment| fragment.subtract(existing))
+                    .collect();
+                if fragments.is_empty() {
+                    break;
+                }
+            }
+            for fragment in fragments {
+                index.insert(fragment);
+            }
+        } else {
+            index.insert(piece);
+        }
+    }
+    index.into_pieces()
+}
+
+/// Pieces spanning more grid cells than this are kept in an unbucketed list.
+const MAX_CELLS_PER_PIECE: i64 = 256;
+
+/// Uniform grid over the layer bounds that finds pieces whose bounding boxes
+/// may overlap a query piece, so replay cost grows with local density rather
+/// than with the whole layer.
+struct PieceIndex {
+    pieces: Vec<Option<Piece>>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// Pieces too large to bucket; checked on every query.
+    large: Vec<usize>,
+    origin: Point,
+    cell_size: f64,
+}
+
+impl PieceIndex {
+    /// Sizes cells so each holds about one triangle of `geometry` on average.
+    #[allow(clippy::cast_precision_loss)]
+    fn new(geometry: &LayerGeometry) -> Self {
+        let bounds = geometry.bounds;
+        let extent = (bounds.max_x - bounds.min_x).max(bounds.max_y - bounds.min_y);
+        let triangles = (geometry.indices.len() / 3).max(1) as f64;
+        let cell_size = extent / triangles.sqrt();
+        let valid = cell_size.is_finite() && cell_size > 0.0;
+        Self {
+            pieces: Vec::new(),
+            cells: HashMap::new(),
+            large: Vec::new(),
+            origin: if valid {
+                Point {
+                    x: bounds.min_x,
+                    y: bounds.min_y,
+                }
+            } else {
+                Point { x: 0.0, y: 0.0 }
+            },
+            cell_size: if valid { cell_size } else { 1.0 },
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell_span(&self, piece: &Piece) -> ((i64, i64), (i64, i64)) {
+        let cell = |p: Point| {
+            (
+                ((p.x - self.origin.x) / self.cell_size).floor() as i64,
+                ((p.y - self.origin.y) / self.cell_size).floor() as i64,
+            )
+        };
+        (cell(piece.min), cell(piece.max))
+    }
+
+    const fn is_large(((x0, y0), (x1, y1)): ((i64, i64), (i64, i64))) -> bool {
+        (x1 - x0 + 1).saturating_mul(y1 - y0 + 1) > MAX_CELLS_PER_PIECE
+    }
+
+    fn insert(&mut self, piece: Piece) {
+        let id = self.pieces.len();
+        let span = self.cell_span(&piece);
+        if Self::is_large(span) {
+            self.large.push(id);
+        } else {
+            let ((x0, y0), (x1, y1)) = span;
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    self.cells.entry((x, y)).or_default().push(id);
+                }
+            }
+        }
+        self.pieces.push(Some(piece));
+    }
+
+    fn get(&self, id: usize) -> Option<&Piece> {
+        self.pieces.get(id).and_then(Option::as_ref)
+    }
+
+    fn remove(&mut self, id: usize) -> Option<Piece> {
+        self.pieces.get_mut(id).and_then(Option::take)
+    }
+
+    /// Returns ids, in insertion order, of live pieces whose bounding boxes
+    /// overlap `piece`.
+    fn overlapping(&self, piece: &Piece) -> Vec<usize> {
+        let span = self.cell_span(piece);
+        let mut ids: Vec<usize> = if Self::is_large(span) {
+            (0..self.pieces.len()).collect()
+        } else {
+            let ((x0, y0), (x1, y1)) = span;
+            let mut ids = self.large.clone();
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    if let Some(bucket) = self.cells.get(&(x, y)) {
+                        ids.extend(bucket);
+                    }
+                }
+            }
+            ids.sort_unstable();
+            ids.dedup();
+            ids
+        };
+        ids.retain(|&id| {
+            self.get(id)
+                .is_some_and(|other| other.overlaps_bounds(piece))
+        });
+        ids
+    }
+
+    fn into_pieces(self) -> Vec<Piece> {
+        self.pieces.into_iter().flatten().collect()
+    }
+}
+
+/// Vertex snapped to the outline grid.
+type GridPoint = (i64, i64);
+
+#[allow(clippy::cast_possible_truncation)]
+fn snap(p: Point) -> GridPoint {
+    (
+        (p.x / OUTLINE_QUANTUM).round() as i64,
+        (p.y / OUTLINE_QUANTUM).round() as i64,
+    )
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn grid_to_point((x, y): GridPoint) -> Point {
+    Point {
+        x: x as f64 * OUTLINE_QUANTUM,
+        y: y as f64 * OUTLINE_QUANTUM,
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn grid_cross(a: GridPoint, b: GridPoint, p: GridPoint) -> f64 {
+    let (abx, aby) = ((b.0 - a.0) as f64, (b.1 - a.1) as f64);
+    let (apx, apy) = ((p.0 - a.0) as f64, (p.1 - a.1) as f64);
+    abx.mul_add(apy, -(aby * apx))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn grid_length(a: GridPoint, b: GridPoint) -> f64 {
+    ((b.0 - a.0) as f64).hypot((b.1 - a.1) as f64)
+}
+
+/// Returns the directed piece edges not cancelled by an opposite edge of a
+/// neighboring piece. Edges are first split at every piece vertex lying on
+/// them so T-junctions between pieces cancel too.
+fn boundary_edges(pieces: &[Piece]) -> Vec<(GridPoint, GridPoint)> {
+    let rings: Vec<Vec<GridPoint>> = pieces
+        .iter()
+        .map(|piece| {
+            let mut ring: Vec<GridPoint> = Vec::with_capacity(piece.points.len());
+            for &p in &piece.points {
+                let g = snap(p);
+                if ring.last() != Some(&g) {
+                    ring.push(g);
+                }
+            }
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            ring
+        })
+        .filter(|ring| ring.len() >= 3)
+        .collect();
+
+    let mut vertices: Vec<GridPoint> = rings.iter().flatten().copied().collect();
+    vertices.sort_unstable();
+    vertices.dedup();
+
+    let mut counts: BTreeMap<(GridPoint, GridPoint), i32> = BTreeMap::new();
+    for ring in &rings {
+        for (&a, &b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+            let mut stops = points_on_edge(&vertices, a, b);
+            stops.push(b);
+            let mut from = a;
+            for to in stops {
+                if to != from {
+                    *counts.entry((from, to)).or_insert(0) += 1;
+                    *counts.entry((to, from)).or_insert(0) -= 1;
+                }
+                from = to;
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|&(_, count)| count > 0)
+        .map(|(edge, _)| edge)
+        .collect()
+}
+
+/// Returns the vertices strictly inside segment `a -> b`, ordered from `a`.
+fn points_on_edge(vertices: &[GridPoint], a: GridPoint, b: GridPoint) -> Vec<GridPoint> {
+    let length = grid_length(a, b);
+    if length <= 0.0 {
+        return Vec::new();
+    }
+    let (min_x, max_x) = (a.0.min(b.0), a.0.max(b.0));
+    let (min_y, max_y) = (a.1.min(b.1), a.1.max(b.1));
+    let start = vertices.partition_point(|v| v.0 < min_x);
+    let mut on_edge: Vec<(f64, GridPoint)> = vertices
+        .get(start..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|v| v.0 <= max_x)
+        .filter(|&&v| v != a && v != b && (min_y..=max_y).contains(&v.1))
+        .filter(|&&v| grid_cross(a, b, v).abs() <= OUTLINE_TOLERANCE * length)
+        .map(|&v| (grid_length(a, v), v))
+        .collect();
+    on_edge.sort_by(|(da, _), (db, _)| da.total_cmp(db));
+    on_edge.into_iter().map(|(_, v)| v).collect()
+}
+
+/// Chains directed boundary edges into closed rings.
+fn trace_rings(edges: Vec<(GridPoint, GridPoint)>) -> Vec<Vec<GridPoint>> {
+    let mut outgoing: HashMap<GridPoint, Vec<GridPoint>> = HashMap::new();
+    for &(a, b) in &edges {
+        outgoing.entry(a).or_default().push(b);
+    }
+
+    let mut rings = Vec::new();
+    for (start, _) in edges {
+        let mut ring = vec![start];
+        let mut current = start;
+        while let Some(next) = outgoing.get_mut(&current).and_then(Vec::pop) {
+            if next == start {
+                break;
+            }
+            ring.push(next);
+            current = next;
+        }
+        if ring.len() >= 3 {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+/// Removes ring points that lie on the line through their neighbors.
+fn simplify_ring(mut ring: Vec<GridPoint>) -> Vec<GridPoint> {
+    let mut changed = true;
+    while changed && ring.len() >= 3 {
+        changed = false;
+        let count = ring.len();
+        let keep: Vec<bool> = (0..count)
+            .map(|i| {
+                let prev = ring.get((i + count - 1) % count);
+                let next = ring.get((i + 1) % count);
+                match (prev, ring.get(i), next) {
+                    (Some(&p), Some(&c), Some(&n)) => {
+                        grid_cross(p, n, c).abs() > OUTLINE_TOLERANCE * grid_length(p, n)
+                    }
+                    _ => true,
+                }
+            })
+            .collect();
+        if let Some(drop) = keep.iter().position(|k| !k) {
+            ring.remove(drop);
+            changed = true;
+        }
+    }
+    ring
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn grid_area(ring: &[GridPoint]) -> f64 {
+    let pairs = ring.iter().zip(ring.iter().cycle().skip(1));
+    pairs.fold(0.0, |acc, (a, b)| {
+        (a.0 as f64).mul_add(b.1 as f64, -(b.0 as f64) * (a.1 as f64)) + acc
+    }) / 2.0
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn ring_contains(ring: &[GridPoint], p: GridPoint) -> bool {
+    let (px, py) = (p.0 as f64, p.1 as f64);
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let (ax, ay, bx, by) = (a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+        if (ay > py) != (by > py) && px < (bx - ax).mul_add((py - ay) / (by - ay), ax) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Convex, counter-clockwise polygon with a cached bounding box.
+#[derive(Debug, Clone)]
+struct Piece {
+    points: Vec<Point>,
+    min: Point,
+    max: Point,
+}
+
+impl Piece {
+    /// Builds a piece from convex points, normalizing the winding to
+    /// counter-clockwise. Returns `None` for degenerate polygons.
+    fn new(mut points: Vec<Point>) -> Option<Self> {
+        // Clipping repeats a vertex that lies on the clip line; a zero-length
+        // edge would later classify everything as outside it.
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        let area = signed_area(&points);
+        if area.abs() <= AREA_EPSILON {
+            return None;
+        }
+        if area < 0.0 {
+            points.reverse();
+        }
+        let first = *points.first()?;
+        let (min, max) = points.iter().fold((first, first), |(lo, hi), p| {
+            (
+                Point {
+                    x: lo.x.min(p.x),
+                    y: lo.y.min(p.y),
+                },
+                Point {
+                    x: hi.x.max(p.x),
+                    y: hi.y.max(p.y),
+                },
+            )
+        });
+        Some(Self { points, min, max })
+    }
+
+    /// Returns the outline rounded to stored `f32` precision, with points
+    /// that collapse onto their predecessor removed.
+    #[allow(clippy::cast_possible_truncation)]
+    fn stored_outline(&self) -> Vec<(f32, f32)> {
+        let mut outline: Vec<(f32, f32)> = Vec::with_capacity(self.points.len());
+        for p in &self.points {
+            let stored = (p.x as f32, p.y as f32);
+            if outline.last() != Some(&stored) {
+                outline.push(stored);
+            }
+        }
+        if outline.len() > 1 && outline.first() == outline.last() {
+            outline.pop();
+        }
+        outline
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(&start, &end)| (start, end))
+    }
+
+    fn overlaps_bounds(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+
+    /// Returns `self` minus `clip` as disjoint convex pieces.
+    ///
+    /// Each clip edge splits off the part of the remainder lying outside it;
+    /// whatever survives every edge is inside `clip` and is dropped. Pieces
+    /// that only touch `clip` are returned whole rather than split along its
+    /// edges, which keeps fans of adjacent triangles from fragmenting.
+    fn subtract(self, clip: &Self) -> Vec<Self> {
+        if !self.overlaps_bounds(clip) {
+            return vec![self];
+        }
+        let overlap = clip
+            .edges()
+            .try_fold(self.points.clone(), |inside, (start, end)| {
+                let inside = clip_half_plane(&inside, start, end);
+                (inside.len() >= 3).then_some(inside)
+            });
+        if overlap.map_or(true, |inside| signed_area(&inside) <= AREA_EPSILON) {
+            return vec![self];
+        }
+
+        let mut outside = Vec::new();
+        let mut remaining = self.points;
+        for (start, end) in clip.edges() {
+            if let Some(piece) = Self::new(clip_half_plane(&remaining, end, start)) {
+                outside.push(piece);
+            }
+            remaining = clip_half_plane(&remaining, start, end);
+            if remaining.len() < 3 {
+                break;
+            }
+        }
+        outside
+    }
+}
+
+/// Sutherland–Hodgman step keeping the part of `polygon` left of `start -> end`.
+fn clip_half_plane(polygon: &[Point], start: Point, end: Point) -> Vec<Point> {
+    let side = |p: Point| cross(start, end, p);
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    let pairs = polygon.iter().zip(polygon.iter().cycle().skip(1));
+    for (&current, &next) in pairs {
+        let (current_side, next_side) = (side(current), side(next));
+        if current_side >= 0.0 {
+            clipped.push(current);
+        }
+        if (current_side >= 0.0) != (next_side >= 0.0) {
+            let t = current_side / (current_side - next_side);
+            clipped.push(Point {
+                x: (next.x - current.x).mul_add(t, current.x),
+                y: (next.y - current.y).mul_add(t, current.y),
+            });
+        }
+    }
+    clipped
+}
+
+fn cross(a: Point, b: Point, p: Point) -> f64 {
+    (b.x - a.x).mul_add(p.y - a.y, -((b.y - a.y) * (p.x - a.x)))
+}
+
+fn signed_area(points: &[Point]) -> f64 {
+    let pairs = points.iter().zip(points.iter().cycle().skip(1));
+    pairs.fold(0.0, |acc, (a, b)| b.x.mul_add(-a.y, a.x.mul_add(b.y, acc))) / 2.0
+}
+
+fn triangle_corners(geometry: &LayerGeometry, triangle: &[u32]) -> Option<[Point; 3]> {
+    let mut corners = [Point { x: 0.0, y: 0.0 }; 3];
+    for (corner, index) in corners.iter_mut().zip(triangle) {
+        let base = (*index as usize).checked_mul(2)?;
+        let x = *geometry.positions.get(base)?;
+        let y = *geometry.positions.get(base + 1)?;
+        *corner = Point {
+            x: f64::from(x),
+            y: f64::from(y),
+        };
+    }
+    Some(corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use gerber_types::{ApertureMacro, CirclePrimitive, MacroBoolean, MacroDecimal};
+
+    use super::*;
+    use crate::geometry::evaluate_macro;
+
+    fn circle(exposure: bool, diameter: f64) -> CirclePrimitive {
+        CirclePrimitive {
+            exposure: MacroBoolean::Value(exposure),
+            diameter: MacroDecimal::Value(diameter),
+            center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+            angle: None,
+        }
+    }
+
+    fn covers(geometry: &LayerGeometry, point: Point) -> bool {
+        geometry.indices.chunks_exact(3).any(|triangle| {
+            triangle_corners(geometry, triangle).is_some_and(|[a, b, c]| {
+                let sides = [cross(a, b, point), cross(b, c, point), cross(c, a, point)];
+                sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0)
+            })
+        })
+    }
+
+    fn total_area(geometry: &LayerGeometry) -> f64 {
+        geometry
+            .indices
+            .chunks_exact(3)
+            .filter_map(|triangle| triangle_corners(geometry, triangle))
+            .map(|corners| signed_area(&corners).abs())
+            .sum()
+    }
+
+    #[test]
+    fn macro_clear_circle_punches_ring() {
+        let washer = ApertureMacro::new("WASHER")
+            .add_content(circle(true, 2.0))
+            .add_content(circle(false, 1.0));
+        let mut builder = GeometryBuilder::new();
+        let origin = Point { x: 0.0, y: 0.0 };
+        assert!(evaluate_macro(&mut builder, &washer, &[], origin, 1.0).is_ok());
+        let geometry = builder.build();
+
+        let flattened = flatten_polarity(&geometry);
+        assert!(flattened.clear_ranges.is_empty());
+        let hole = Point { x: 0.2, y: 0.1 };
+        assert!(!covers(&flattened, hole), "center must be punched out");
+        assert!(covers(&flattened, Point { x: 0.75, y: 0.1 }));
+        assert!(covers(&flattened, Point { x: 0.1, y: -0.75 }));
+        assert!(!covers(&flattened, Point { x: 1.1, y: 0.1 }));
+
+        let expected_ring = std::f64::consts::PI * (1.0 - 0.25);
+        let ring = total_area(&flattened);
+        assert!(
+            (ring - expected_ring).abs() < expected_ring * 0.02,
+            "ring area {ring} should approximate {expected_ring}"
+        );
+    }
+
+    #[test]
+    fn rectangle_flash_yields_single_quad_outline() {
+        let mut builder = GeometryBuilder::new();
+        let a = builder.push_vertex(0.0, 0.0);
+        let b = builder.push_vertex(2.0, 0.0);
+        let c = builder.push_vertex(2.0, 1.0);
+        let d = builder.push_vertex(0.0, 1.0);
+        builder.push_quad(a, b, c, d);
+
+        let polygons = to_polygons(&builder.build());
+        assert_eq!(polygons.len(), 1);
+        let Some(polygon) = polygons.first() else {
+            return;
+        };
+        assert_eq!(polygon.outer.len(), 4, "{:?}", polygon.outer);
+        assert!(polygon.holes.is_empty());
+    }
+
+    #[test]
+    fn overlapping_squares_merge_into_one_outline() {
+        let mut builder = GeometryBuilder::new();
+        for (x, y) in [(0.0, 0.0), (1.0, 1.0)] {
+            let a = builder.push_vertex(x, y);
+            let b = builder.push_vertex(x + 2.0, y);
+            let c = builder.push_vertex(x + 2.0, y + 2.0);
+            let d = builder.push_vertex(x, y + 2.0);
+            builder.push_quad(a, b, c, d);
+        }
+
+        let polygons = to_polygons(&builder.build());
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons.first().map(|p| p.outer.len()), Some(8));
+    }
+
+    #[test]
+    fn copper_area_counts_overlap_once() {
+        let mut builder = GeometryBuilder::new();
+        for (x, y) in [(0.0, 0.0), (1.0, 1.0)] {
+            let a = builder.push_vertex(x, y);
+            let b = builder.push_vertex(x + 2.0, y);
+            let c = builder.push_vertex(x + 2.0, y + 2.0);
+            let d = builder.push_vertex(x, y + 2.0);
+            builder.push_quad(a, b, c, d);
+        }
+
+        let area = copper_area(&builder.build());
+        assert!((area - 7.0).abs() < 1e-9, "area was {area}");
+    }
+
+    #[test]
+    fn clear_center_becomes_hole() {
+        let mut builder = GeometryBuilder::new();
+        let a = builder.push_vertex(-2.0, -2.0);
+        let b = builder.push_vertex(2.0, -2.0);
+        let c = builder.push_vertex(2.0, 2.0);
+        let d = builder.push_vertex(-2.0, 2.0);
+        builder.push_quad(a, b, c, d);
+        let clear_start = builder.index_count();
+        builder.push_ngon(0.0, 0.0, 1.0, 16);
+        builder.record_clear_range(clear_start, builder.index_count());
+
+        let polygons = to_polygons(&builder.build());
+        assert_eq!(polygons.len(), 1);
+        let Some(polygon) = polygons.first() else {
+            return;
+        };
+        assert_eq!(polygon.outer.len(), 4, "{:?}", polygon.outer);
+        assert_eq!(polygon.holes.len(), 1);
+        assert_eq!(polygon.holes.first().map(Vec::len), Some(16));
+    }
+
+    #[test]
+    fn clear_does_not_affect_later_dark_geometry() {
+        let mut builder = GeometryBuilder::new();
+        let clear_start = builder.index_count();
+        builder.push_ngon(0.0, 0.0, 1.0, 16);
+        builder.record_clear_range(clear_start, builder.index_count());
+        builder.push_ngon(0.0, 0.0, 0.5, 16);
+        let geometry = builder.build();
+
+        let flattened = flatten_polarity(&geometry);
+        assert!(covers(&flattened, Point { x: 0.2, y: 0.1 }));
+        assert!(!covers(&flattened, Point { x: 0.75, y: 0.1 }));
+    }
+}
@@ -0,0 +1,69 @@
+//! Stable hashing of converted geometry for caching and regression checks.
+
+use super::types::LayerGeometry;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Returns a 64-bit FNV-1a hash of the renderable content of `geometry`.
+///
+/// Positions (by `f32` bit pattern), indices and clear ranges are hashed in
+/// buffer order, each preceded by its length, so the value is the same on
+/// every platform and run. Metadata such as warnings and bounds is not
+/// hashed; geometry that renders identically hashes identically.
+pub fn geometry_hash(geometry: &LayerGeometry) -> u64 {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    hasher.write_len(geometry.positions.len());
+    for position in &geometry.positions {
+        hasher.write(&position.to_bits().to_le_bytes());
+    }
+    hasher.write_len(geometry.indices.len());
+    for index in &geometry.indices {
+        hasher.write(&index.to_le_bytes());
+    }
+    hasher.write_len(geometry.clear_ranges.len());
+    for (start, end) in &geometry.clear_ranges {
+        hasher.write(&start.to_le_bytes());
+        hasher.write(&end.to_le_bytes());
+    }
+    hasher.0
+}
+
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryBuilder;
+
+    #[test]
+    fn empty_geometry_hash_is_fixed() {
+        let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+        for _ in 0..3 {
+            hasher.write_len(0);
+        }
+        assert_eq!(geometry_hash(&GeometryBuilder::new().build()), hasher.0);
+    }
+
+    #[test]
+    fn clear_ranges_change_the_hash() {
+        let mut builder = GeometryBuilder::new();
+        builder.push_ngon(0.0, 0.0, 1.0, 8);
+        let dark = builder.build();
+        let mut clear = dark.clone();
+        clear.clear_ranges.push((0, 3));
+        assert_ne!(geometry_hash(&dark), geometry_hash(&clear));
+    }
+}
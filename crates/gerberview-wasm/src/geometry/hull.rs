@@ -0,0 +1,137 @@
+//! Convex hull of layer geometry.
+//!
+//! A tighter footprint than [`LayerGeometry::bounds`] for quick collision
+//! and placement checks. Hulls are computed with Andrew's monotone chain
+//! over the vertex buffer.
+
+use super::types::{index_in_ranges, LayerGeometry, Point};
+
+/// Returns the convex hull of every vertex in `geometry`.
+///
+/// Points are counter-clockwise, start at the lowest-x (then lowest-y)
+/// vertex, and collinear points along hull edges are dropped. Fewer than
+/// three points are returned when the geometry is empty or degenerate.
+#[must_use]
+pub fn convex_hull(geometry: &LayerGeometry) -> Vec<Point> {
+    let points = geometry
+        .positions
+        .chunks_exact(2)
+        .map(vertex_point)
+        .collect();
+    monotone_chain(points)
+}
+
+/// Returns the convex hull of the dark geometry in `geometry`.
+///
+/// Like [`convex_hull`], but only vertices of triangles outside the clear
+/// ranges are considered, so clear-polarity geometry does not widen the
+/// hull.
+#[must_use]
+pub fn dark_convex_hull(geometry: &LayerGeometry) -> Vec<Point> {
+    let mut clear_ranges = geometry.clear_ranges.clone();
+    clear_ranges.sort_unstable();
+
+    let points = geometry
+        .indices
+        .chunks_exact(3)
+        .enumerate()
+        .filter(|(triangle_index, _)| {
+            !index_in_ranges(&clear_ranges, triangle_index.saturating_mul(3))
+        })
+        .flat_map(|(_, triangle)| triangle.iter())
+        .filter_map(|&index| {
+            let base = (index as usize).checked_mul(2)?;
+            geometry.positions.get(base..base + 2).map(vertex_point)
+        })
+        .collect();
+    monotone_chain(points)
+}
+
+fn vertex_point(xy: &[f32]) -> Point {
+    let (x, y) = match xy {
+        [x, y, ..] => (*x, *y),
+        _ => (0.0, 0.0),
+    };
+    Point {
+        x: f64::from(x),
+        y: f64::from(y),
+    }
+}
+
+fn monotone_chain(mut points: Vec<Point>) -> Vec<Point> {
+    points.sort_unstable_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut lower: Vec<Point> = Vec::new();
+    for &p in &points {
+        push_turning_left(&mut lower, p);
+    }
+    let mut upper: Vec<Point> = Vec::new();
+    for &p in points.iter().rev() {
+        push_turning_left(&mut upper, p);
+    }
+
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+/// Pushes `p`, first popping points that would not make a strict left turn.
+fn push_turning_left(chain: &mut Vec<Point>, p: Point) {
+    while let [.., a, b] = chain.as_slice() {
+        if cross(*a, *b, p) > 0.0 {
+            break;
+        }
+        chain.pop();
+    }
+    chain.push(p);
+}
+
+fn cross(a: Point, b: Point, p: Point) -> f64 {
+    (b.x - a.x).mul_add(p.y - a.y, -((b.y - a.y) * (p.x - a.x)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryBuilder;
+
+    #[test]
+    fn square_with_interior_and_edge_points_keeps_corners() {
+        let mut builder = GeometryBuilder::new();
+        for (x, y) in [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)] {
+            builder.push_vertex(x, y);
+        }
+        builder.push_vertex(1.0, 1.0);
+        builder.push_vertex(1.0, 0.0);
+
+        let hull = convex_hull(&builder.build());
+        assert_eq!(
+            hull,
+            vec![
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 2.0, y: 0.0 },
+                Point { x: 2.0, y: 2.0 },
+                Point { x: 0.0, y: 2.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn dark_hull_ignores_clear_triangles() {
+        let mut builder = GeometryBuilder::new();
+        builder.push_ngon(0.0, 0.0, 1.0, 4);
+        let clear_start = builder.index_count();
+        builder.push_ngon(0.0, 0.0, 3.0, 4);
+        builder.record_clear_range(clear_start, builder.index_count());
+        let geometry = builder.build();
+
+        let widest = |hull: &[Point]| hull.iter().map(|p| p.x.abs()).fold(0.0, f64::max);
+        assert!((widest(&convex_hull(&geometry)) - 3.0).abs() < 1e-6);
+        assert!((widest(&dark_convex_hull(&geometry)) - 1.0).abs() < 1e-6);
+    }
+}
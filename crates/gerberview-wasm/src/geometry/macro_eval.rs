@@ -2,8 +2,16 @@
 //!
 //! Evaluates aperture macro primitives (`Circle`, `VectorLine`, `CenterLine`,
 //! `Outline`, `Polygon`) with exposure flags and arithmetic expression evaluation.
+//!
+//! Primitives are tessellated independently and are not unioned, so shapes
+//! built from overlapping primitives (such as the `RoundRect` pad macro: a body
+//! outline, four corner circles, and four edge lines) produce triangles that
+//! cover some areas more than once. Rendering is unaffected; area metrics
+//! must be taken from [`to_polygons`](super::to_polygons), which merges the
+//! overlap, rather than by summing triangle areas.
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use gerber_types::{
     ApertureMacro, CenterLinePrimitive, CirclePrimitive, MacroBoolean, MacroContent, MacroDecimal,
@@ -22,6 +30,11 @@ const BC_GBR_025_WARN: &str = "BC-GBR-025: expression nesting >10 levels";
 const MAX_NEST_WARN: u32 = 10;
 const MAX_NEST_ERROR: u32 = 20;
 
+/// Upper bound on the vertex count of a polygon primitive. The specification
+/// allows at most 12; anything far beyond is a corrupt file and would
+/// otherwise push billions of vertices.
+const MAX_POLYGON_VERTICES: u32 = 1024;
+
 /// Resolves aperture macro parameters from `MacroDecimal` to `f64`.
 ///
 /// Parameters are resolved in order; each resolved value populates the
@@ -39,13 +52,13 @@ pub fn resolve_macro_params(
     let Some(params) = params else {
         return Ok(Vec::new());
     };
-    let mut vars: HashMap<u32, f64> = HashMap::new();
+    let mut vars = MacroVars::default();
     let mut resolved = Vec::with_capacity(params.len());
     for (i, p) in params.iter().enumerate() {
         let v = resolve_decimal(builder, p, &vars)?;
         let key = u32::try_from(i).map_or(0, |n| n + 1);
         if key > 0 {
-            vars.insert(key, v);
+            vars.values.insert(key, v);
         }
         resolved.push(v);
     }
@@ -56,41 +69,55 @@ pub fn resolve_macro_params(
 ///
 /// Builds variable context from `params` ($1 = params[0], etc.), processes
 /// variable definitions and primitives, and adds geometry to the builder.
+/// Primitive dimensions and centers are in file units and are multiplied by
+/// `scale` (e.g. 25.4 for inch files) to match the millimeter `position`.
 ///
 /// # Errors
 ///
-/// Returns an error for unsupported primitives (Moire, Thermal) or invalid
-/// macro content.
+/// Returns an error for invalid macro content, including a variable
+/// definition that reads a variable only defined further down the macro.
+/// Unsupported primitives
+/// (Moire, Thermal) are skipped with a warning and recorded with
+/// [`GeometryBuilder::record_unsupported`].
 pub fn evaluate_macro(
     builder: &mut GeometryBuilder,
     macro_def: &ApertureMacro,
     params: &[f64],
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
-    let mut vars: HashMap<u32, f64> = HashMap::new();
+    let mut vars = MacroVars {
+        defined: macro_def
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                MacroContent::VariableDefinition(vd) => Some(vd.number),
+                _ => None,
+            })
+            .collect(),
+        ..MacroVars::default()
+    };
     for (i, &v) in params.iter().enumerate() {
         let key = u32::try_from(i).map_or(0, |n| n + 1);
         if key > 0 {
-            vars.insert(key, v);
+            vars.values.insert(key, v);
         }
     }
+    check_definition_chain(macro_def, &vars)?;
 
     for content in &macro_def.content {
         match content {
             MacroContent::VariableDefinition(vd) => {
                 let val = evaluate_expression(builder, &vd.expression, &vars)?;
-                vars.insert(vd.number, val);
-            }
-            MacroContent::Circle(c) => eval_circle(builder, c, &vars, position)?,
-            MacroContent::VectorLine(vl) => eval_vector_line(builder, vl, &vars, position)?,
-            MacroContent::CenterLine(cl) => eval_center_line(builder, cl, &vars, position)?,
-            MacroContent::Outline(o) => eval_outline(builder, o, &vars, position)?,
-            MacroContent::Polygon(p) => eval_polygon(builder, p, &vars, position)?,
-            MacroContent::Moire(_) | MacroContent::Thermal(_) => {
-                return Err(GeometryError::UnsupportedFeature(
-                    "moire and thermal primitives not supported".to_string(),
-                ));
+                vars.values.insert(vd.number, val);
             }
+            MacroContent::Circle(c) => eval_circle(builder, c, &vars, position, scale)?,
+            MacroContent::VectorLine(vl) => eval_vector_line(builder, vl, &vars, position, scale)?,
+            MacroContent::CenterLine(cl) => eval_center_line(builder, cl, &vars, position, scale)?,
+            MacroContent::Outline(o) => eval_outline(builder, o, &vars, position, scale)?,
+            MacroContent::Polygon(p) => eval_polygon(builder, p, &vars, position, scale)?,
+            MacroContent::Moire(_) => skip_unsupported(builder, macro_def, "moire"),
+            MacroContent::Thermal(_) => skip_unsupported(builder, macro_def, "thermal"),
             MacroContent::Comment(_) => {}
         }
     }
@@ -98,17 +125,93 @@ pub fn evaluate_macro(
     Ok(())
 }
 
+/// Checks that every `$n=` definition only reads parameters and variables
+/// defined above it.
+///
+/// Evaluation would otherwise stop at the first such read with a generic
+/// undefined-variable error; this names the definition at fault.
+fn check_definition_chain(
+    macro_def: &ApertureMacro,
+    vars: &MacroVars,
+) -> Result<(), GeometryError> {
+    let mut assigned: HashSet<u32> = vars.values.keys().copied().collect();
+    for content in &macro_def.content {
+        let MacroContent::VariableDefinition(vd) = content else {
+            continue;
+        };
+        // Malformed expressions are reported when they are evaluated.
+        let Ok(tokens) = tokenize(&vd.expression) else {
+            continue;
+        };
+        let forward = tokens.iter().find_map(|token| match *token {
+            Token::Variable(n) if !assigned.contains(&n) && vars.defined.contains(&n) => Some(n),
+            _ => None,
+        });
+        if let Some(n) = forward {
+            return Err(GeometryError::MacroError(format!(
+                "aperture macro `{}`: ${}={} reads ${n} before its definition",
+                macro_def.name,
+                vd.number,
+                vd.expression.trim()
+            )));
+        }
+        assigned.insert(vd.number);
+    }
+    Ok(())
+}
+
+/// Warns that a `feature` primitive of `macro_def` is not rendered.
+fn skip_unsupported(builder: &mut GeometryBuilder, macro_def: &ApertureMacro, feature: &str) {
+    builder.warn(format!(
+        "{feature} primitive in aperture macro `{}` not supported; skipping primitive",
+        macro_def.name
+    ));
+    builder.record_unsupported(feature);
+}
+
+/// Variable bindings for one macro evaluation.
+#[derive(Debug, Default)]
+struct MacroVars {
+    /// Current values of `$n` variables.
+    values: HashMap<u32, f64>,
+    /// Variables assigned by a `$n=` definition somewhere in the macro.
+    defined: HashSet<u32>,
+    /// Unsupplied parameters already reported during this evaluation.
+    defaulted: RefCell<HashSet<u32>>,
+}
+
+impl MacroVars {
+    /// Returns the value of `$n`.
+    ///
+    /// Parameters the aperture definition does not supply default to 0 with a
+    /// warning, as the specification requires. A variable assigned by a
+    /// definition that has not been evaluated yet is still an error.
+    fn lookup(&self, builder: &mut GeometryBuilder, n: u32) -> Result<f64, GeometryError> {
+        if let Some(v) = self.values.get(&n) {
+            return Ok(*v);
+        }
+        if self.defined.contains(&n) {
+            return Err(GeometryError::MacroError(format!(
+                "undefined variable ${n}"
+            )));
+        }
+        if self.defaulted.borrow_mut().insert(n) {
+            builder.warn(format!(
+                "macro parameter ${n} not supplied; defaulting to 0"
+            ));
+        }
+        Ok(0.0)
+    }
+}
+
 fn resolve_decimal(
     builder: &mut GeometryBuilder,
     d: &MacroDecimal,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
 ) -> Result<f64, GeometryError> {
     match d {
         MacroDecimal::Value(v) => Ok(*v),
-        MacroDecimal::Variable(n) => vars
-            .get(n)
-            .copied()
-            .ok_or_else(|| GeometryError::MacroError(format!("undefined variable ${n}"))),
+        MacroDecimal::Variable(n) => vars.lookup(builder, *n),
         MacroDecimal::Expression(s) => evaluate_expression(builder, s, vars),
     }
 }
@@ -116,15 +219,12 @@ fn resolve_decimal(
 fn resolve_boolean(
     builder: &mut GeometryBuilder,
     b: &MacroBoolean,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
 ) -> Result<bool, GeometryError> {
     match b {
         MacroBoolean::Value(v) => Ok(*v),
         MacroBoolean::Variable(n) => {
-            let v = vars
-                .get(n)
-                .copied()
-                .ok_or_else(|| GeometryError::MacroError(format!("undefined variable ${n}")))?;
+            let v = vars.lookup(builder, *n)?;
             Ok(v != 0.0)
         }
         MacroBoolean::Expression(s) => {
@@ -137,15 +237,12 @@ fn resolve_boolean(
 fn resolve_integer(
     builder: &mut GeometryBuilder,
     i: &MacroInteger,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
 ) -> Result<u32, GeometryError> {
     match i {
         MacroInteger::Value(v) => Ok(*v),
         MacroInteger::Variable(n) => {
-            let v = vars
-                .get(n)
-                .copied()
-                .ok_or_else(|| GeometryError::MacroError(format!("undefined variable ${n}")))?;
+            let v = vars.lookup(builder, *n)?;
             #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
             Ok(v as u32)
         }
@@ -160,7 +257,7 @@ fn resolve_integer(
 fn evaluate_expression(
     builder: &mut GeometryBuilder,
     expr: &str,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
 ) -> Result<f64, GeometryError> {
     let expr = expr.trim();
     if expr.is_empty() {
@@ -173,7 +270,7 @@ fn evaluate_expression(
 fn eval_with_nesting(
     builder: &mut GeometryBuilder,
     tokens: &[Token],
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     depth: u32,
 ) -> Result<f64, GeometryError> {
     let (val, rest) = parse_additive(builder, tokens, vars, depth)?;
@@ -256,7 +353,7 @@ fn tokenize(expr: &str) -> Result<Vec<Token>, GeometryError> {
 fn parse_additive<'a>(
     builder: &mut GeometryBuilder,
     tokens: &'a [Token],
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     depth: u32,
 ) -> Result<(f64, &'a [Token]), GeometryError> {
     let (mut left, mut rest) = parse_multiplicative(builder, tokens, vars, depth)?;
@@ -281,7 +378,7 @@ fn parse_additive<'a>(
 fn parse_multiplicative<'a>(
     builder: &mut GeometryBuilder,
     tokens: &'a [Token],
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     depth: u32,
 ) -> Result<(f64, &'a [Token]), GeometryError> {
     let (mut left, mut rest) = parse_unary(builder, tokens, vars, depth)?;
@@ -313,7 +410,7 @@ fn parse_multiplicative<'a>(
 fn parse_unary<'a>(
     builder: &mut GeometryBuilder,
     tokens: &'a [Token],
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     depth: u32,
 ) -> Result<(f64, &'a [Token]), GeometryError> {
     let tail = tokens.get(1..).map_or(&[] as &[Token], |s| s);
@@ -338,10 +435,7 @@ fn parse_unary<'a>(
         }
         Some(Token::Number(n)) => Ok((*n, tail)),
         Some(Token::Variable(n)) => {
-            let v = vars
-                .get(n)
-                .copied()
-                .ok_or_else(|| GeometryError::MacroError(format!("undefined variable ${n}")))?;
+            let v = vars.lookup(builder, *n)?;
             Ok((v, tail))
         }
         _ => Err(GeometryError::MacroError(
@@ -353,14 +447,15 @@ fn parse_unary<'a>(
 fn eval_circle(
     builder: &mut GeometryBuilder,
     c: &CirclePrimitive,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
     let exposure = resolve_boolean(builder, &c.exposure, vars)?;
-    let diameter = resolve_decimal(builder, &c.diameter, vars)?;
+    let diameter = resolve_decimal(builder, &c.diameter, vars)? * scale;
     let (cx, cy) = (
-        resolve_decimal(builder, &c.center.0, vars)?,
-        resolve_decimal(builder, &c.center.1, vars)?,
+        resolve_decimal(builder, &c.center.0, vars)? * scale,
+        resolve_decimal(builder, &c.center.1, vars)? * scale,
     );
     let angle = c
         .angle
@@ -378,7 +473,8 @@ fn eval_circle(
     let (rx, ry) = rotate_point(cx, cy, angle);
     let px = position.x + rx;
     let py = position.y + ry;
-    builder.push_ngon(px, py, radius, CIRCLE_SEGMENTS);
+    let segments = builder.options().curve_segments(CIRCLE_SEGMENTS);
+    builder.push_ngon(px, py, radius, segments);
     let idx_end = builder.index_count();
 
     if !exposure {
@@ -391,18 +487,19 @@ fn eval_circle(
 fn eval_vector_line(
     builder: &mut GeometryBuilder,
     vl: &VectorLinePrimitive,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
     let exposure = resolve_boolean(builder, &vl.exposure, vars)?;
-    let width = resolve_decimal(builder, &vl.width, vars)?;
+    let width = resolve_decimal(builder, &vl.width, vars)? * scale;
     let (sx, sy) = (
-        resolve_decimal(builder, &vl.start.0, vars)?,
-        resolve_decimal(builder, &vl.start.1, vars)?,
+        resolve_decimal(builder, &vl.start.0, vars)? * scale,
+        resolve_decimal(builder, &vl.start.1, vars)? * scale,
     );
     let (ex, ey) = (
-        resolve_decimal(builder, &vl.end.0, vars)?,
-        resolve_decimal(builder, &vl.end.1, vars)?,
+        resolve_decimal(builder, &vl.end.0, vars)? * scale,
+        resolve_decimal(builder, &vl.end.1, vars)? * scale,
     );
     let angle = resolve_decimal(builder, &vl.angle, vars)?;
 
@@ -431,17 +528,18 @@ fn eval_vector_line(
 fn eval_center_line(
     builder: &mut GeometryBuilder,
     cl: &CenterLinePrimitive,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
     let exposure = resolve_boolean(builder, &cl.exposure, vars)?;
     let (w, h) = (
-        resolve_decimal(builder, &cl.dimensions.0, vars)?,
-        resolve_decimal(builder, &cl.dimensions.1, vars)?,
+        resolve_decimal(builder, &cl.dimensions.0, vars)? * scale,
+        resolve_decimal(builder, &cl.dimensions.1, vars)? * scale,
     );
     let (cx, cy) = (
-        resolve_decimal(builder, &cl.center.0, vars)?,
-        resolve_decimal(builder, &cl.center.1, vars)?,
+        resolve_decimal(builder, &cl.center.0, vars)? * scale,
+        resolve_decimal(builder, &cl.center.1, vars)? * scale,
     );
     let angle = resolve_decimal(builder, &cl.angle, vars)?;
 
@@ -466,8 +564,9 @@ fn eval_center_line(
 fn eval_outline(
     builder: &mut GeometryBuilder,
     o: &OutlinePrimitive,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
     let exposure = resolve_boolean(builder, &o.exposure, vars)?;
     let angle = resolve_decimal(builder, &o.angle, vars)?;
@@ -478,21 +577,21 @@ fn eval_outline(
 
     let mut flat = Vec::with_capacity(o.points.len() * 2);
     for pt in &o.points {
-        let x = resolve_decimal(builder, &pt.0, vars)?;
-        let y = resolve_decimal(builder, &pt.1, vars)?;
+        let x = resolve_decimal(builder, &pt.0, vars)? * scale;
+        let y = resolve_decimal(builder, &pt.1, vars)? * scale;
         let (rx, ry) = rotate_point(x, y, angle);
         flat.push(position.x + rx);
         flat.push(position.y + ry);
     }
 
-    let tri_indices = earclip::earcut::earcut(&flat, &[], 2);
-    if tri_indices.is_empty() {
+    let triangulation = builder.options().triangulator.backend().triangulate(&flat);
+    if triangulation.indices.is_empty() {
         return Ok(());
     }
 
     let idx_start = builder.index_count();
-    let base = outline_emit_vertices(builder, &flat);
-    outline_emit_triangles(builder, &tri_indices, base)?;
+    let base = outline_emit_vertices(builder, &triangulation.vertices);
+    outline_emit_triangles(builder, &triangulation.indices, base)?;
     let idx_end = builder.index_count();
 
     if !exposure {
@@ -541,21 +640,30 @@ fn outline_offset(base: u32, offset: usize) -> Result<u32, GeometryError> {
 fn eval_polygon(
     builder: &mut GeometryBuilder,
     p: &PolygonPrimitive,
-    vars: &HashMap<u32, f64>,
+    vars: &MacroVars,
     position: Point,
+    scale: f64,
 ) -> Result<(), GeometryError> {
     let exposure = resolve_boolean(builder, &p.exposure, vars)?;
     let vertices = resolve_integer(builder, &p.vertices, vars)?;
     let (cx, cy) = (
-        resolve_decimal(builder, &p.center.0, vars)?,
-        resolve_decimal(builder, &p.center.1, vars)?,
+        resolve_decimal(builder, &p.center.0, vars)? * scale,
+        resolve_decimal(builder, &p.center.1, vars)? * scale,
     );
-    let diameter = resolve_decimal(builder, &p.diameter, vars)?;
+    let diameter = resolve_decimal(builder, &p.diameter, vars)? * scale;
     let angle = resolve_decimal(builder, &p.angle, vars)?;
 
     if vertices < 3 || diameter <= 0.0 {
         return Ok(());
     }
+    let vertices = if vertices > MAX_POLYGON_VERTICES {
+        builder.warn(format!(
+            "macro polygon has {vertices} vertices; clamping to {MAX_POLYGON_VERTICES}"
+        ));
+        MAX_POLYGON_VERTICES
+    } else {
+        vertices
+    };
 
     let idx_start = builder.index_count();
     let (rcx, rcy) = rotate_point(cx, cy, angle);
@@ -624,7 +732,10 @@ fn push_centered_rect(
 
 #[cfg(test)]
 mod tests {
-    use gerber_types::{CirclePrimitive, MacroBoolean, MacroDecimal, VariableDefinition};
+    use gerber_types::{
+        CirclePrimitive, MacroBoolean, MacroDecimal, MacroInteger, PolygonPrimitive,
+        VariableDefinition,
+    };
 
     use super::*;
 
@@ -641,7 +752,7 @@ mod tests {
     fn ut_mac_001_circle_primitive_produces_vertices() {
         let macro_def = make_macro_with_circle();
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert_eq!(geom.vertex_count, CIRCLE_SEGMENTS);
@@ -658,7 +769,7 @@ mod tests {
             angle: MacroDecimal::Value(0.0),
         });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert_eq!(geom.vertex_count, 4);
@@ -679,7 +790,7 @@ mod tests {
             angle: MacroDecimal::Value(0.0),
         });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert!(geom.vertex_count >= 3);
@@ -702,7 +813,7 @@ mod tests {
             angle: MacroDecimal::Value(0.0),
         });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert!(geom.vertex_count >= 5);
@@ -721,7 +832,7 @@ mod tests {
             angle: None,
         });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert!(!geom.clear_ranges.is_empty());
@@ -743,6 +854,7 @@ mod tests {
             &macro_def,
             &[3.0, 1.0],
             Point { x: 0.0, y: 0.0 },
+            1.0,
         );
         assert!(result.is_ok());
         let geom = builder.build();
@@ -764,7 +876,7 @@ mod tests {
                 angle: None,
             });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert!(geom.warnings.iter().any(|w| w.contains("BC-GBR-024")));
@@ -785,9 +897,113 @@ mod tests {
                 angle: None,
             });
         let mut builder = GeometryBuilder::new();
-        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 });
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
         assert!(result.is_ok());
         let geom = builder.build();
         assert!(geom.warnings.iter().any(|w| w.contains("BC-GBR-025")));
     }
+
+    #[test]
+    fn ut_mac_006_inch_macro_primitives_scale_to_millimeters() {
+        let macro_def = ApertureMacro::new("INCH").add_content(CirclePrimitive {
+            exposure: MacroBoolean::Value(true),
+            diameter: MacroDecimal::Value(0.1),
+            center: (MacroDecimal::Value(0.1), MacroDecimal::Value(0.0)),
+            angle: None,
+        });
+        let mut builder = GeometryBuilder::new();
+        let position = Point { x: 25.4, y: 25.4 };
+        let result = evaluate_macro(&mut builder, &macro_def, &[], position, 25.4);
+        assert!(result.is_ok());
+        let geom = builder.build();
+        assert!((geom.bounds.max_x - geom.bounds.min_x - 2.54).abs() < 1e-5);
+        assert!((geom.bounds.min_x - 26.67).abs() < 1e-5);
+        assert!((geom.bounds.max_x - 29.21).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ut_mac_007_missing_parameter_defaults_to_zero() {
+        let macro_def = ApertureMacro::new("PARAMS").add_content(CirclePrimitive {
+            exposure: MacroBoolean::Value(true),
+            diameter: MacroDecimal::Variable(1),
+            center: (MacroDecimal::Variable(5), MacroDecimal::Value(0.0)),
+            angle: None,
+        });
+        let mut builder = GeometryBuilder::new();
+        let origin = Point { x: 0.0, y: 0.0 };
+        let result = evaluate_macro(&mut builder, &macro_def, &[2.0, 0.0, 0.0], origin, 1.0);
+        assert!(result.is_ok(), "{result:?}");
+        let geom = builder.build();
+        assert_eq!(geom.vertex_count, CIRCLE_SEGMENTS);
+        assert!((geom.bounds.max_x - 1.0).abs() < 1e-6, "$5 centers at 0");
+        assert!(geom
+            .warnings
+            .iter()
+            .any(|w| w.contains("$5 not supplied; defaulting to 0")));
+    }
+
+    #[test]
+    fn ut_mac_008_defined_variable_used_before_definition_errors() {
+        let macro_def = ApertureMacro::new("FORWARD")
+            .add_content(CirclePrimitive {
+                exposure: MacroBoolean::Value(true),
+                diameter: MacroDecimal::Variable(4),
+                center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                angle: None,
+            })
+            .add_content(VariableDefinition::new(4, "1.0"));
+        let mut builder = GeometryBuilder::new();
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
+        assert!(matches!(result, Err(GeometryError::MacroError(_))));
+    }
+
+    #[test]
+    fn ut_mac_009_forward_definition_chain_names_the_variable() {
+        let macro_def = ApertureMacro::new("CHAIN")
+            .add_content(VariableDefinition::new(2, "$3x2"))
+            .add_content(VariableDefinition::new(3, "$1+1"))
+            .add_content(CirclePrimitive {
+                exposure: MacroBoolean::Value(true),
+                diameter: MacroDecimal::Variable(2),
+                center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                angle: None,
+            });
+        let mut builder = GeometryBuilder::new();
+        let result = evaluate_macro(
+            &mut builder,
+            &macro_def,
+            &[1.0],
+            Point { x: 0.0, y: 0.0 },
+            1.0,
+        );
+        assert!(
+            matches!(
+                &result,
+                Err(GeometryError::MacroError(message))
+                    if message == "aperture macro `CHAIN`: $2=$3x2 reads $3 before its definition"
+            ),
+            "{result:?}"
+        );
+        assert_eq!(builder.build().vertex_count, 0);
+    }
+
+    #[test]
+    fn ut_mac_010_oversized_polygon_vertex_count_is_clamped() {
+        let macro_def = ApertureMacro::new("HUGE").add_content(PolygonPrimitive {
+            exposure: MacroBoolean::Value(true),
+            vertices: MacroInteger::Value(4_000_000_000),
+            center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+            diameter: MacroDecimal::Value(2.0),
+            angle: MacroDecimal::Value(0.0),
+        });
+        let mut builder = GeometryBuilder::new();
+        let result = evaluate_macro(&mut builder, &macro_def, &[], Point { x: 0.0, y: 0.0 }, 1.0);
+        assert!(result.is_ok(), "{result:?}");
+        let geom = builder.build();
+        assert_eq!(geom.vertex_count, MAX_POLYGON_VERTICES);
+        assert!(geom
+            .warnings
+            .iter()
+            .any(|w| w.contains("4000000000 vertices; clamping to 1024")));
+    }
 }
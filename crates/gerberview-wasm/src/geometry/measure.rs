@@ -0,0 +1,411 @@
+//! Spatial queries for probe and ruler tools.
+//!
+//! [`CopperProbe`] buckets the layer's triangles and copper outline edges
+//! into uniform grids once, so each query only tests nearby features.
+//! [`point_in_copper`] and [`distance_to_nearest_edge`] build a probe for a
+//! single query; keep a [`CopperProbe`] around for interactive use.
+//! [`centroid`] gives the area-weighted center of the dark geometry.
+
+use std::collections::HashMap;
+use std::iter;
+
+use super::flatten::to_polygons;
+use super::types::{index_in_ranges, LayerGeometry, Point};
+
+/// Items spanning more grid cells than this are kept in an unbucketed list.
+const MAX_CELLS_PER_ITEM: i64 = 256;
+
+/// Returns whether `(x, y)` lies in dark copper of `geometry`.
+///
+/// See [`CopperProbe::point_in_copper`].
+pub fn point_in_copper(geometry: &LayerGeometry, x: f64, y: f64) -> bool {
+    CopperProbe::new(geometry).point_in_copper(x, y)
+}
+
+/// Returns the distance from `(x, y)` to the nearest copper outline edge of
+/// `geometry`.
+///
+/// See [`CopperProbe::distance_to_nearest_edge`].
+pub fn distance_to_nearest_edge(geometry: &LayerGeometry, x: f64, y: f64) -> f64 {
+    CopperProbe::new(geometry).distance_to_nearest_edge(x, y)
+}
+
+/// Returns the area-weighted centroid of the dark triangles of `geometry`.
+///
+/// Each triangle outside `clear_ranges` contributes its centroid weighted by
+/// its area, so overlapping triangles count twice. Without dark area the
+/// center of the bounds is returned, or the origin for empty geometry.
+pub fn centroid(geometry: &LayerGeometry) -> (f64, f64) {
+    let mut clear_ranges = geometry.clear_ranges.clone();
+    clear_ranges.sort_unstable();
+    let (mut area, mut moment_x, mut moment_y) = (0.0, 0.0, 0.0);
+    for (triangle_index, triangle) in geometry.indices.chunks_exact(3).enumerate() {
+        if index_in_ranges(&clear_ranges, triangle_index.saturating_mul(3)) {
+            continue;
+        }
+        let Some(corners @ [a, b, c]) = triangle_corners(geometry, triangle) else {
+            continue;
+        };
+        let weight = cross(a, b, c).abs() / 2.0;
+        area += weight;
+        moment_x += weight * corners.iter().map(|p| p.x).sum::<f64>() / 3.0;
+        moment_y += weight * corners.iter().map(|p| p.y).sum::<f64>() / 3.0;
+    }
+    if area > 0.0 {
+        return (moment_x / area, moment_y / area);
+    }
+    let bounds = geometry.bounds;
+    if bounds.min_x <= bounds.max_x && bounds.min_y <= bounds.max_y {
+        (
+            (bounds.min_x + bounds.max_x) / 2.0,
+            (bounds.min_y + bounds.max_y) / 2.0,
+        )
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+/// Spatial index over one layer for repeated copper queries.
+#[derive(Debug, Clone)]
+pub struct CopperProbe {
+    /// Triangle corners in draw order, with whether each is dark.
+    triangles: Vec<([Point; 3], bool)>,
+    triangle_grid: BoxGrid,
+    /// Edges of the merged copper outlines from [`to_polygons`].
+    edges: Vec<(Point, Point)>,
+    edge_grid: BoxGrid,
+}
+
+impl CopperProbe {
+    /// Indexes the triangles and copper outline of `geometry`.
+    pub fn new(geometry: &LayerGeometry) -> Self {
+        let mut clear_ranges = geometry.clear_ranges.clone();
+        clear_ranges.sort_unstable();
+        let triangles: Vec<([Point; 3], bool)> = geometry
+            .indices
+            .chunks_exact(3)
+            .enumerate()
+            .filter_map(|(triangle_index, triangle)| {
+                let corners = triangle_corners(geometry, triangle)?;
+                let clear = index_in_ranges(&clear_ranges, triangle_index.saturating_mul(3));
+                Some((corners, !clear))
+            })
+            .collect();
+        let triangle_grid = BoxGrid::new(
+            &triangles
+                .iter()
+                .map(|&(corners, _)| bounds_of(corners))
+                .collect::<Vec<_>>(),
+        );
+
+        let edges: Vec<(Point, Point)> = to_polygons(geometry)
+            .iter()
+            .flat_map(|polygon| iter::once(&polygon.outer).chain(&polygon.holes))
+            .flat_map(|ring| {
+                ring.iter()
+                    .zip(ring.iter().cycle().skip(1))
+                    .filter(|(a, b)| a != b)
+                    .map(|(&a, &b)| (a, b))
+            })
+            .collect();
+        let edge_grid = BoxGrid::new(
+            &edges
+                .iter()
+                .map(|&edge| bounds_of(<[Point; 2]>::from(edge)))
+                .collect::<Vec<_>>(),
+        );
+
+        Self {
+            triangles,
+            triangle_grid,
+            edges,
+            edge_grid,
+        }
+    }
+
+    /// Returns whether `(x, y)` lies in dark copper.
+    ///
+    /// The last-drawn triangle covering the point decides, so clear geometry
+    /// only erases copper drawn before it. Points on a triangle edge count as
+    /// covered.
+    pub fn point_in_copper(&self, x: f64, y: f64) -> bool {
+        let point = Point { x, y };
+        self.triangle_grid
+            .candidates(self.triangle_grid.cell(point))
+            .filter(|&id| {
+                self.triangles
+                    .get(id)
+                    .is_some_and(|(corners, _)| triangle_contains(corners, point))
+            })
+            .max()
+            .and_then(|id| self.triangles.get(id))
+            .is_some_and(|&(_, dark)| dark)
+    }
+
+    /// Returns the distance from `(x, y)` to the nearest edge of the merged
+    /// copper outline, from inside or outside the copper.
+    ///
+    /// Returns `f64::INFINITY` when the layer has no copper.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn distance_to_nearest_edge(&self, x: f64, y: f64) -> f64 {
+        let point = Point { x, y };
+        let grid = &self.edge_grid;
+        let nearest = |ids: &mut dyn Iterator<Item = &usize>, best: f64| {
+            ids.filter_map(|&id| self.edges.get(id))
+                .map(|&(a, b)| segment_distance(point, a, b))
+                .fold(best, f64::min)
+        };
+        let mut best = nearest(&mut grid.large.iter(), f64::INFINITY);
+
+        let Some(((x0, y0), (x1, y1))) = grid.occupied else {
+            return best;
+        };
+        let (cx, cy) = grid.cell(point);
+        let outside = (x0 - cx).max(cx - x1).max(y0 - cy).max(cy - y1).max(0);
+        let farthest = (cx - x0).max(x1 - cx).max(cy - y0).max(y1 - cy);
+        for ring in outside..=farthest {
+            // Cells in ring `k` around the point's cell are at least `k - 1`
+            // cells away from it.
+            if best <= (ring - 1).max(0) as f64 * grid.cell_size {
+                break;
+            }
+            for cell in ring_cells((cx, cy), ring, ((x0, y0), (x1, y1))) {
+                best = nearest(&mut grid.bucket(cell).iter(), best);
+            }
+        }
+        best
+    }
+}
+
+/// Uniform grid bucketing item ids by their bounding boxes.
+#[derive(Debug, Clone)]
+struct BoxGrid {
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// Items too large to bucket; part of every query.
+    large: Vec<usize>,
+    origin: Point,
+    cell_size: f64,
+    /// Range of cells holding any bucketed item.
+    occupied: Option<((i64, i64), (i64, i64))>,
+}
+
+impl BoxGrid {
+    /// Sizes cells so each holds about one item on average.
+    #[allow(clippy::cast_precision_loss)]
+    fn new(boxes: &[(Point, Point)]) -> Self {
+        let (min, max) = bounds_of(boxes.iter().flat_map(|&bounds| <[Point; 2]>::from(bounds)));
+        let extent = (max.x - min.x).max(max.y - min.y);
+        let cell_size = extent / (boxes.len().max(1) as f64).sqrt();
+        let valid = cell_size.is_finite() && cell_size > 0.0;
+        let mut grid = Self {
+            cells: HashMap::new(),
+            large: Vec::new(),
+            origin: if valid { min } else { Point { x: 0.0, y: 0.0 } },
+            cell_size: if valid { cell_size } else { 1.0 },
+            occupied: None,
+        };
+        for (id, &(lo, hi)) in boxes.iter().enumerate() {
+            grid.insert(id, grid.cell(lo), grid.cell(hi));
+        }
+        grid
+    }
+
+    fn insert(&mut self, id: usize, (x0, y0): (i64, i64), (x1, y1): (i64, i64)) {
+        if (x1 - x0 + 1).saturating_mul(y1 - y0 + 1) > MAX_CELLS_PER_ITEM {
+            self.large.push(id);
+            return;
+        }
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+        self.occupied = Some(
+            self.occupied
+                .map_or(((x0, y0), (x1, y1)), |((ox0, oy0), (ox1, oy1))| {
+                    ((ox0.min(x0), oy0.min(y0)), (ox1.max(x1), oy1.max(y1)))
+                }),
+        );
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell(&self, p: Point) -> (i64, i64) {
+        (
+            ((p.x - self.origin.x) / self.cell_size).floor() as i64,
+            ((p.y - self.origin.y) / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn bucket(&self, cell: (i64, i64)) -> &[usize] {
+        self.cells.get(&cell).map_or(&[], Vec::as_slice)
+    }
+
+    /// Ids of items that may cover `cell`, including the large ones.
+    fn candidates(&self, cell: (i64, i64)) -> impl Iterator<Item = usize> + '_ {
+        self.large.iter().chain(self.bucket(cell)).copied()
+    }
+}
+
+/// Cells at Chebyshev distance `ring` from `center`, clipped to `range`.
+fn ring_cells(
+    (cx, cy): (i64, i64),
+    ring: i64,
+    ((x0, y0), (x1, y1)): ((i64, i64), (i64, i64)),
+) -> impl Iterator<Item = (i64, i64)> {
+    let columns = (cx - ring).max(x0)..=(cx + ring).min(x1);
+    let rows = (cy - ring + 1).max(y0)..=(cy + ring - 1).min(y1);
+    let mut rows_at = vec![cy - ring];
+    if ring > 0 {
+        rows_at.push(cy + ring);
+    }
+    let mut columns_at = Vec::new();
+    if ring > 0 {
+        columns_at.extend([cx - ring, cx + ring]);
+    }
+    let horizontal = rows_at
+        .into_iter()
+        .filter(move |y| (y0..=y1).contains(y))
+        .flat_map(move |y| columns.clone().map(move |x| (x, y)));
+    let vertical = columns_at
+        .into_iter()
+        .filter(move |x| (x0..=x1).contains(x))
+        .flat_map(move |x| rows.clone().map(move |y| (x, y)));
+    horizontal.chain(vertical)
+}
+
+fn bounds_of(points: impl IntoIterator<Item = Point>) -> (Point, Point) {
+    points.into_iter().fold(
+        (
+            Point {
+                x: f64::INFINITY,
+                y: f64::INFINITY,
+            },
+            Point {
+                x: f64::NEG_INFINITY,
+                y: f64::NEG_INFINITY,
+            },
+        ),
+        |(min, max), p| {
+            (
+                Point {
+                    x: min.x.min(p.x),
+                    y: min.y.min(p.y),
+                },
+                Point {
+                    x: max.x.max(p.x),
+                    y: max.y.max(p.y),
+                },
+            )
+        },
+    )
+}
+
+fn triangle_corners(geometry: &LayerGeometry, triangle: &[u32]) -> Option<[Point; 3]> {
+    let mut corners = [Point { x: 0.0, y: 0.0 }; 3];
+    for (corner, index) in corners.iter_mut().zip(triangle) {
+        let base = (*index as usize).checked_mul(2)?;
+        let x = *geometry.positions.get(base)?;
+        let y = *geometry.positions.get(base + 1)?;
+        *corner = Point {
+            x: f64::from(x),
+            y: f64::from(y),
+        };
+    }
+    Some(corners)
+}
+
+fn triangle_contains(&[a, b, c]: &[Point; 3], p: Point) -> bool {
+    let sides = [cross(a, b, p), cross(b, c, p), cross(c, a, p)];
+    sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0)
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_sq = dx.mul_add(dx, dy * dy);
+    let t = if length_sq > 0.0 {
+        ((p.x - a.x).mul_add(dx, (p.y - a.y) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.x - dx.mul_add(t, a.x)).hypot(p.y - dy.mul_add(t, a.y))
+}
+
+fn cross(a: Point, b: Point, p: Point) -> f64 {
+    (b.x - a.x).mul_add(p.y - a.y, -((b.y - a.y) * (p.x - a.x)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryBuilder;
+
+    fn push_square(builder: &mut GeometryBuilder, center: f64, half: f64) {
+        let a = builder.push_vertex(center - half, center - half);
+        let b = builder.push_vertex(center + half, center - half);
+        let c = builder.push_vertex(center + half, center + half);
+        let d = builder.push_vertex(center - half, center + half);
+        builder.push_quad(a, b, c, d);
+    }
+
+    #[test]
+    fn clear_square_punches_hole_in_copper() {
+        let mut builder = GeometryBuilder::new();
+        push_square(&mut builder, 0.0, 2.0);
+        let clear_start = builder.index_count();
+        push_square(&mut builder, 0.0, 1.0);
+        builder.record_clear_range(clear_start, builder.index_count());
+        let probe = CopperProbe::new(&builder.build());
+
+        assert!(!probe.point_in_copper(0.0, 0.0));
+        assert!(probe.point_in_copper(1.5, 0.0));
+        assert!(!probe.point_in_copper(3.0, 0.0));
+        assert!((probe.distance_to_nearest_edge(0.0, 0.0) - 1.0).abs() < 1e-6);
+        assert!((probe.distance_to_nearest_edge(1.5, 0.0) - 0.5).abs() < 1e-6);
+        assert!((probe.distance_to_nearest_edge(10.0, 0.0) - 8.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn dark_after_clear_covers_point_again() {
+        let mut builder = GeometryBuilder::new();
+        push_square(&mut builder, 0.0, 2.0);
+        let clear_start = builder.index_count();
+        push_square(&mut builder, 0.0, 1.0);
+        builder.record_clear_range(clear_start, builder.index_count());
+        push_square(&mut builder, 0.0, 0.5);
+
+        assert!(point_in_copper(&builder.build(), 0.0, 0.0));
+    }
+
+    #[test]
+    fn empty_layer_has_no_copper() {
+        let geometry = GeometryBuilder::new().build();
+        assert!(!point_in_copper(&geometry, 0.0, 0.0));
+        assert!(distance_to_nearest_edge(&geometry, 0.0, 0.0).is_infinite());
+    }
+
+    #[test]
+    fn centroid_is_area_weighted() {
+        let mut builder = GeometryBuilder::new();
+        push_square(&mut builder, 3.0, 2.0);
+        let (center_x, center_y) = centroid(&builder.build());
+        assert!((center_x - 3.0).abs() < 1e-9 && (center_y - 3.0).abs() < 1e-9);
+
+        // L-shape: a 4x1 bar along the bottom and a 1x3 bar up the left
+        // side. Area 7 at x = (4 * 2 + 3 * 0.5) / 7, y = (4 * 0.5 + 3 * 2.5) / 7.
+        let mut builder = GeometryBuilder::new();
+        for (x0, y0, x1, y1) in [(0.0, 0.0, 4.0, 1.0), (0.0, 1.0, 1.0, 4.0)] {
+            let [v0, v1, v2, v3] =
+                [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|(x, y)| builder.push_vertex(x, y));
+            builder.push_quad(v0, v1, v2, v3);
+        }
+        let (x, y) = centroid(&builder.build());
+        assert!((x - 9.5 / 7.0).abs() < 1e-9);
+        assert!((y - 9.5 / 7.0).abs() < 1e-9);
+        assert!(
+            x < 2.0 && y < 2.0,
+            "pulled toward the corner from the box center"
+        );
+
+        assert_eq!(centroid(&GeometryBuilder::new().build()), (0.0, 0.0));
+    }
+}
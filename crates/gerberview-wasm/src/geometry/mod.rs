@@ -1,352 +1,669 @@
 //! Core geometry types and the geometry conversion pipeline.
 
 pub mod aperture;
+pub mod aperture_report;
 pub mod arc;
+pub mod attributes;
+pub mod export;
+pub mod flatten;
+pub mod hash;
+pub mod hull;
 pub mod macro_eval;
+pub mod measure;
+pub mod operations;
+pub mod options;
 pub mod polarity;
+pub mod raster;
 pub mod region;
+pub mod role;
 pub mod step_repeat;
 pub mod stroke;
+pub mod transform;
+pub mod triangulate;
 pub mod types;
 
 pub use aperture::*;
+pub use aperture_report::*;
 pub use arc::*;
+pub use attributes::*;
+pub use export::*;
+pub use flatten::*;
+pub use hash::*;
+pub use hull::*;
 pub use macro_eval::*;
+pub use measure::*;
+pub use operations::*;
+pub use options::*;
 pub use polarity::*;
+pub use raster::*;
 pub use region::*;
+pub use role::*;
 pub use step_repeat::*;
 pub use stroke::*;
+pub use transform::*;
+pub use triangulate::*;
 pub use types::*;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use gerber_parser::GerberDoc;
-use gerber_types::{
-    Command, CoordinateFormat, CoordinateMode, CoordinateOffset, Coordinates, ExtendedCode,
-    FunctionCode, Operation, Unit, ZeroOmission,
-};
+use web_time::Instant;
 
 use crate::error::GeometryError;
 
-const DEFAULT_FORMAT: (u8, u8) = (2, 6);
-const MM_PER_INCH: f64 = 25.4;
+use self::operations::{Event, Interpreter};
 
 /// Converts a parsed Gerber document into renderable layer geometry.
 ///
-/// Walks the command list, maintains interpreter state, and dispatches to
-/// geometry sub-modules for flashes, strokes, arcs, regions, step-repeat,
-/// and aperture macros.
+/// Consumes the [`operations`] interpreter and dispatches each resolved
+/// operation to geometry sub-modules for flashes, strokes, arcs, regions,
+/// step-repeat, and aperture macros.
 ///
 /// # Errors
 ///
 /// Returns [`GeometryError`] when conversion fails fatally (e.g. invalid
 /// aperture reference, degenerate geometry).
-#[allow(clippy::too_many_lines)]
 pub fn convert(doc: &GerberDoc) -> Result<LayerGeometry, GeometryError> {
-    let format = doc.format_specification.unwrap_or_else(|| {
-        CoordinateFormat::new(
-            ZeroOmission::Leading,
-            CoordinateMode::Absolute,
-            DEFAULT_FORMAT.0,
-            DEFAULT_FORMAT.1,
-        )
-    });
-    let units = doc.units;
-
-    let mut state = types::GerberState {
-        current_point: types::Point { x: 0.0, y: 0.0 },
-        current_aperture: None,
-        interpolation_mode: types::InterpolationMode::Linear,
-        region_mode: false,
-        region_points: Vec::new(),
-        units,
-        format: Some(format),
-    };
+    convert_with_options(doc, &TessellationOptions::new(), None)
+}
 
-    let mut builder = types::GeometryBuilder::new();
-    let mut polarity_tracker = polarity::PolarityTracker::new();
-    let mut arc_quadrant_mode = ArcQuadrantMode::MultiQuadrant;
+/// Runs the interpreter over `doc` without building geometry.
+///
+/// Counts processed commands and interpreter warnings (undefined apertures,
+/// undecodable commands, and the like). Geometry-level warnings such as
+/// degenerate shapes are only found by [`convert`].
+pub fn validate(doc: &GerberDoc) -> ValidationReport {
+    let mut interpreter = Interpreter::new(doc);
+    let warning_count = interpreter
+        .by_ref()
+        .filter(|event| {
+            matches!(
+                event,
+                Event::Warning(_)
+                    | Event::CategorizedWarning(..)
+                    | Event::Unsupported(..)
+                    | Event::ParseError(_)
+            )
+        })
+        .count();
+    ValidationReport {
+        command_count: interpreter.command_count(),
+        warning_count: types::saturate_u32(warning_count),
+        fatal: false,
+        error: None,
+    }
+}
 
-    let mut macros: HashMap<String, gerber_types::ApertureMacro> = HashMap::new();
-    let mut sr_stack: Vec<(types::GeometryBuilder, u32, u32, f64, f64)> = Vec::new();
-    let mut command_count: u32 = 0;
+/// Converts a parsed Gerber document with explicit tessellation options.
+///
+/// When `on_warning` is given it is called with each warning as soon as the
+/// conversion records it, in order. Warnings are still accumulated in
+/// [`LayerGeometry::warnings`].
+///
+/// When the triangle or time budget in `options` runs out, conversion stops
+/// early and returns the geometry built so far, with a warning and
+/// [`LayerGeometry::truncated`] set. Geometry of a step-repeat block still
+/// open at that point is discarded.
+///
+/// # Errors
+///
+/// Returns [`GeometryError`] when conversion fails fatally, as for [`convert`],
+/// or [`GeometryError::StrictViolation`] listing every warning that
+/// `options.strict` promotes.
+pub fn convert_with_options(
+    doc: &GerberDoc,
+    options: &TessellationOptions,
+    on_warning: Option<&mut dyn FnMut(&str)>,
+) -> Result<LayerGeometry, GeometryError> {
+    let mut builder = types::GeometryBuilder::with_options(*options);
+    convert_with_builder(doc, &mut builder, on_warning, |builder| {
+        std::mem::take(builder).build()
+    })
+    .map(|(geom, _)| geom)
+}
 
-    for cmd_result in &doc.commands {
-        let cmd = match cmd_result {
-            Ok(c) => c,
-            Err(e) => {
-                builder.warn(format!("parse error: {e:?}"));
-                continue;
-            }
-        };
+/// Converts `doc` like [`convert_with_options`], also returning the
+/// interpreter's final state and per-type operation counts.
+///
+/// Counts include operations later dropped by the command filter, the
+/// minimum feature size or the preview limits.
+///
+/// # Errors
+///
+/// Returns [`GeometryError`] as for [`convert_with_options`].
+pub fn convert_with_debug(
+    doc: &GerberDoc,
+    options: &TessellationOptions,
+) -> Result<(LayerGeometry, ConvertDebug), GeometryError> {
+    let mut builder = types::GeometryBuilder::with_options(*options);
+    convert_with_builder(doc, &mut builder, None, |builder| {
+        std::mem::take(builder).build()
+    })
+}
 
-        command_count = command_count.saturating_add(1);
-
-        if matches!(
-            cmd,
-            Command::ExtendedCode(ExtendedCode::StepAndRepeat(
-                gerber_types::StepAndRepeat::Close,
-            ))
-        ) {
-            if let Some((block_builder, repeat_x, repeat_y, distance_x, distance_y)) =
-                sr_stack.pop()
-            {
-                let block_geom = block_builder.build();
-                let parent_builder = if let Some((ref mut pb, ..)) = sr_stack.last_mut() {
-                    pb
-                } else {
-                    &mut builder
-                };
-                step_repeat::apply_step_repeat(
-                    parent_builder,
-                    &block_geom,
-                    repeat_x,
-                    repeat_y,
-                    distance_x,
-                    distance_y,
-                )?;
-            } else {
-                builder.warn("SR close without matching open; ignoring".to_string());
-            }
-            continue;
-        }
+/// Converts `doc` using a reusable `builder` and its options.
+///
+/// The builder is [reset](GeometryBuilder::reset) first and keeps its
+/// allocations afterwards, so a batch of files can be converted without
+/// growing fresh buffers for each one.
+///
+/// # Errors
+///
+/// Returns [`GeometryError`] when conversion fails fatally, as for [`convert`].
+pub fn convert_into(
+    doc: &GerberDoc,
+    builder: &mut GeometryBuilder,
+) -> Result<LayerGeometry, GeometryError> {
+    builder.reset();
+    convert_with_builder(doc, builder, None, GeometryBuilder::build_and_reset).map(|(geom, _)| geom)
+}
 
-        let builder_ref: &mut GeometryBuilder =
-            if let Some((ref mut b, _, _, _, _)) = sr_stack.last_mut() {
-                b
-            } else {
-                &mut builder
-            };
+/// Runs the conversion loop into `builder`; `finish` turns the builder into
+/// the returned geometry, which comes with the interpreter's final state.
+fn convert_with_builder(
+    doc: &GerberDoc,
+    builder: &mut GeometryBuilder,
+    on_warning: Option<&mut dyn FnMut(&str)>,
+    finish: impl FnOnce(&mut GeometryBuilder) -> LayerGeometry,
+) -> Result<(LayerGeometry, ConvertDebug), GeometryError> {
+    let options = *builder.options();
+    let mut warning_stream = WarningStream::new(on_warning);
+    let mut trackers = RangeTrackers::default();
+    let mut sr_stack: Vec<StepRepeatFrame> = Vec::new();
+    let mut interpreter = Interpreter::new(doc);
+    let mut preview_skipped: u32 = 0;
+    let mut tiny_skipped: BTreeMap<i32, u32> = BTreeMap::new();
+    let mut budget = ConversionBudget::new(&options);
+    let mut truncated = false;
+    let mut debug = ConvertDebug::new();
+    let mut apertures = aperture_report::aperture_table(doc);
 
-        match cmd {
-            Command::ExtendedCode(ExtendedCode::CoordinateFormat(cf)) => {
-                state.format = Some(*cf);
-            }
-            Command::ExtendedCode(ExtendedCode::Unit(u)) => {
-                state.units = Some(*u);
-            }
-            Command::ExtendedCode(ExtendedCode::LoadPolarity(gerber_types::Polarity::Dark)) => {
-                polarity_tracker.set_polarity(types::Polarity::Dark, builder_ref);
-            }
-            Command::ExtendedCode(ExtendedCode::LoadPolarity(gerber_types::Polarity::Clear)) => {
-                polarity_tracker.set_polarity(types::Polarity::Clear, builder_ref);
-            }
-            Command::ExtendedCode(ExtendedCode::StepAndRepeat(
-                gerber_types::StepAndRepeat::Open {
-                    repeat_x,
-                    repeat_y,
-                    distance_x,
-                    distance_y,
-                },
-            )) => {
-                sr_stack.push((
-                    types::GeometryBuilder::new(),
-                    *repeat_x,
-                    *repeat_y,
-                    *distance_x,
-                    *distance_y,
-                ));
-            }
-            Command::ExtendedCode(ExtendedCode::ApertureMacro(am)) => {
-                macros.insert(am.name.clone(), am.clone());
-            }
-            Command::FunctionCode(FunctionCode::GCode(gerber_types::GCode::InterpolationMode(
-                mode,
-            ))) => {
-                state.interpolation_mode = match mode {
-                    gerber_types::InterpolationMode::Linear => types::InterpolationMode::Linear,
-                    gerber_types::InterpolationMode::ClockwiseCircular => {
-                        types::InterpolationMode::ClockwiseArc
-                    }
-                    gerber_types::InterpolationMode::CounterclockwiseCircular => {
-                        types::InterpolationMode::CounterClockwiseArc
-                    }
-                };
-            }
-            Command::FunctionCode(FunctionCode::GCode(gerber_types::GCode::RegionMode(true))) => {
-                state.region_mode = true;
-                state.region_points.clear();
+    while let Some((command_index, event)) = interpreter.next_with_command() {
+        builder.set_command_index(Some(command_index));
+        if let Some((block_builder, ..)) = sr_stack.last_mut() {
+            block_builder.set_command_index(Some(command_index));
+        }
+        match event {
+            Event::ParseError(message) => builder.warn(message),
+            Event::StepRepeatClose => {
+                trackers.suspend(active_builder(&mut sr_stack, builder));
+                if let Some(moved) = close_step_repeat(&mut sr_stack, builder, &mut budget)? {
+                    warning_stream.absorb(sr_stack.len(), moved);
+                } else {
+                    builder.warn("SR close without matching open; ignoring".to_string());
+                }
+                trackers.resume(active_builder(&mut sr_stack, builder));
             }
-            Command::FunctionCode(FunctionCode::GCode(gerber_types::GCode::RegionMode(false))) => {
-                region::fill_region(builder_ref, &state.region_points)?;
-                state.region_mode = false;
-                state.region_points.clear();
+            Event::StepRepeatOpen {
+                repeat_x,
+                repeat_y,
+                distance_x,
+                distance_y,
+            } => {
+                // Clear and attribute ranges are recorded in the builder they
+                // index into, so block ranges are replicated with the block's
+                // geometry.
+                trackers.suspend(active_builder(&mut sr_stack, builder));
+                // A new SR open implicitly closes the active block.
+                if let Some(moved) = close_step_repeat(&mut sr_stack, builder, &mut budget)? {
+                    warning_stream.absorb(sr_stack.len(), moved);
+                    warning_stream.flush(sr_stack.len(), active_builder(&mut sr_stack, builder));
+                }
+                let mut block_builder = types::GeometryBuilder::with_options(options);
+                block_builder.set_command_index(Some(command_index));
+                sr_stack.push((block_builder, repeat_x, repeat_y, distance_x, distance_y));
+                warning_stream.enter_block(sr_stack.len());
+                trackers.resume(active_builder(&mut sr_stack, builder));
             }
-            Command::FunctionCode(FunctionCode::GCode(gerber_types::GCode::QuadrantMode(
-                gerber_types::QuadrantMode::Single,
-            ))) => {
-                builder_ref.warn(
-                    "G74 single-quadrant arc mode not supported; using multi-quadrant".to_string(),
-                );
-                arc_quadrant_mode = arc::ArcQuadrantMode::SingleQuadrant;
+            Event::Polarity(polarity) => {
+                debug.final_polarity = polarity;
+                let builder_ref = active_builder(&mut sr_stack, builder);
+                trackers.polarity.set_polarity(polarity, builder_ref);
             }
-            Command::FunctionCode(FunctionCode::GCode(gerber_types::GCode::QuadrantMode(
-                gerber_types::QuadrantMode::Multi,
-            ))) => {
-                arc_quadrant_mode = arc::ArcQuadrantMode::MultiQuadrant;
+            Event::ObjectAttributes(attributes) => {
+                let builder_ref = active_builder(&mut sr_stack, builder);
+                trackers.attributes.set_attributes(attributes, builder_ref);
             }
-            Command::FunctionCode(FunctionCode::DCode(gerber_types::DCode::SelectAperture(n))) => {
-                state.current_aperture = Some(*n);
+            Event::Warning(message) => {
+                active_builder(&mut sr_stack, builder).warn(message);
             }
-            Command::FunctionCode(FunctionCode::DCode(gerber_types::DCode::Operation(
-                Operation::Move(Some(ref c)),
-            ))) => {
-                let pt = coords_to_point(c, &state);
-                state.current_point = pt;
+            Event::CategorizedWarning(category, message) => {
+                active_builder(&mut sr_stack, builder).warn_in(category, message);
             }
-            Command::FunctionCode(FunctionCode::DCode(gerber_types::DCode::Operation(
-                Operation::Flash(Some(ref c)),
-            ))) => {
-                let pt = coords_to_point(c, &state);
-                if let Some(dcode) = state.current_aperture {
-                    if let Some(aperture) = doc.apertures.get(&dcode) {
-                        match aperture {
-                            gerber_types::Aperture::Macro(name, params) => {
-                                if let Some(macro_def) = macros.get(name) {
-                                    let resolved = macro_eval::resolve_macro_params(
-                                        builder_ref,
-                                        params.as_deref(),
-                                    )?;
-                                    macro_eval::evaluate_macro(
-                                        builder_ref,
-                                        macro_def,
-                                        &resolved,
-                                        pt,
-                                    )?;
-                                } else {
-                                    builder_ref.warn(format!(
-                                        "aperture macro `{name}` not defined; skipping flash"
-                                    ));
-                                }
-                            }
-                            _ => {
-                                aperture::flash_aperture(builder_ref, aperture, pt)?;
-                            }
-                        }
-                    } else {
-                        builder_ref.warn(format!("aperture D{dcode} not defined; skipping flash"));
-                    }
-                } else {
-                    builder_ref.warn("flash without selected aperture; skipping".to_string());
-                }
+            Event::Unsupported(feature, message) => {
+                let builder_ref = active_builder(&mut sr_stack, builder);
+                builder_ref.warn(message);
+                builder_ref.record_unsupported(feature);
             }
-            Command::FunctionCode(FunctionCode::DCode(gerber_types::DCode::Operation(
-                Operation::Interpolate(Some(ref c), ref offset),
-            ))) => {
-                let target = coords_to_point(c, &state);
-
-                if state.region_mode {
-                    match state.interpolation_mode {
-                        types::InterpolationMode::Linear => {
-                            state.region_points.push(target);
-                        }
-                        types::InterpolationMode::ClockwiseArc
-                        | types::InterpolationMode::CounterClockwiseArc => {
-                            let center_offset = offset_to_point(offset.as_ref(), &state);
-                            let direction = match state.interpolation_mode {
-                                types::InterpolationMode::CounterClockwiseArc => {
-                                    arc::ArcDirection::CounterClockwise
-                                }
-                                _ => arc::ArcDirection::Clockwise,
-                            };
-                            if let Some(points) = arc::arc_centerline_points(
-                                builder_ref,
-                                state.current_point,
-                                target,
-                                center_offset,
-                                direction,
-                                arc_quadrant_mode,
-                                arc::DEFAULT_REGION_ARC_SEGMENT_LENGTH,
-                            ) {
-                                for pt in points.into_iter().skip(1) {
-                                    state.region_points.push(pt);
-                                }
-                            } else {
-                                state.region_points.push(target);
-                            }
-                        }
-                    }
-                } else if let Some(aperture) =
-                    state.current_aperture.and_then(|d| doc.apertures.get(&d))
+            Event::Op(op) => {
+                debug.count(&op);
+                aperture_report::count_aperture_use(&mut apertures, &op);
+                // Filtered-out operations have already moved the current point.
+                if filter_includes(options.command_filter, &op)
+                    && !record_skip(&options, &op, &mut tiny_skipped, &mut preview_skipped)
                 {
-                    match state.interpolation_mode {
-                        types::InterpolationMode::Linear => {
-                            stroke::draw_linear(
-                                builder_ref,
-                                state.current_point,
-                                target,
-                                aperture,
-                            )?;
-                        }
-                        types::InterpolationMode::ClockwiseArc
-                        | types::InterpolationMode::CounterClockwiseArc => {
-                            let center_offset = offset_to_point(offset.as_ref(), &state);
-                            let direction = match state.interpolation_mode {
-                                types::InterpolationMode::CounterClockwiseArc => {
-                                    arc::ArcDirection::CounterClockwise
-                                }
-                                types::InterpolationMode::ClockwiseArc
-                                | types::InterpolationMode::Linear => arc::ArcDirection::Clockwise,
-                            };
-                            arc::draw_arc(
-                                builder_ref,
-                                state.current_point,
-                                target,
-                                center_offset,
-                                direction,
-                                arc_quadrant_mode,
-                                aperture,
-                            )?;
-                        }
-                    }
-                } else {
-                    builder_ref.warn("interpolate without selected aperture; skipping".to_string());
+                    emit_operation(active_builder(&mut sr_stack, builder), &op)?;
                 }
-
-                state.current_point = target;
             }
-            _ => {}
         }
+        warning_stream.flush(sr_stack.len(), active_builder(&mut sr_stack, builder));
+
+        if let Some(reason) = budget.exhausted(emitted_triangles(builder, &sr_stack)) {
+            stop_conversion(&mut sr_stack, builder, &mut trackers, reason);
+            warning_stream.flush(0, builder);
+            truncated = true;
+            break;
+        }
+    }
+
+    builder.set_command_index(None);
+    interpreter.record_state(&mut debug);
+    debug.step_repeat_depth = types::saturate_u32(sr_stack.len());
+    for warning in skipped_feature_warnings(&options, &tiny_skipped, preview_skipped) {
+        builder.warn(warning);
+        warning_stream.flush(0, builder);
     }
 
-    let ranges: Vec<polarity::ClearRange> = polarity_tracker.finish(&builder);
-    let mut geom = builder.build();
-    geom.command_count = command_count;
+    let (ranges, attribute_ranges) = trackers.finish(builder);
+    let mut geom = finish(builder);
+    options
+        .strict
+        .check(&geom.warnings, &geom.warning_categories)?;
+    geom.attribute_ranges.extend(attribute_ranges);
+    geom.apertures = apertures;
+    interpreter.record_file_info(&mut geom);
     apply_clear_ranges(&mut geom, ranges);
+    apply_output_options(&mut geom, &options, truncated);
+
+    Ok((geom, debug))
+}
+
+/// Trackers whose ranges index into the builder being drawn into, handed
+/// over whenever conversion switches builders at a step-repeat boundary.
+#[derive(Default)]
+struct RangeTrackers {
+    polarity: polarity::PolarityTracker,
+    attributes: attributes::AttributeTracker,
+}
+
+impl RangeTrackers {
+    fn suspend(&mut self, builder: &mut GeometryBuilder) {
+        self.polarity.suspend(builder);
+        self.attributes.suspend(builder);
+    }
+
+    fn resume(&mut self, builder: &GeometryBuilder) {
+        self.polarity.resume(builder);
+        self.attributes.resume(builder);
+    }
+
+    fn finish(
+        self,
+        builder: &GeometryBuilder,
+    ) -> (Vec<polarity::ClearRange>, Vec<attributes::AttributeRange>) {
+        (
+            self.polarity.finish(builder),
+            self.attributes.finish(builder),
+        )
+    }
+}
+
+/// Drops any open step-repeat blocks and records why conversion stopped.
+fn stop_conversion(
+    sr_stack: &mut Vec<StepRepeatFrame>,
+    builder: &mut GeometryBuilder,
+    trackers: &mut RangeTrackers,
+    reason: String,
+) {
+    trackers.suspend(active_builder(sr_stack, builder));
+    sr_stack.clear();
+    trackers.resume(builder);
+    builder.warn(reason);
+}
+
+/// Applies the triangle budget cut and the output transforms requested by
+/// `options` to finished geometry.
+fn apply_output_options(geom: &mut LayerGeometry, options: &TessellationOptions, truncated: bool) {
+    if truncated {
+        if let Some(max_triangles) = options.max_triangles {
+            geom.truncate_triangles(max_triangles);
+        }
+        geom.truncated = true;
+    }
+    if let Some(matrix) = options.layer_transform {
+        geom.transform(matrix);
+    }
+    match options.flip_y {
+        Some(FlipAxis::LayerTop) if geom.vertex_count > 0 => geom.flip_y(geom.bounds.max_y),
+        Some(FlipAxis::At(axis)) => geom.flip_y(axis),
+        _ => {}
+    }
+}
 
-    Ok(geom)
+/// Counts triangles emitted so far, including those in open step-repeat
+/// blocks that have not been replicated yet.
+fn emitted_triangles(builder: &GeometryBuilder, sr_stack: &[StepRepeatFrame]) -> u32 {
+    sr_stack
+        .iter()
+        .fold(builder.index_count() / 3, |total, (block_builder, ..)| {
+            total.saturating_add(block_builder.index_count() / 3)
+        })
 }
 
-fn coords_to_point(coords: &Coordinates, state: &types::GerberState) -> types::Point {
-    let x = coords.x.map_or(state.current_point.x, f64::from);
-    let y = coords.y.map_or(state.current_point.y, f64::from);
+/// Commands processed between checks of the conversion deadline.
+const DEADLINE_CHECK_INTERVAL: u32 = 256;
+
+/// Triangle and time limits for one conversion.
+struct ConversionBudget {
+    max_triangles: Option<u32>,
+    time_budget_ms: u128,
+    deadline: Option<Instant>,
+    until_deadline_check: u32,
+}
 
-    let scale = unit_scale(state.units);
-    types::Point {
-        x: x * scale,
-        y: y * scale,
+impl ConversionBudget {
+    fn new(options: &TessellationOptions) -> Self {
+        Self {
+            max_triangles: options.max_triangles,
+            time_budget_ms: options.time_budget.map_or(0, |budget| budget.as_millis()),
+            deadline: options
+                .time_budget
+                .and_then(|budget| Instant::now().checked_add(budget)),
+            until_deadline_check: 0,
+        }
+    }
+
+    /// Returns the budget left for replicating a step-repeat block into a
+    /// builder, when `others` triangles are held by the other builders.
+    fn replication(&self, others: u32) -> step_repeat::ReplicationBudget {
+        step_repeat::ReplicationBudget {
+            max_triangles: self
+                .max_triangles
+                .map(|max_triangles| max_triangles.saturating_sub(others)),
+            deadline: self.deadline,
+        }
+    }
+
+    /// Makes the next [`Self::exhausted`] call check the deadline.
+    const fn check_deadline_next(&mut self) {
+        self.until_deadline_check = 0;
+    }
+
+    /// Returns why conversion must stop after `triangles` triangles have
+    /// been emitted, or `None` while the budget lasts.
+    fn exhausted(&mut self, triangles: u32) -> Option<String> {
+        if let Some(max_triangles) = self.max_triangles {
+            if triangles > max_triangles {
+                return Some(
+                    GeometryError::Truncated(format!("triangle budget of {max_triangles} reached"))
+                        .to_string(),
+                );
+            }
+        }
+        let deadline = self.deadline?;
+        if self.until_deadline_check > 0 {
+            self.until_deadline_check -= 1;
+            return None;
+        }
+        self.until_deadline_check = DEADLINE_CHECK_INTERVAL;
+        (Instant::now() >= deadline).then(|| {
+            GeometryError::Truncated(format!("time budget of {} ms reached", self.time_budget_ms))
+                .to_string()
+        })
     }
 }
 
-fn offset_to_point(offset: Option<&CoordinateOffset>, state: &types::GerberState) -> types::Point {
-    let Some(off) = offset else {
-        return types::Point { x: 0.0, y: 0.0 };
+/// Returns whether `filter` lets `op` through.
+const fn filter_includes(filter: CommandFilter, op: &ResolvedOp<'_>) -> bool {
+    match op {
+        ResolvedOp::Flash { .. } | ResolvedOp::MacroFlash { .. } => filter.flashes,
+        ResolvedOp::Draw { .. } => filter.draws,
+        ResolvedOp::Arc { .. } => filter.arcs,
+        ResolvedOp::RegionFill { .. } => filter.regions,
+    }
+}
+
+/// Summarizes the features dropped by the minimum feature size, with a
+/// per-D-code breakdown, and by preview mode.
+fn skipped_feature_warnings(
+    options: &TessellationOptions,
+    tiny_skipped: &BTreeMap<i32, u32>,
+    preview_skipped: u32,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !tiny_skipped.is_empty() {
+        let total = tiny_skipped
+            .values()
+            .fold(0u32, |total, count| total.saturating_add(*count));
+        let breakdown: Vec<String> = tiny_skipped
+            .iter()
+            .map(|(dcode, count)| format!("D{dcode}: {count}"))
+            .collect();
+        warnings.push(format!(
+            "skipped {total} features smaller than {} ({})",
+            options.min_feature_size,
+            breakdown.join(", ")
+        ));
+    }
+    if preview_skipped > 0 {
+        warnings.push(format!(
+            "preview mode skipped {preview_skipped} flashes smaller than {}",
+            options.preview_min_feature_size
+        ));
+    }
+    warnings
+}
+
+/// Counts `op` in `tiny_skipped` or `preview_skipped` when the minimum
+/// feature size or the preview limits drop it, returning whether it was
+/// dropped.
+fn record_skip(
+    options: &TessellationOptions,
+    op: &ResolvedOp<'_>,
+    tiny_skipped: &mut BTreeMap<i32, u32>,
+    preview_skipped: &mut u32,
+) -> bool {
+    if let Some(dcode) = below_min_feature_size(options, op) {
+        let count = tiny_skipped.entry(dcode).or_default();
+        *count = count.saturating_add(1);
+    } else if preview_skips(options, op) {
+        *preview_skipped = preview_skipped.saturating_add(1);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Returns the D-code of `op` when it is a flash or stroke whose aperture
+/// is smaller than [`TessellationOptions::min_feature_size`].
+fn below_min_feature_size(options: &TessellationOptions, op: &ResolvedOp<'_>) -> Option<i32> {
+    if options.min_feature_size <= 0.0 {
+        return None;
+    }
+    let (dcode, aperture) = match *op {
+        ResolvedOp::Flash {
+            dcode, aperture, ..
+        }
+        | ResolvedOp::Draw {
+            dcode, aperture, ..
+        }
+        | ResolvedOp::Arc {
+            dcode, aperture, ..
+        } => (dcode, aperture),
+        ResolvedOp::MacroFlash { .. } | ResolvedOp::RegionFill { .. } => return None,
     };
+    aperture::aperture_extent(aperture)
+        .filter(|&extent| extent < options.min_feature_size)
+        .map(|_| dcode)
+}
 
-    let x = off.x.map_or(0.0, f64::from);
-    let y = off.y.map_or(0.0, f64::from);
+/// Returns whether preview mode drops `op` as a feature below the size
+/// threshold.
+fn preview_skips(options: &TessellationOptions, op: &ResolvedOp<'_>) -> bool {
+    match *op {
+        ResolvedOp::Flash { aperture, .. } => {
+            aperture::aperture_extent(aperture).is_some_and(|extent| options.skips_flash(extent))
+        }
+        _ => false,
+    }
+}
+
+/// Emits `op`, naming its D-code in zero-size aperture warnings.
+fn emit_operation(builder: &mut GeometryBuilder, op: &ResolvedOp<'_>) -> Result<(), GeometryError> {
+    builder.set_aperture_use(match *op {
+        ResolvedOp::Flash { dcode, .. } => Some((dcode, "flash")),
+        ResolvedOp::Draw { dcode, .. } => Some((dcode, "stroke")),
+        ResolvedOp::Arc { dcode, .. } => Some((dcode, "arc")),
+        ResolvedOp::MacroFlash { .. } | ResolvedOp::RegionFill { .. } => None,
+    });
+    let result = emit_resolved(builder, op);
+    builder.set_aperture_use(None);
+    result
+}
+
+fn emit_resolved(builder: &mut GeometryBuilder, op: &ResolvedOp<'_>) -> Result<(), GeometryError> {
+    match *op {
+        ResolvedOp::Flash {
+            point, aperture, ..
+        } => aperture::flash_aperture(builder, aperture, point),
+        ResolvedOp::MacroFlash {
+            point,
+            macro_def,
+            params,
+            unit_scale,
+            ..
+        } => {
+            let resolved = macro_eval::resolve_macro_params(builder, params)?;
+            macro_eval::evaluate_macro(builder, macro_def, &resolved, point, unit_scale)
+        }
+        ResolvedOp::Draw {
+            from, to, aperture, ..
+        } => stroke::draw_linear(builder, from, to, aperture),
+        ResolvedOp::Arc {
+            from,
+            to,
+            center_offset,
+            direction,
+            quadrant_mode,
+            aperture,
+            ..
+        } => arc::draw_arc(
+            builder,
+            from,
+            to,
+            center_offset,
+            direction,
+            quadrant_mode,
+            aperture,
+        ),
+        ResolvedOp::RegionFill { ref points } => region::fill_region(builder, points),
+    }
+}
+
+/// Forwards warnings to a callback as builders record them.
+struct WarningStream<'f> {
+    callback: Option<&'f mut dyn FnMut(&str)>,
+    /// Warnings already forwarded per builder depth (0 is the top level).
+    forwarded: Vec<usize>,
+}
+
+impl<'f> WarningStream<'f> {
+    fn new(callback: Option<&'f mut dyn FnMut(&str)>) -> Self {
+        Self {
+            callback,
+            forwarded: Vec::new(),
+        }
+    }
+
+    /// Forwards the warnings `builder` recorded since the last flush at `depth`.
+    fn flush(&mut self, depth: usize, builder: &GeometryBuilder) {
+        let Some(callback) = self.callback.as_mut() else {
+            return;
+        };
+        if self.forwarded.len() <= depth {
+            self.forwarded.resize(depth + 1, 0);
+        }
+        if let Some(forwarded) = self.forwarded.get_mut(depth) {
+            for warning in builder.warnings().iter().skip(*forwarded) {
+                callback(warning);
+            }
+            *forwarded = builder.warnings().len();
+        }
+    }
+
+    /// Marks `count` warnings moved into the builder at `depth` from a closed
+    /// step-repeat block as already forwarded.
+    fn absorb(&mut self, depth: usize, count: usize) {
+        if let Some(forwarded) = self.forwarded.get_mut(depth) {
+            *forwarded += count;
+        }
+    }
+
+    /// Resets the count for a freshly opened step-repeat block at `depth`.
+    fn enter_block(&mut self, depth: usize) {
+        self.forwarded.truncate(depth);
+    }
+}
 
-    let scale = unit_scale(state.units);
-    types::Point {
-        x: x * scale,
-        y: y * scale,
+/// Returns the builder of the innermost open step-repeat block, or the
+/// top-level builder when no block is open.
+fn active_builder<'b>(
+    sr_stack: &'b mut [StepRepeatFrame],
+    builder: &'b mut GeometryBuilder,
+) -> &'b mut GeometryBuilder {
+    if let Some((block_builder, ..)) = sr_stack.last_mut() {
+        block_builder
+    } else {
+        builder
     }
 }
 
-#[allow(clippy::missing_const_for_fn)]
-fn unit_scale(units: Option<Unit>) -> f64 {
-    match units {
-        Some(Unit::Inches) => MM_PER_INCH,
-        Some(Unit::Millimeters) | None => 1.0,
+type StepRepeatFrame = (types::GeometryBuilder, u32, u32, f64, f64);
+
+/// Finalizes the innermost open step-repeat block into its parent builder.
+///
+/// Warnings recorded inside the block are moved to the parent builder.
+/// Replication stops early once the conversion budget is spent, leaving the
+/// truncation to the caller's next budget check.
+/// Returns the number of moved warnings, or `Ok(None)` when no block is open.
+fn close_step_repeat(
+    sr_stack: &mut Vec<StepRepeatFrame>,
+    builder: &mut types::GeometryBuilder,
+    budget: &mut ConversionBudget,
+) -> Result<Option<usize>, GeometryError> {
+    let Some((block_builder, repeat_x, repeat_y, distance_x, distance_y)) = sr_stack.pop() else {
+        return Ok(None);
+    };
+    let held = emitted_triangles(builder, sr_stack);
+
+    let mut block_geom = block_builder.build();
+    let parent_builder = if let Some((ref mut pb, ..)) = sr_stack.last_mut() {
+        pb
+    } else {
+        builder
+    };
+    let block_warnings = std::mem::take(&mut block_geom.warnings);
+    let moved = block_warnings.len();
+    for (i, warning) in block_warnings.into_iter().enumerate() {
+        let command_index = block_geom.warning_commands.get(i).copied().flatten();
+        let category = block_geom.warning_categories.get(i).copied().flatten();
+        parent_builder.record_warning(warning, command_index, category);
+    }
+    for feature in &block_geom.unsupported_features {
+        parent_builder.record_unsupported(feature);
+    }
+    let others = held.saturating_sub(parent_builder.index_count() / 3);
+    let stopped = step_repeat::apply_step_repeat_within(
+        parent_builder,
+        &block_geom,
+        repeat_x,
+        repeat_y,
+        distance_x,
+        distance_y,
+        budget.replication(others),
+    )?;
+    if stopped {
+        budget.check_deadline_next();
     }
+    Ok(Some(moved))
 }
@@ -0,0 +1,862 @@
+//! Gerber interpreter yielding resolved drawing operations.
+//!
+//! Walks the command list of a [`GerberDoc`], tracks interpreter state
+//! (current point, aperture, interpolation and region modes, units), and
+//! yields semantic operations with absolute millimeter coordinates and
+//! resolved apertures. [`super::convert`] consumes the same event stream to
+//! build triangle geometry.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use gerber_parser::GerberDoc;
+use gerber_types::{
+    Aperture, ApertureMacro, AttributeDeletionCriterion, Command, CoordinateFormat, CoordinateMode,
+    CoordinateNumber, CoordinateOffset, Coordinates, DCode, ExtendedCode, FunctionCode, GCode,
+    ImagePolarity, MacroDecimal, Operation, QuadrantMode, StepAndRepeat, Unit, ZeroOmission,
+};
+
+use super::arc::{self, ArcDirection, ArcQuadrantMode};
+use super::attributes::{object_attribute_entry, ObjectAttributes};
+use super::options::WarningCategory;
+use super::types::{
+    saturate_u32, GeometryBuilder, GerberState, InterpolationMode, LayerGeometry, OperationCode,
+    Point, Polarity,
+};
+
+const DEFAULT_FORMAT: (u8, u8) = (2, 6);
+const MM_PER_INCH: f64 = 25.4;
+const REGION_ACROSS_STEP_REPEAT: &str =
+    "region open at step-repeat boundary; closing region before the boundary";
+
+/// A decoded Gerber drawing operation with resolved aperture and absolute
+/// millimeter coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedOp<'a> {
+    /// D03 flash of a standard aperture.
+    Flash {
+        /// Flash position.
+        point: Point,
+        /// Selected D-code.
+        dcode: i32,
+        /// Aperture definition for `dcode`.
+        aperture: &'a Aperture,
+    },
+    /// D03 flash of an aperture macro.
+    MacroFlash {
+        /// Flash position.
+        point: Point,
+        /// Selected D-code.
+        dcode: i32,
+        /// Macro definition referenced by the aperture.
+        macro_def: &'a ApertureMacro,
+        /// Unresolved macro parameters from the aperture definition.
+        params: Option<&'a [MacroDecimal]>,
+        /// Multiplier from file units to millimeters.
+        unit_scale: f64,
+    },
+    /// D01 linear interpolation.
+    Draw {
+        /// Start point.
+        from: Point,
+        /// End point.
+        to: Point,
+        /// Selected D-code.
+        dcode: i32,
+        /// Aperture definition for `dcode`.
+        aperture: &'a Aperture,
+    },
+    /// D01 circular interpolation.
+    Arc {
+        /// Start point.
+        from: Point,
+        /// End point.
+        to: Point,
+        /// Center offset relative to `from`.
+        center_offset: Point,
+        /// Sweep direction.
+        direction: ArcDirection,
+        /// Quadrant mode in effect.
+        quadrant_mode: ArcQuadrantMode,
+        /// Selected D-code.
+        dcode: i32,
+        /// Aperture definition for `dcode`.
+        aperture: &'a Aperture,
+    },
+    /// G36/G37 region with its boundary (arcs already tessellated).
+    RegionFill {
+        /// Boundary points in drawing order.
+        points: Vec<Point>,
+    },
+}
+
+/// Interpreter state at the end of a conversion, returned by
+/// [`super::convert_with_debug`] for diagnosing unexpected geometry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertDebug {
+    /// Current point after the last command, in millimeters.
+    pub final_point: Point,
+    /// D-code selected last.
+    pub last_aperture: Option<i32>,
+    /// Whether a `G36` region was never closed by `G37`. Its contour is
+    /// not filled.
+    pub region_open: bool,
+    /// Boundary points collected for the unclosed region contour.
+    pub region_point_count: u32,
+    /// Number of `%SR` blocks still open after the last command.
+    pub step_repeat_depth: u32,
+    /// Polarity in effect after the last command.
+    pub final_polarity: Polarity,
+    /// Number of D03 flashes, including macro flashes.
+    pub flash_count: u32,
+    /// Number of D01 linear draws.
+    pub draw_count: u32,
+    /// Number of D01 circular draws.
+    pub arc_count: u32,
+    /// Number of region fills.
+    pub region_count: u32,
+}
+
+impl ConvertDebug {
+    /// State before the first command.
+    pub(crate) const fn new() -> Self {
+        Self {
+            final_point: Point { x: 0.0, y: 0.0 },
+            last_aperture: None,
+            region_open: false,
+            region_point_count: 0,
+            step_repeat_depth: 0,
+            final_polarity: Polarity::Dark,
+            flash_count: 0,
+            draw_count: 0,
+            arc_count: 0,
+            region_count: 0,
+        }
+    }
+
+    /// Counts `op` in the per-type totals.
+    pub(crate) fn count(&mut self, op: &ResolvedOp<'_>) {
+        let count = match op {
+            ResolvedOp::Flash { .. } | ResolvedOp::MacroFlash { .. } => &mut self.flash_count,
+            ResolvedOp::Draw { .. } => &mut self.draw_count,
+            ResolvedOp::Arc { .. } => &mut self.arc_count,
+            ResolvedOp::RegionFill { .. } => &mut self.region_count,
+        };
+        *count = count.saturating_add(1);
+    }
+}
+
+/// Interpreter event: a drawing operation or a state change that affects how
+/// operations are rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Event<'a> {
+    /// A drawing operation.
+    Op(ResolvedOp<'a>),
+    /// `%LP` polarity change.
+    Polarity(Polarity),
+    /// `%TO`/`%TD` changed the object attribute dictionary.
+    ObjectAttributes(ObjectAttributes),
+    /// `%SR` block open.
+    StepRepeatOpen {
+        repeat_x: u32,
+        repeat_y: u32,
+        distance_x: f64,
+        distance_y: f64,
+    },
+    /// `%SR*%` block close.
+    StepRepeatClose,
+    /// Interpreter warning tied to the current command.
+    Warning(String),
+    /// Warning in a category that strict mode can promote.
+    CategorizedWarning(WarningCategory, String),
+    /// Warning about a feature that is skipped as unsupported, with the
+    /// feature name for [`super::LayerGeometry::unsupported_features`].
+    Unsupported(&'static str, String),
+    /// Command the parser could not decode.
+    ParseError(String),
+}
+
+/// Returns an iterator over the resolved drawing operations of `doc`.
+///
+/// Runs the same interpreter state machine as [`super::convert`] without
+/// building geometry. Polarity and step-repeat commands are not yielded.
+pub fn operations(doc: &GerberDoc) -> impl Iterator<Item = ResolvedOp<'_>> {
+    Interpreter::new(doc).filter_map(|event| match event {
+        Event::Op(op) => Some(op),
+        _ => None,
+    })
+}
+
+/// Returns the document's aperture definitions ordered by D-code.
+///
+/// `GerberDoc::apertures` is a hash map; exporters and reports should iterate
+/// this list instead so output is identical across runs.
+pub fn sorted_apertures(doc: &GerberDoc) -> Vec<(i32, &Aperture)> {
+    let mut apertures: Vec<(i32, &Aperture)> = doc
+        .apertures
+        .iter()
+        .map(|(dcode, aperture)| (*dcode, aperture))
+        .collect();
+    apertures.sort_by_key(|(dcode, _)| *dcode);
+    apertures
+}
+
+/// Command-by-command Gerber interpreter producing [`Event`]s.
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct Interpreter<'a> {
+    doc: &'a GerberDoc,
+    next_command: usize,
+    state: GerberState,
+    /// Mode of the last `G74`/`G75`. Like the rest of the graphics state it
+    /// is not scoped to `%SR` blocks: a mode set inside a block stays in
+    /// effect after the block closes.
+    quadrant_mode: ArcQuadrantMode,
+    macros: BTreeMap<&'a str, &'a ApertureMacro>,
+    object_attributes: ObjectAttributes,
+    pending: VecDeque<Event<'a>>,
+    emitted_geometry: bool,
+    diagnostics: GeometryBuilder,
+    command_count: u32,
+    format_assumed: bool,
+    units_assumed: bool,
+    image_name: Option<&'a str>,
+    image_negative: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Creates an interpreter positioned before the first command.
+    pub(crate) fn new(doc: &'a GerberDoc) -> Self {
+        let format = doc.format_specification.unwrap_or_else(|| {
+            CoordinateFormat::new(
+                ZeroOmission::Leading,
+                CoordinateMode::Absolute,
+                DEFAULT_FORMAT.0,
+                DEFAULT_FORMAT.1,
+            )
+        });
+
+        let macros = predefined_macros(doc);
+        let mut pending = VecDeque::new();
+        if doc.format_specification.is_none() {
+            pending.push_back(Event::Warning(format!(
+                "no %FS format specification; assuming {}.{} with leading zeros omitted",
+                DEFAULT_FORMAT.0, DEFAULT_FORMAT.1
+            )));
+        }
+        if doc.units.is_none() {
+            pending.push_back(Event::Warning(
+                "no %MO unit specification; assuming millimeters".to_string(),
+            ));
+        }
+        if let Some(message) = undefined_macro_references(doc, &macros) {
+            pending.push_back(Event::Warning(message));
+        }
+
+        Self {
+            doc,
+            next_command: 0,
+            state: GerberState {
+                units: doc.units,
+                format: Some(format),
+                ..GerberState::default()
+            },
+            quadrant_mode: ArcQuadrantMode::MultiQuadrant,
+            macros,
+            object_attributes: ObjectAttributes::new(),
+            pending,
+            emitted_geometry: false,
+            diagnostics: GeometryBuilder::new(),
+            command_count: 0,
+            format_assumed: doc.format_specification.is_none(),
+            units_assumed: doc.units.is_none(),
+            image_name: None,
+            image_negative: false,
+        }
+    }
+
+    /// Number of successfully parsed commands processed so far.
+    pub(crate) const fn command_count(&self) -> u32 {
+        self.command_count
+    }
+
+    /// Copies the file-level state into `geom`: the command count, the
+    /// coordinate format in effect, whether the format or units were
+    /// assumed for lack of `%FS`/`%MO`, and the last `%IN` and `%IP` seen.
+    pub(crate) fn record_file_info(&self, geom: &mut LayerGeometry) {
+        geom.command_count = self.command_count;
+        geom.coordinate_format = self
+            .state
+            .format
+            .map(|format| (format.integer, format.decimal));
+        geom.format_assumed = self.format_assumed;
+        geom.units_assumed = self.units_assumed;
+        geom.layer_name = self.image_name.map(str::to_string);
+        geom.image_negative = self.image_negative;
+    }
+
+    /// Copies the current point, aperture and region state into `debug`.
+    pub(crate) fn record_state(&self, debug: &mut ConvertDebug) {
+        debug.final_point = self.state.current_point;
+        debug.last_aperture = self.state.current_aperture;
+        debug.region_open = self.state.region_mode;
+        debug.region_point_count = saturate_u32(self.state.region_points.len());
+    }
+
+    /// Returns the next event with the zero-based index of the command in
+    /// `doc.commands` that produced it.
+    pub(crate) fn next_with_command(&mut self) -> Option<(u32, Event<'a>)> {
+        let event = self.next()?;
+        let command_index = saturate_u32(self.next_command.saturating_sub(1));
+        Some((command_index, event))
+    }
+
+    fn process(&mut self, cmd: &'a Command) {
+        match cmd {
+            Command::ExtendedCode(code) => self.process_extended(code),
+            Command::FunctionCode(FunctionCode::GCode(code)) => self.process_gcode(code),
+            Command::FunctionCode(FunctionCode::DCode(DCode::SelectAperture(n))) => {
+                self.state.current_aperture = Some(*n);
+            }
+            Command::FunctionCode(FunctionCode::DCode(DCode::Operation(operation))) => {
+                self.process_operation(operation);
+            }
+            Command::FunctionCode(FunctionCode::MCode(_)) => {}
+        }
+    }
+
+    fn process_extended(&mut self, code: &'a ExtendedCode) {
+        match code {
+            ExtendedCode::CoordinateFormat(cf) => {
+                self.state.format = Some(*cf);
+                self.format_assumed = false;
+            }
+            ExtendedCode::Unit(u) => {
+                if self.emitted_geometry {
+                    let code = match u {
+                        Unit::Inches => "IN",
+                        Unit::Millimeters => "MM",
+                    };
+                    self.warn_in(
+                        WarningCategory::MixedUnits,
+                        format!(
+                            "units %MO{code}*% declared after geometry; earlier geometry keeps the previous scale"
+                        ),
+                    );
+                }
+                self.state.units = Some(*u);
+                self.units_assumed = false;
+            }
+            ExtendedCode::LoadPolarity(gerber_types::Polarity::Dark) => {
+                self.pending.push_back(Event::Polarity(Polarity::Dark));
+            }
+            ExtendedCode::LoadPolarity(gerber_types::Polarity::Clear) => {
+                self.pending.push_back(Event::Polarity(Polarity::Clear));
+            }
+            ExtendedCode::StepAndRepeat(StepAndRepeat::Open {
+                repeat_x,
+                repeat_y,
+                distance_x,
+                distance_y,
+            }) => {
+                self.close_region_at_step_repeat();
+                self.pending.push_back(Event::StepRepeatOpen {
+                    repeat_x: *repeat_x,
+                    repeat_y: *repeat_y,
+                    distance_x: *distance_x,
+                    distance_y: *distance_y,
+                });
+            }
+            ExtendedCode::StepAndRepeat(StepAndRepeat::Close) => {
+                self.close_region_at_step_repeat();
+                self.pending.push_back(Event::StepRepeatClose);
+            }
+            ExtendedCode::ApertureMacro(am) => {
+                self.macros.insert(am.name.as_str(), am);
+            }
+            ExtendedCode::ImageName(name) => self.image_name = Some(name.name.as_str()),
+            ExtendedCode::ImagePolarity(polarity) => {
+                self.image_negative = matches!(polarity, ImagePolarity::Negative);
+            }
+            ExtendedCode::ObjectAttribute(attribute) => {
+                let (name, values) = object_attribute_entry(attribute);
+                self.object_attributes.insert(name, values);
+                self.push_object_attributes();
+            }
+            ExtendedCode::DeleteAttribute(criterion) => {
+                match criterion {
+                    AttributeDeletionCriterion::SingleObjectAttribute(name) => {
+                        self.object_attributes.remove(name);
+                    }
+                    AttributeDeletionCriterion::AllApertureAndObjectAttributes => {
+                        self.object_attributes.clear();
+                    }
+                    AttributeDeletionCriterion::SingleApertureAttribute(_) => return,
+                }
+                self.push_object_attributes();
+            }
+            _ => {}
+        }
+    }
+
+    fn push_object_attributes(&mut self) {
+        self.pending
+            .push_back(Event::ObjectAttributes(self.object_attributes.clone()));
+    }
+
+    fn process_gcode(&mut self, code: &GCode) {
+        match code {
+            GCode::InterpolationMode(mode) => {
+                self.state.interpolation_mode = match mode {
+                    gerber_types::InterpolationMode::Linear => InterpolationMode::Linear,
+                    gerber_types::InterpolationMode::ClockwiseCircular => {
+                        InterpolationMode::ClockwiseArc
+                    }
+                    gerber_types::InterpolationMode::CounterclockwiseCircular => {
+                        InterpolationMode::CounterClockwiseArc
+                    }
+                };
+            }
+            GCode::RegionMode(true) => {
+                self.state.region_mode = true;
+                self.state.region_points.clear();
+            }
+            GCode::RegionMode(false) => {
+                if self.state.region_mode {
+                    self.close_region();
+                } else {
+                    self.warn("G37 without open region; ignoring".to_string());
+                }
+            }
+            GCode::QuadrantMode(QuadrantMode::Single) => {
+                self.warn(
+                    "G74 single-quadrant arc mode not supported; using multi-quadrant".to_string(),
+                );
+                self.quadrant_mode = ArcQuadrantMode::SingleQuadrant;
+            }
+            GCode::QuadrantMode(QuadrantMode::Multi) => {
+                self.quadrant_mode = ArcQuadrantMode::MultiQuadrant;
+            }
+            GCode::CoordinateMode(mode) => {
+                if let Some(format) = self.state.format.as_mut() {
+                    format.coordinate_mode = *mode;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn process_operation(&mut self, operation: &Operation) {
+        self.state.modal_operation = Some(match operation {
+            Operation::Interpolate(..) => OperationCode::Interpolate,
+            Operation::Move(_) => OperationCode::Move,
+            Operation::Flash(_) => OperationCode::Flash,
+        });
+        match operation {
+            Operation::Move(c) => {
+                let target = self.target_point(c.as_ref());
+                if self.state.region_mode {
+                    self.start_contour(target);
+                }
+                self.state.current_point = target;
+            }
+            Operation::Flash(c) => {
+                let point = self.target_point(c.as_ref());
+                self.flash(point);
+            }
+            Operation::Interpolate(c, offset) => {
+                let target = self.target_point(c.as_ref());
+                self.interpolate(target, offset.as_ref());
+                self.state.current_point = target;
+            }
+        }
+    }
+
+    /// Recovers a deprecated coordinate block without D-code, which the
+    /// parser reports as an error, as a repeat of the last explicit
+    /// operation. Returns the block, its interpolation mode if it starts
+    /// with one, and the operation to apply.
+    fn modal_block<'e>(&self, error: &'e str) -> Option<(&'e str, Option<GCode>, Operation)> {
+        let modal = self.state.modal_operation?;
+        let format = self.state.format?;
+        let (block, mode, mut fields) = error
+            .split(|ch: char| !ch.is_ascii_alphanumeric() && !matches!(ch, '+' | '-'))
+            .rev()
+            .find_map(|token| {
+                bare_coordinates(token).map(|(mode, fields)| (token, mode, fields))
+            })?;
+
+        let mut values = [None; 4];
+        while let Some(axis) = fields.chars().next() {
+            let rest = fields.get(1..)?;
+            let end = rest.find(['X', 'Y', 'I', 'J']).unwrap_or(rest.len());
+            let slot = values.get_mut("XYIJ".find(axis)?)?;
+            *slot = Some(coordinate_number(rest.get(..end)?, format)?);
+            fields = rest.get(end..)?;
+        }
+        let [x, y, i, j] = values;
+        let coords = (x.is_some() || y.is_some()).then(|| Coordinates::new(x, y, format));
+        let operation = match modal {
+            OperationCode::Interpolate => {
+                let offset =
+                    (i.is_some() || j.is_some()).then(|| CoordinateOffset::new(i, j, format));
+                Operation::Interpolate(coords, offset)
+            }
+            OperationCode::Move => Operation::Move(coords),
+            OperationCode::Flash => Operation::Flash(coords),
+        };
+        Some((block, mode, operation))
+    }
+
+    /// Resolves the target of a D01/D02/D03. An operation without
+    /// coordinates acts at the current point.
+    fn target_point(&self, coords: Option<&Coordinates>) -> Point {
+        coords.map_or(self.state.current_point, |c| {
+            coords_to_point(c, &self.state)
+        })
+    }
+
+    fn flash(&mut self, point: Point) {
+        let Some(dcode) = self.state.current_aperture else {
+            self.warn_in(
+                WarningCategory::UndefinedAperture,
+                "flash without selected aperture; skipping".to_string(),
+            );
+            return;
+        };
+        let Some(aperture) = self.doc.apertures.get(&dcode) else {
+            self.warn_in(
+                WarningCategory::UndefinedAperture,
+                format!("aperture D{dcode} not defined; skipping flash"),
+            );
+            return;
+        };
+
+        let op = if let Aperture::Macro(name, params) = aperture {
+            let Some(macro_def) = self.macros.get(name.as_str()).copied() else {
+                self.warn_in(
+                    WarningCategory::UndefinedAperture,
+                    format!("aperture macro `{name}` not defined; skipping flash"),
+                );
+                return;
+            };
+            ResolvedOp::MacroFlash {
+                point,
+                dcode,
+                macro_def,
+                params: params.as_deref(),
+                unit_scale: unit_scale(self.state.units),
+            }
+        } else {
+            ResolvedOp::Flash {
+                point,
+                dcode,
+                aperture,
+            }
+        };
+        self.push_op(op);
+    }
+
+    fn interpolate(&mut self, target: Point, offset: Option<&CoordinateOffset>) {
+        let from = self.state.current_point;
+        let direction = match self.state.interpolation_mode {
+            InterpolationMode::Linear => None,
+            InterpolationMode::ClockwiseArc => Some(ArcDirection::Clockwise),
+            InterpolationMode::CounterClockwiseArc => Some(ArcDirection::CounterClockwise),
+        };
+
+        if self.state.region_mode {
+            if self.state.region_points.is_empty() {
+                self.state.region_points.push(from);
+            }
+            let Some(direction) = direction else {
+                self.state.region_points.push(target);
+                return;
+            };
+            let center_offset = offset_to_point(offset, &self.state);
+            let points = arc::arc_centerline_points(
+                &mut self.diagnostics,
+                from,
+                target,
+                center_offset,
+                direction,
+                self.quadrant_mode,
+                arc::DEFAULT_REGION_ARC_SEGMENT_LENGTH,
+            );
+            self.flush_diagnostics();
+            if let Some(points) = points {
+                self.state.region_points.extend(points.into_iter().skip(1));
+                // The tessellated end lands on `target` only up to rounding;
+                // snap it so the contour, and a full-circle region's ring,
+                // closes exactly.
+                if let Some(last) = self.state.region_points.last_mut() {
+                    *last = target;
+                }
+            } else {
+                self.state.region_points.push(target);
+            }
+            return;
+        }
+
+        let Some((dcode, aperture)) = self
+            .state
+            .current_aperture
+            .and_then(|d| self.doc.apertures.get(&d).map(|aperture| (d, aperture)))
+        else {
+            self.warn_in(
+                WarningCategory::UndefinedAperture,
+                "interpolate without selected aperture; skipping".to_string(),
+            );
+            return;
+        };
+
+        if let Aperture::Macro(name, _) = aperture {
+            let kind = if direction.is_some() { "arc" } else { "draw" };
+            if self.macros.contains_key(name.as_str()) {
+                let message = format!("aperture macro `{name}` cannot be stroked; skipping {kind}");
+                self.pending
+                    .push_back(Event::Unsupported("macro aperture stroke", message));
+            } else {
+                self.warn_in(
+                    WarningCategory::UndefinedAperture,
+                    format!("aperture macro `{name}` not defined; skipping {kind}"),
+                );
+            }
+            return;
+        }
+
+        let op = match direction {
+            None => ResolvedOp::Draw {
+                from,
+                to: target,
+                dcode,
+                aperture,
+            },
+            Some(direction) => ResolvedOp::Arc {
+                from,
+                to: target,
+                center_offset: offset_to_point(offset, &self.state),
+                direction,
+                quadrant_mode: self.quadrant_mode,
+                dcode,
+                aperture,
+            },
+        };
+        self.push_op(op);
+    }
+
+    fn close_region(&mut self) {
+        let points = std::mem::take(&mut self.state.region_points);
+        self.state.region_mode = false;
+        self.push_op(ResolvedOp::RegionFill { points });
+    }
+
+    /// Closes a region left open at an `%SR` boundary so its contour is
+    /// filled in the block it was started in.
+    fn close_region_at_step_repeat(&mut self) {
+        if self.state.region_mode {
+            self.warn(REGION_ACROSS_STEP_REPEAT.to_string());
+            self.close_region();
+        }
+    }
+
+    /// Begins a region contour at `start`. A D02 inside a region ends the
+    /// contour drawn so far, which is emitted as its own fill.
+    fn start_contour(&mut self, start: Point) {
+        if self.state.region_points.len() > 1 {
+            let points = std::mem::take(&mut self.state.region_points);
+            self.push_op(ResolvedOp::RegionFill { points });
+        }
+        self.state.region_points.clear();
+        self.state.region_points.push(start);
+    }
+
+    fn push_op(&mut self, op: ResolvedOp<'a>) {
+        self.emitted_geometry = true;
+        self.pending.push_back(Event::Op(op));
+    }
+
+    fn warn(&mut self, message: String) {
+        self.pending.push_back(Event::Warning(message));
+    }
+
+    fn warn_in(&mut self, category: WarningCategory, message: String) {
+        self.pending
+            .push_back(Event::CategorizedWarning(category, message));
+    }
+
+    fn flush_diagnostics(&mut self) {
+        for message in self.diagnostics.take_warnings() {
+            self.pending.push_back(Event::Warning(message));
+        }
+    }
+}
+
+impl<'a> Iterator for Interpreter<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Pending events all come from the last processed command.
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let doc = self.doc;
+            let cmd_result = doc.commands.get(self.next_command)?;
+            self.next_command += 1;
+            match cmd_result {
+                Ok(cmd) => {
+                    self.command_count = self.command_count.saturating_add(1);
+                    self.process(cmd);
+                }
+                Err(e) => {
+                    let error = format!("{e:?}");
+                    if let Some((block, mode, operation)) = self.modal_block(&error) {
+                        self.command_count = self.command_count.saturating_add(1);
+                        let code = match operation {
+                            Operation::Interpolate(..) => "D01",
+                            Operation::Move(_) => "D02",
+                            Operation::Flash(_) => "D03",
+                        };
+                        self.warn(format!(
+                            "coordinate block `{block}` without D-code repeats {code} (deprecated)"
+                        ));
+                        if let Some(mode) = mode {
+                            self.process_gcode(&mode);
+                        }
+                        self.process_operation(&operation);
+                    } else {
+                        self.pending
+                            .push_back(Event::ParseError(format!("parse error: {error}")));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collects every macro defined in `doc`, keeping the first definition of
+/// each name, so a flash resolves its macro even when the `%AM` command comes
+/// later in the file. Definitions still replace earlier ones as they are
+/// reached, so redefinitions apply from their position onward.
+pub(crate) fn predefined_macros(doc: &GerberDoc) -> BTreeMap<&str, &ApertureMacro> {
+    let mut macros = BTreeMap::new();
+    for cmd in doc.commands.iter().flatten() {
+        if let Command::ExtendedCode(ExtendedCode::ApertureMacro(am)) = cmd {
+            macros.entry(am.name.as_str()).or_insert(am);
+        }
+    }
+    macros
+}
+
+/// Lists every aperture whose macro is not defined anywhere in `doc` as one
+/// warning, so a truncated file reports all broken apertures up front rather
+/// than only those it happens to use.
+fn undefined_macro_references(
+    doc: &GerberDoc,
+    macros: &BTreeMap<&str, &ApertureMacro>,
+) -> Option<String> {
+    let references: Vec<String> = sorted_apertures(doc)
+        .into_iter()
+        .filter_map(|(dcode, aperture)| match aperture {
+            Aperture::Macro(name, _) if !macros.contains_key(name.as_str()) => {
+                Some(format!("D{dcode} (`{name}`)"))
+            }
+            _ => None,
+        })
+        .collect();
+    (!references.is_empty())
+        .then(|| format!("undefined macro referenced by {}", references.join(", ")))
+}
+
+/// Resolves D-code coordinates to a point in millimetres.
+///
+/// A missing axis keeps the current point's value. In incremental mode
+/// (`G91` or an incremental format specification) the coordinates are
+/// deltas from the current point; arc offsets are relative in either mode.
+fn coords_to_point(coords: &Coordinates, state: &GerberState) -> Point {
+    let incremental = state
+        .format
+        .is_some_and(|format| format.coordinate_mode == CoordinateMode::Incremental);
+    let scale = unit_scale(state.units);
+    let resolve = |value: Option<_>, current: f64| match value {
+        Some(value) if incremental => f64::from(value).mul_add(scale, current),
+        Some(value) => f64::from(value) * scale,
+        None => current,
+    };
+    Point {
+        x: resolve(coords.x, state.current_point.x),
+        y: resolve(coords.y, state.current_point.y),
+    }
+}
+
+fn offset_to_point(offset: Option<&CoordinateOffset>, state: &GerberState) -> Point {
+    let Some(off) = offset else {
+        return Point { x: 0.0, y: 0.0 };
+    };
+
+    let x = off.x.map_or(0.0, f64::from);
+    let y = off.y.map_or(0.0, f64::from);
+
+    let scale = unit_scale(state.units);
+    Point {
+        x: x * scale,
+        y: y * scale,
+    }
+}
+
+/// Splits a coordinate block without D-code, such as `G01X100Y200`, into
+/// its optional interpolation mode and its coordinate fields.
+fn bare_coordinates(token: &str) -> Option<(Option<GCode>, &str)> {
+    use gerber_types::InterpolationMode::{ClockwiseCircular, CounterclockwiseCircular, Linear};
+
+    let (mode, fields) = [
+        ("G01", Linear),
+        ("G02", ClockwiseCircular),
+        ("G03", CounterclockwiseCircular),
+        ("G1", Linear),
+        ("G2", ClockwiseCircular),
+        ("G3", CounterclockwiseCircular),
+    ]
+    .into_iter()
+    .find_map(|(prefix, mode)| token.strip_prefix(prefix).map(|rest| (Some(mode), rest)))
+    .unwrap_or((None, token));
+    let valid = fields.starts_with(['X', 'Y', 'I', 'J'])
+        && fields.bytes().any(|b| b.is_ascii_digit())
+        && fields
+            .bytes()
+            .all(|b| matches!(b, b'X' | b'Y' | b'I' | b'J' | b'+' | b'-' | b'0'..=b'9'));
+    valid.then_some((mode.map(GCode::InterpolationMode), fields))
+}
+
+/// Reads the digits of one coordinate field in the given `%FS` format.
+fn coordinate_number(raw: &str, format: CoordinateFormat) -> Option<CoordinateNumber> {
+    let digits = raw.strip_prefix(['+', '-']).unwrap_or(raw);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let width = usize::from(format.integer) + usize::from(format.decimal);
+    let value: i64 = match format.zero_omission {
+        ZeroOmission::Leading => digits.parse().ok()?,
+        ZeroOmission::Trailing => format!("{digits:0<width$}").parse().ok()?,
+    };
+    // CoordinateNumber holds millionths of a unit.
+    let decimal = u32::from(format.decimal);
+    let millionths = if decimal <= 6 {
+        value.checked_mul(10_i64.checked_pow(6 - decimal)?)?
+    } else {
+        value / 10_i64.checked_pow(decimal - 6)?
+    };
+    Some(CoordinateNumber::new(if raw.starts_with('-') {
+        -millionths
+    } else {
+        millionths
+    }))
+}
+
+#[allow(clippy::missing_const_for_fn)]
+fn unit_scale(units: Option<Unit>) -> f64 {
+    match units {
+        Some(Unit::Inches) => MM_PER_INCH,
+        Some(Unit::Millimeters) | None => 1.0,
+    }
+}
@@ -0,0 +1,418 @@
+//! Tessellation options that tune how strokes and shapes are emitted.
+//!
+//! Options are carried by [`super::types::GeometryBuilder`] so every emitter
+//! can read them without extra parameters.
+
+use std::time::Duration;
+
+use crate::error::GeometryError;
+
+use super::triangulate::TriangulatorKind;
+
+/// End treatment for strokes drawn with a circular aperture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CapStyle {
+    /// Semicircle endcaps, matching the Gerber specification (default).
+    #[default]
+    Round,
+    /// Flat ends flush with the segment endpoints.
+    Butt,
+    /// Flat ends extended by half the stroke width past each endpoint.
+    Square,
+}
+
+/// Horizontal axis that [`TessellationOptions::flip_y`] mirrors about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlipAxis {
+    /// The layer's maximum Y, so its top edge lands on `y = 0`.
+    LayerTop,
+    /// The given Y, which lands on `y = 0`.
+    At(f64),
+}
+
+/// Kinds of drawing operation that [`TessellationOptions::command_filter`]
+/// lets through.
+///
+/// Excluded operations emit nothing but still move the current point, so
+/// the operations that remain land where they would in a full conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CommandFilter {
+    /// D03 flashes of standard and macro apertures.
+    pub flashes: bool,
+    /// D01 linear draws.
+    pub draws: bool,
+    /// G36/G37 region fills.
+    pub regions: bool,
+    /// D01 circular draws.
+    pub arcs: bool,
+}
+
+impl CommandFilter {
+    /// Every operation (default).
+    pub const ALL: Self = Self {
+        flashes: true,
+        draws: true,
+        regions: true,
+        arcs: true,
+    };
+
+    /// Pads only: flashes.
+    pub const FLASHES_ONLY: Self = Self {
+        flashes: true,
+        draws: false,
+        regions: false,
+        arcs: false,
+    };
+
+    /// Traces only: linear and circular draws.
+    pub const TRACES_ONLY: Self = Self {
+        flashes: false,
+        draws: true,
+        regions: false,
+        arcs: true,
+    };
+}
+
+impl Default for CommandFilter {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Category of a warning that [`StrictMode`] can promote to an error.
+///
+/// Recorded next to the message when the warning is raised, in
+/// [`super::types::LayerGeometry::warning_categories`] and
+/// [`crate::excellon::ExcellonResult::warning_categories`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningCategory {
+    /// A flash or draw references an undefined or missing aperture.
+    UndefinedAperture,
+    /// A drill hit references an undefined tool.
+    UndefinedTool,
+    /// A region contour had to be closed automatically.
+    AutoClosedRegion,
+    /// The file switches units part way through.
+    MixedUnits,
+}
+
+/// Warning categories that [`TessellationOptions::strict`] promotes to
+/// errors.
+///
+/// Warnings are matched by the [`WarningCategory`] they were raised with,
+/// so each category covers both Gerber conversion and Excellon parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StrictMode {
+    /// Flashes and draws that reference an undefined or missing aperture.
+    pub undefined_aperture: bool,
+    /// Drill hits that reference an undefined tool.
+    pub undefined_tool: bool,
+    /// Region contours that had to be closed automatically.
+    pub auto_closed_region: bool,
+    /// Files that switch units part way through.
+    pub mixed_units: bool,
+}
+
+impl StrictMode {
+    /// Lenient conversion: every condition stays a warning (default).
+    pub const OFF: Self = Self {
+        undefined_aperture: false,
+        undefined_tool: false,
+        auto_closed_region: false,
+        mixed_units: false,
+    };
+
+    /// Every category is an error.
+    pub const ALL: Self = Self {
+        undefined_aperture: true,
+        undefined_tool: true,
+        auto_closed_region: true,
+        mixed_units: true,
+    };
+
+    /// Returns whether `category` is promoted.
+    #[must_use]
+    pub const fn promotes(&self, category: WarningCategory) -> bool {
+        match category {
+            WarningCategory::UndefinedAperture => self.undefined_aperture,
+            WarningCategory::UndefinedTool => self.undefined_tool,
+            WarningCategory::AutoClosedRegion => self.auto_closed_region,
+            WarningCategory::MixedUnits => self.mixed_units,
+        }
+    }
+
+    /// Fails with every promoted warning in `warnings`, in order.
+    /// `categories` runs parallel to `warnings`; missing entries are
+    /// uncategorized.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GeometryError::StrictViolation`] if any warning is promoted.
+    pub fn check(
+        &self,
+        warnings: &[String],
+        categories: &[Option<WarningCategory>],
+    ) -> Result<(), GeometryError> {
+        let violations: Vec<String> = warnings
+            .iter()
+            .zip(categories)
+            .filter(|(_, category)| category.is_some_and(|category| self.promotes(category)))
+            .map(|(warning, _)| warning.clone())
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(GeometryError::StrictViolation(violations))
+        }
+    }
+}
+
+impl Default for StrictMode {
+    fn default() -> Self {
+        Self::OFF
+    }
+}
+
+/// Default upper bound on step-repeat instances per block.
+pub const DEFAULT_MAX_STEP_REPEAT_INSTANCES: u64 = 1_000_000;
+
+/// Default gap, in layer units, below which a region contour counts as
+/// closed. Well under any Gerber coordinate resolution, but above the error
+/// of unit scaling and arc tessellation.
+pub const DEFAULT_REGION_CLOSE_TOLERANCE: f64 = 1e-6;
+
+/// Default upper bound on the segments tessellated for a single arc.
+pub const DEFAULT_MAX_ARC_SEGMENTS: u32 = 4096;
+
+/// Preview mode divides circle, endcap, and arc segment counts by this factor.
+pub const PREVIEW_SEGMENT_DIVISOR: u32 = 4;
+
+/// Fewest segments preview mode uses for any curved outline.
+pub const PREVIEW_MIN_SEGMENTS: u32 = 4;
+
+/// Options controlling geometry tessellation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TessellationOptions {
+    /// End treatment for circular-aperture strokes.
+    pub cap_style: CapStyle,
+    /// Maximum `repeat_x * repeat_y` accepted for a step-repeat block.
+    pub max_step_repeat_instances: u64,
+    /// Also retain vertex positions at full `f64` precision.
+    ///
+    /// The `f32` buffer loses sub-micron precision on large boards. Enabling
+    /// this keeps a parallel `f64` buffer for measurement tooling, which costs
+    /// twice the memory of the `f32` positions on top of them.
+    pub keep_f64_positions: bool,
+    /// Trade fidelity for speed: fewer curve segments and no tiny flashes.
+    pub preview: bool,
+    /// In preview mode, flashes whose aperture extent is below this size
+    /// (in layer units) are skipped.
+    pub preview_min_feature_size: f64,
+    /// Stop converting once this many triangles have been emitted.
+    pub max_triangles: Option<u32>,
+    /// Stop converting once this much wall-clock time has elapsed.
+    pub time_budget: Option<Duration>,
+    /// Backend that triangulates regions and outline primitives.
+    pub triangulator: TriangulatorKind,
+    /// Emit screen-space positions with Y pointing down, `y' = axis - y`.
+    pub flip_y: Option<FlipAxis>,
+    /// Drawing operations to emit; the rest are skipped.
+    pub command_filter: CommandFilter,
+    /// Warning categories that fail the conversion instead.
+    pub strict: StrictMode,
+    /// Also record fans in [`super::types::LayerGeometry::fan_ranges`] for
+    /// renderers that draw them as `TRIANGLE_FAN` primitives.
+    pub record_fans: bool,
+    /// Largest gap between the first and last point of a region contour
+    /// that still counts as closed; wider gaps are auto-closed with a
+    /// warning.
+    pub region_close_tolerance: f64,
+    /// Affine matrix `[a, b, c, d, e, f]` applied to the whole layer after
+    /// conversion, such as [`super::transform::layer_affine`] to view the
+    /// bottom side mirrored. Applied before [`Self::flip_y`].
+    pub layer_transform: Option<[f64; 6]>,
+    /// Flashes and strokes whose aperture extent (in layer units) is below
+    /// this size are dropped as spurious, independent of preview mode.
+    /// `0.0` keeps everything.
+    pub min_feature_size: f64,
+    /// Most segments a single arc is tessellated into. Arcs that would need
+    /// more, such as a huge radius at a fine segment length, are clamped with
+    /// a warning.
+    pub max_arc_segments: u32,
+}
+
+impl TessellationOptions {
+    /// Creates the default options, which reproduce spec-accurate output.
+    pub const fn new() -> Self {
+        Self {
+            cap_style: CapStyle::Round,
+            max_step_repeat_instances: DEFAULT_MAX_STEP_REPEAT_INSTANCES,
+            keep_f64_positions: false,
+            preview: false,
+            preview_min_feature_size: 0.0,
+            max_triangles: None,
+            time_budget: None,
+            triangulator: TriangulatorKind::Earclip,
+            flip_y: None,
+            command_filter: CommandFilter::ALL,
+            strict: StrictMode::OFF,
+            record_fans: false,
+            region_close_tolerance: DEFAULT_REGION_CLOSE_TOLERANCE,
+            layer_transform: None,
+            min_feature_size: 0.0,
+            max_arc_segments: DEFAULT_MAX_ARC_SEGMENTS,
+        }
+    }
+
+    /// Returns a copy with the given cap style.
+    #[must_use]
+    pub const fn with_cap_style(mut self, cap_style: CapStyle) -> Self {
+        self.cap_style = cap_style;
+        self
+    }
+
+    /// Returns a copy with the given step-repeat instance cap.
+    #[must_use]
+    pub const fn with_max_step_repeat_instances(mut self, max_instances: u64) -> Self {
+        self.max_step_repeat_instances = max_instances;
+        self
+    }
+
+    /// Returns a copy that does or does not retain `f64` positions.
+    #[must_use]
+    pub const fn with_f64_positions(mut self, keep: bool) -> Self {
+        self.keep_f64_positions = keep;
+        self
+    }
+
+    /// Returns a copy in preview mode, skipping flashes smaller than
+    /// `min_feature_size`.
+    #[must_use]
+    pub const fn with_preview(mut self, min_feature_size: f64) -> Self {
+        self.preview = true;
+        self.preview_min_feature_size = min_feature_size;
+        self
+    }
+
+    /// Returns a copy that stops converting after `max_triangles` triangles,
+    /// keeping the partial geometry.
+    #[must_use]
+    pub const fn with_max_triangles(mut self, max_triangles: u32) -> Self {
+        self.max_triangles = Some(max_triangles);
+        self
+    }
+
+    /// Returns a copy that stops converting once `budget` has elapsed,
+    /// keeping the partial geometry.
+    #[must_use]
+    pub const fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Returns a copy that triangulates regions and outline primitives with
+    /// `triangulator`.
+    #[must_use]
+    pub const fn with_triangulator(mut self, triangulator: TriangulatorKind) -> Self {
+        self.triangulator = triangulator;
+        self
+    }
+
+    /// Returns a copy that emits geometry with Y flipped about `axis`.
+    #[must_use]
+    pub const fn with_flip_y(mut self, axis: FlipAxis) -> Self {
+        self.flip_y = Some(axis);
+        self
+    }
+
+    /// Returns a copy that emits only the operations `filter` includes.
+    #[must_use]
+    pub const fn with_command_filter(mut self, filter: CommandFilter) -> Self {
+        self.command_filter = filter;
+        self
+    }
+
+    /// Returns a copy that fails on warnings in the categories `strict`
+    /// promotes.
+    #[must_use]
+    pub const fn with_strict(mut self, strict: StrictMode) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns a copy that does or does not record fan ranges.
+    #[must_use]
+    pub const fn with_fan_ranges(mut self, record: bool) -> Self {
+        self.record_fans = record;
+        self
+    }
+
+    /// Returns a copy with the given region close tolerance.
+    #[must_use]
+    pub const fn with_region_close_tolerance(mut self, tolerance: f64) -> Self {
+        self.region_close_tolerance = tolerance;
+        self
+    }
+
+    /// Returns a copy that applies `matrix` to the converted layer.
+    #[must_use]
+    pub const fn with_layer_transform(mut self, matrix: [f64; 6]) -> Self {
+        self.layer_transform = Some(matrix);
+        self
+    }
+
+    /// Returns a copy that drops features smaller than `min_feature_size`.
+    #[must_use]
+    pub const fn with_min_feature_size(mut self, min_feature_size: f64) -> Self {
+        self.min_feature_size = min_feature_size;
+        self
+    }
+
+    /// Returns a copy with the given per-arc segment cap.
+    #[must_use]
+    pub const fn with_max_arc_segments(mut self, max_segments: u32) -> Self {
+        self.max_arc_segments = max_segments;
+        self
+    }
+
+    /// Returns the segment count to use for a curve that gets
+    /// `full_quality` segments outside preview mode.
+    #[must_use]
+    pub const fn curve_segments(&self, full_quality: u32) -> u32 {
+        if !self.preview {
+            return full_quality;
+        }
+        let reduced = full_quality / PREVIEW_SEGMENT_DIVISOR;
+        if reduced < PREVIEW_MIN_SEGMENTS {
+            PREVIEW_MIN_SEGMENTS
+        } else {
+            reduced
+        }
+    }
+
+    /// Returns the maximum arc segment length to use in place of
+    /// `full_quality`.
+    #[must_use]
+    pub fn arc_segment_length(&self, full_quality: f64) -> f64 {
+        if self.preview {
+            full_quality * f64::from(PREVIEW_SEGMENT_DIVISOR)
+        } else {
+            full_quality
+        }
+    }
+
+    /// Returns whether a flash with the given aperture extent is dropped by
+    /// preview mode.
+    #[must_use]
+    pub fn skips_flash(&self, extent: f64) -> bool {
+        self.preview && extent < self.preview_min_feature_size
+    }
+}
+
+impl Default for TessellationOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,15 +3,28 @@
 //! Tracks dark vs clear polarity and records index ranges for clear-polarity
 //! geometry so the renderer can apply background color.
 
+use serde::Serialize;
+
 use super::types::{GeometryBuilder, LayerGeometry, Polarity};
 
+/// What produced a clear range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ClearRangeSource {
+    /// Geometry drawn under `%LPC*%` load polarity.
+    Polarity,
+    /// An aperture macro primitive with exposure off.
+    MacroExposure,
+}
+
 /// Index range for clear-polarity geometry.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct ClearRange {
     /// Start index (inclusive) in the triangle index buffer.
     pub index_start: u32,
     /// End index (exclusive) in the triangle index buffer.
     pub index_end: u32,
+    /// What produced the range.
+    pub source: ClearRangeSource,
 }
 
 /// Tracks polarity state and records clear-polarity index ranges.
@@ -59,6 +72,7 @@ impl PolarityTracker {
                 self.clear_ranges.push(ClearRange {
                     index_start: start,
                     index_end: idx,
+                    source: ClearRangeSource::Polarity,
                 });
             }
         }
@@ -66,6 +80,28 @@ impl PolarityTracker {
         self.polarity = p;
     }
 
+    /// Hands the ranges recorded so far to `builder`, the builder they index
+    /// into, before conversion switches to another builder at a step-repeat
+    /// boundary. An open clear range is closed at the builder's current end;
+    /// call [`Self::resume`] with the next builder to reopen it there.
+    pub fn suspend(&mut self, builder: &mut GeometryBuilder) {
+        if let Some(start) = self.clear_start.take() {
+            let end = builder.index_count();
+            builder.record_clear_range_from(start, end, ClearRangeSource::Polarity);
+        }
+        for range in self.clear_ranges.drain(..) {
+            builder.record_clear_range_from(range.index_start, range.index_end, range.source);
+        }
+    }
+
+    /// Continues tracking against `builder` after [`Self::suspend`],
+    /// reopening the clear range if clear polarity is still active.
+    pub fn resume(&mut self, builder: &GeometryBuilder) {
+        if self.polarity == Polarity::Clear {
+            self.clear_start = Some(builder.index_count());
+        }
+    }
+
     /// Finishes tracking and returns all clear ranges.
     ///
     /// Closes any open clear range if still in clear polarity.
@@ -78,6 +114,7 @@ impl PolarityTracker {
                     self.clear_ranges.push(ClearRange {
                         index_start: start,
                         index_end: idx,
+                        source: ClearRangeSource::Polarity,
                     });
                 }
             }
@@ -96,11 +133,19 @@ impl Default for PolarityTracker {
 ///
 /// Call after [`GeometryBuilder::build`] to merge tracker ranges with any
 /// ranges already recorded by the builder (e.g. from macro primitives with
-/// exposure off).
+/// exposure off). The merged ranges are sorted by start index so consumers
+/// replaying them in draw order see each clear range right after the dark
+/// geometry it cuts.
 pub fn apply_clear_ranges(geom: &mut LayerGeometry, ranges: Vec<ClearRange>) {
-    for r in ranges {
-        geom.clear_ranges.push((r.index_start, r.index_end));
-    }
+    let mut tagged = geom.tagged_clear_ranges();
+    tagged.extend(ranges);
+    tagged.sort_unstable_by_key(|r| (r.index_start, r.index_end));
+    geom.clear_ranges = tagged
+        .iter()
+        .map(|r| (r.index_start, r.index_end))
+        .collect();
+    geom.clear_range_sources = tagged.iter().map(|r| r.source).collect();
+    geom.refresh_dark_bounds();
 }
 
 #[cfg(test)]
@@ -179,4 +224,55 @@ mod tests {
         assert_eq!(r0.index_start, 3);
         assert_eq!(r0.index_end, 6);
     }
+
+    #[test]
+    fn ut_pol_004_polarity_toggled_before_any_vertices_records_nothing() {
+        let mut builder = GeometryBuilder::new();
+        let mut tracker = PolarityTracker::new();
+
+        tracker.set_polarity(Polarity::Clear, &builder);
+        tracker.set_polarity(Polarity::Dark, &builder);
+        tracker.set_polarity(Polarity::Clear, &builder);
+        tracker.set_polarity(Polarity::Dark, &builder);
+
+        builder.push_vertex(0.0, 0.0);
+        builder.push_vertex(1.0, 0.0);
+        builder.push_vertex(0.0, 1.0);
+        builder.push_triangle(0, 1, 2);
+
+        assert!(tracker.finish(&builder).is_empty());
+    }
+
+    #[test]
+    fn ut_pol_005_empty_clear_range_between_dark_geometry_is_dropped() {
+        let mut builder = GeometryBuilder::new();
+        let mut tracker = PolarityTracker::new();
+
+        builder.push_vertex(0.0, 0.0);
+        builder.push_vertex(1.0, 0.0);
+        builder.push_vertex(0.0, 1.0);
+        builder.push_triangle(0, 1, 2);
+        tracker.set_polarity(Polarity::Clear, &builder);
+        tracker.set_polarity(Polarity::Dark, &builder);
+        tracker.set_polarity(Polarity::Clear, &builder);
+
+        assert!(tracker.finish(&builder).is_empty());
+    }
+
+    #[test]
+    fn ut_pol_006_first_triangle_clear_without_closing_dark() {
+        let mut builder = GeometryBuilder::new();
+        let mut tracker = PolarityTracker::new();
+
+        tracker.set_polarity(Polarity::Clear, &builder);
+        builder.push_vertex(0.0, 0.0);
+        builder.push_vertex(1.0, 0.0);
+        builder.push_vertex(0.0, 1.0);
+        builder.push_triangle(0, 1, 2);
+        builder.push_triangle(2, 1, 0);
+
+        let ranges = tracker.finish(&builder);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!((ranges[0].index_start, ranges[0].index_end), (0, 6));
+    }
 }
@@ -0,0 +1,214 @@
+//! Headless software rasterization of layer geometry.
+//!
+//! Produces RGBA pixel buffers from a [`LayerGeometry`] without a GPU so that
+//! fixtures can be snapshot-tested and compared with [`count_differing_pixels`].
+
+use super::transform::fit_affine;
+use super::types::{index_in_ranges, LayerGeometry, Point};
+
+/// RGBA value written for covered pixels.
+pub const FILL_RGBA: [u8; 4] = [255, 255, 255, 255];
+/// RGBA value written for uncovered pixels.
+pub const BACKGROUND_RGBA: [u8; 4] = [0, 0, 0, 0];
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Rasterizes a layer into a `width` x `height` RGBA buffer.
+///
+/// The layer bounds are fitted to the image with uniform scale and centered;
+/// row 0 is the top of the board (maximum Y). Triangles are painted in index
+/// order: dark triangles fill the pixels whose centers they contain and
+/// triangles in `clear_ranges` reset them to background, so clear polarity
+/// leaves real holes in the earlier dark geometry. Returns an empty buffer
+/// when either dimension is zero.
+pub fn rasterize_layer(geometry: &LayerGeometry, width: u32, height: u32) -> Vec<u8> {
+    let pixel_count = (width as usize).saturating_mul(height as usize);
+    let mut pixels = BACKGROUND_RGBA.repeat(pixel_count);
+    let Some(transform) = PixelTransform::fit(geometry, width, height) else {
+        return pixels;
+    };
+
+    let mut clear_ranges = geometry.clear_ranges.clone();
+    clear_ranges.sort_unstable();
+
+    for (triangle_index, triangle) in geometry.indices.chunks_exact(3).enumerate() {
+        let Some(corners) = triangle_corners(geometry, triangle, &transform) else {
+            continue;
+        };
+        let rgba = if index_in_ranges(&clear_ranges, triangle_index.saturating_mul(3)) {
+            BACKGROUND_RGBA
+        } else {
+            FILL_RGBA
+        };
+        fill_triangle(&mut pixels, width, height, corners, rgba);
+    }
+
+    pixels
+}
+
+/// Counts pixels whose channels differ by more than `threshold`.
+///
+/// Buffers are compared pixel-by-pixel as RGBA quadruples. When lengths
+/// differ, every pixel present in only one buffer counts as different.
+pub fn count_differing_pixels(a: &[u8], b: &[u8], threshold: u8) -> usize {
+    let matched = a
+        .chunks_exact(BYTES_PER_PIXEL)
+        .zip(b.chunks_exact(BYTES_PER_PIXEL))
+        .filter(|(left, right)| {
+            left.iter()
+                .zip(right.iter())
+                .any(|(l, r)| l.abs_diff(*r) > threshold)
+        })
+        .count();
+    let extra = a.len().abs_diff(b.len()) / BYTES_PER_PIXEL;
+    matched + extra
+}
+
+/// Counts pixels with a non-zero alpha channel.
+pub fn count_filled_pixels(pixels: &[u8]) -> usize {
+    pixels
+        .chunks_exact(BYTES_PER_PIXEL)
+        .filter(|px| px.get(3).is_some_and(|alpha| *alpha > 0))
+        .count()
+}
+
+struct PixelTransform([f64; 6]);
+
+impl PixelTransform {
+    fn fit(geometry: &LayerGeometry, width: u32, height: u32) -> Option<Self> {
+        fit_affine(&geometry.bounds, f64::from(width), f64::from(height), true).map(Self)
+    }
+
+    fn apply(&self, x: f32, y: f32) -> Point {
+        let [scale_x, skew_y, skew_x, scale_y, translate_x, translate_y] = self.0;
+        let (board_x, board_y) = (f64::from(x), f64::from(y));
+        Point {
+            x: scale_x.mul_add(board_x, skew_x.mul_add(board_y, translate_x)),
+            y: skew_y.mul_add(board_x, scale_y.mul_add(board_y, translate_y)),
+        }
+    }
+}
+
+fn triangle_corners(
+    geometry: &LayerGeometry,
+    triangle: &[u32],
+    transform: &PixelTransform,
+) -> Option<[Point; 3]> {
+    let mut corners = [Point { x: 0.0, y: 0.0 }; 3];
+    for (corner, index) in corners.iter_mut().zip(triangle) {
+        let base = (*index as usize).checked_mul(2)?;
+        let x = *geometry.positions.get(base)?;
+        let y = *geometry.positions.get(base + 1)?;
+        *corner = transform.apply(x, y);
+    }
+    Some(corners)
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn fill_triangle(pixels: &mut [u8], width: u32, height: u32, [a, b, c]: [Point; 3], rgba: [u8; 4]) {
+    let area = edge(a, b, c);
+    if area.abs() <= f64::EPSILON {
+        return;
+    }
+
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+    let max_x = a.x.max(b.x).max(c.x).ceil().min(f64::from(width)) as u32;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+    let max_y = a.y.max(b.y).max(c.y).ceil().min(f64::from(height)) as u32;
+
+    for row in min_y..max_y {
+        for column in min_x..max_x {
+            let center = Point {
+                x: f64::from(column) + 0.5,
+                y: f64::from(row) + 0.5,
+            };
+            let w0 = edge(b, c, center) / area;
+            let w1 = edge(c, a, center) / area;
+            let w2 = edge(a, b, center) / area;
+            if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                continue;
+            }
+
+            let offset = (row as usize * width as usize + column as usize) * BYTES_PER_PIXEL;
+            if let Some(pixel) = pixels.get_mut(offset..offset + BYTES_PER_PIXEL) {
+                pixel.copy_from_slice(&rgba);
+            }
+        }
+    }
+}
+
+fn edge(a: Point, b: Point, p: Point) -> f64 {
+    (b.x - a.x).mul_add(p.y - a.y, -((b.y - a.y) * (p.x - a.x)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeometryBuilder;
+
+    fn unit_square() -> LayerGeometry {
+        let mut builder = GeometryBuilder::new();
+        let a = builder.push_vertex(0.0, 0.0);
+        let b = builder.push_vertex(1.0, 0.0);
+        let c = builder.push_vertex(1.0, 1.0);
+        let d = builder.push_vertex(0.0, 1.0);
+        builder.push_quad(a, b, c, d);
+        builder.build()
+    }
+
+    #[test]
+    fn full_square_covers_every_pixel() {
+        let pixels = rasterize_layer(&unit_square(), 8, 8);
+        assert_eq!(pixels.len(), 8 * 8 * 4);
+        assert_eq!(count_filled_pixels(&pixels), 64);
+    }
+
+    #[test]
+    fn square_is_centered_in_wide_image() {
+        let pixels = rasterize_layer(&unit_square(), 16, 8);
+        assert_eq!(count_filled_pixels(&pixels), 64);
+        assert_eq!(pixels.get(..4), Some(BACKGROUND_RGBA.as_slice()));
+    }
+
+    #[test]
+    fn empty_geometry_yields_background() {
+        let pixels = rasterize_layer(&GeometryBuilder::new().build(), 4, 4);
+        assert_eq!(count_filled_pixels(&pixels), 0);
+    }
+
+    #[test]
+    fn clear_circle_punches_hole_in_dark_square() {
+        let mut builder = GeometryBuilder::new();
+        let a = builder.push_vertex(0.0, 0.0);
+        let b = builder.push_vertex(4.0, 0.0);
+        let c = builder.push_vertex(4.0, 4.0);
+        let d = builder.push_vertex(0.0, 4.0);
+        builder.push_quad(a, b, c, d);
+        let clear_start = builder.index_count();
+        builder.push_ngon(2.0, 2.0, 1.0, 32);
+        builder.record_clear_range(clear_start, builder.index_count());
+        let geometry = builder.build();
+
+        let pixels = rasterize_layer(&geometry, 16, 16);
+        let pixel = |column: usize, row: usize| {
+            let offset = (row * 16 + column) * BYTES_PER_PIXEL;
+            pixels.get(offset..offset + BYTES_PER_PIXEL)
+        };
+        assert_eq!(pixel(8, 8), Some(BACKGROUND_RGBA.as_slice()), "center");
+        assert_eq!(pixel(1, 8), Some(FILL_RGBA.as_slice()), "left of hole");
+        assert_eq!(pixel(8, 14), Some(FILL_RGBA.as_slice()), "below hole");
+        assert_eq!(pixel(0, 0), Some(FILL_RGBA.as_slice()), "corner");
+    }
+
+    #[test]
+    fn diff_counts_pixels_above_threshold_and_length_mismatch() {
+        let a = [0, 0, 0, 0, 10, 10, 10, 255];
+        let b = [0, 0, 0, 0, 12, 10, 10, 255, 1, 1, 1, 1];
+        assert_eq!(count_differing_pixels(&a, &b, 2), 1);
+        assert_eq!(count_differing_pixels(&a, &b, 1), 2);
+    }
+}
@@ -1,19 +1,38 @@
 //! Region fill triangulation for G36/G37 commands.
 //!
 //! Converts closed polygon boundaries into triangle geometry using the
-//! `earclip` ear-clipping triangulation algorithm.
+//! [`Triangulator`](super::triangulate::Triangulator) selected in the
+//! builder's options, `earclip` ear clipping by default.
+
+use serde::Serialize;
 
 use crate::error::GeometryError;
 
+use super::options::WarningCategory;
+use super::triangulate::Triangulation;
 use super::types::{GeometryBuilder, Point};
 
 const POINT_EQUALITY_EPSILON: f64 = 1e-9;
+/// Relative tolerance (scaled by the squared boundary extent) below which an
+/// area or cross product is treated as zero.
+const AREA_EPSILON_FACTOR: f64 = 1e-12;
+
+/// Filled area and boundary length of one region, in layer units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RegionMetrics {
+    /// Sum of the region's triangle areas.
+    pub area: f64,
+    /// Length of the closed boundary.
+    pub perimeter: f64,
+}
 
 /// Fill a closed polygon region by triangulating its boundary.
 ///
 /// Boundary points are expected to be pre-tessellated (arc segments already
-/// converted to line segments by the caller). The function auto-closes the
-/// polygon when the last point does not coincide with the first.
+/// converted to line segments by the caller). A last point within the
+/// [`region_close_tolerance`](super::TessellationOptions::region_close_tolerance)
+/// of the first is snapped onto it; a wider gap is auto-closed with a
+/// warning.
 ///
 /// # Errors
 ///
@@ -29,13 +48,13 @@ pub fn fill_region(builder: &mut GeometryBuilder, boundary: &[Point]) -> Result<
         return Ok(());
     }
 
-    let needs_close = !points_approx_equal(
-        boundary
-            .first()
-            .copied()
-            .unwrap_or(Point { x: 0.0, y: 0.0 }),
-        boundary.last().copied().unwrap_or(Point { x: 0.0, y: 0.0 }),
-    );
+    let first = boundary
+        .first()
+        .copied()
+        .unwrap_or(Point { x: 0.0, y: 0.0 });
+    let last = boundary.last().copied().unwrap_or(Point { x: 0.0, y: 0.0 });
+    let tolerance = builder.options().region_close_tolerance;
+    let needs_close = (first.x - last.x).abs() > tolerance || (first.y - last.y).abs() > tolerance;
 
     let effective_len = boundary.len() + usize::from(needs_close);
     let mut flat = Vec::with_capacity(effective_len * 2);
@@ -44,24 +63,62 @@ pub fn fill_region(builder: &mut GeometryBuilder, boundary: &[Point]) -> Result<
         flat.push(pt.y);
     }
     if needs_close {
-        if let Some(first) = boundary.first() {
-            flat.push(first.x);
-            flat.push(first.y);
-            builder.warn(
-                "region boundary is not closed; auto-closing by appending first point".to_string(),
-            );
-        }
+        flat.push(first.x);
+        flat.push(first.y);
+        builder.warn_in(
+            WarningCategory::AutoClosedRegion,
+            "region boundary is not closed; auto-closing by appending first point".to_string(),
+        );
+    } else if let [.., x, y] = flat.as_mut_slice() {
+        *x = first.x;
+        *y = first.y;
     }
 
-    let indices = earclip::earcut::earcut(&flat, &[], 2);
+    let triangulation = builder.options().triangulator.backend().triangulate(&flat);
 
-    if indices.is_empty() {
-        builder.warn("earclip produced no triangles for region; skipping".to_string());
+    if triangulation.indices.is_empty() {
+        builder.warn(format!(
+            "triangulation produced no triangles for region ({}); skipping",
+            diagnose_empty_triangulation(&flat)
+        ));
         return Ok(());
     }
 
-    let base_vertex = emit_vertices(builder, &flat);
-    emit_triangles(builder, &indices, base_vertex)
+    let base_vertex = emit_vertices(builder, &triangulation.vertices);
+    emit_triangles(builder, &triangulation.indices, base_vertex)?;
+    builder.record_region_metrics(region_metrics(&flat, &triangulation));
+    Ok(())
+}
+
+/// Measures a triangulated region from its closed flat boundary.
+fn region_metrics(flat: &[f64], triangulation: &Triangulation) -> RegionMetrics {
+    let point = |i: usize| -> Option<Point> {
+        Some(Point {
+            x: *triangulation.vertices.get(i * 2)?,
+            y: *triangulation.vertices.get(i * 2 + 1)?,
+        })
+    };
+
+    let mut area = 0.0;
+    for tri in triangulation.indices.chunks_exact(3) {
+        if let [ia, ib, ic] = *tri {
+            if let (Some(a), Some(b), Some(c)) = (point(ia), point(ib), point(ic)) {
+                area += cross(a, b, c).abs() / 2.0;
+            }
+        }
+    }
+
+    let mut perimeter = 0.0;
+    let mut pairs = flat.chunks_exact(2);
+    let mut previous = pairs.next();
+    for pair in pairs {
+        if let (Some(&[ax, ay]), &[bx, by]) = (previous, pair) {
+            perimeter += (bx - ax).hypot(by - ay);
+        }
+        previous = Some(pair);
+    }
+
+    RegionMetrics { area, perimeter }
 }
 
 /// Push all vertices from the flat coordinate buffer and return the first vertex index.
@@ -104,6 +161,117 @@ fn offset_index(base: u32, offset: usize) -> Result<u32, GeometryError> {
         .ok_or_else(|| GeometryError::RegionError("vertex index overflow".into()))
 }
 
+/// Describe the most likely reason triangulation produced no triangles for `flat`.
+///
+/// Checks, in order: all vertices collinear, non-adjacent edges crossing,
+/// and a near-zero signed area.
+fn diagnose_empty_triangulation(flat: &[f64]) -> String {
+    let points: Vec<Point> = flat
+        .chunks_exact(2)
+        .filter_map(|pair| match *pair {
+            [x, y] => Some(Point { x, y }),
+            _ => None,
+        })
+        .collect();
+
+    let area = signed_area(&points);
+    let extent = boundary_extent(&points);
+    let area_tolerance = AREA_EPSILON_FACTOR * extent * extent;
+
+    if all_collinear(&points, extent) {
+        return format!("boundary is collinear or degenerate; signed area {area:.3e}");
+    }
+
+    if let Some((first, second)) = find_self_intersection(&points) {
+        return format!(
+            "boundary is self-intersecting (edge {first} crosses edge {second}); signed area {area:.3e}"
+        );
+    }
+
+    if area.abs() <= area_tolerance {
+        return format!("boundary encloses near-zero area; signed area {area:.3e}");
+    }
+
+    format!("cause unknown; signed area {area:.3e}")
+}
+
+/// Shoelace signed area; positive for counter-clockwise boundaries.
+fn signed_area(points: &[Point]) -> f64 {
+    let mut twice_area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        if let Some(b) = points.get((i + 1) % points.len()) {
+            twice_area += a.x.mul_add(b.y, -(b.x * a.y));
+        }
+    }
+    twice_area / 2.0
+}
+
+fn boundary_extent(points: &[Point]) -> f64 {
+    let mut min_x = f64::INFINITY;
+    let mut min_y = f64::INFINITY;
+    let mut max_x = f64::NEG_INFINITY;
+    let mut max_y = f64::NEG_INFINITY;
+    for pt in points {
+        min_x = min_x.min(pt.x);
+        min_y = min_y.min(pt.y);
+        max_x = max_x.max(pt.x);
+        max_y = max_y.max(pt.y);
+    }
+    if points.is_empty() {
+        return 0.0;
+    }
+    (max_x - min_x).max(max_y - min_y)
+}
+
+fn all_collinear(points: &[Point], extent: f64) -> bool {
+    let Some(&origin) = points.first() else {
+        return true;
+    };
+    let Some(&far) = points.iter().find(|pt| !points_approx_equal(**pt, origin)) else {
+        return true;
+    };
+    let tolerance = AREA_EPSILON_FACTOR * extent * extent;
+    points
+        .iter()
+        .all(|pt| cross(origin, far, *pt).abs() <= tolerance)
+}
+
+/// Returns the first pair of non-adjacent boundary edges that properly cross.
+fn find_self_intersection(points: &[Point]) -> Option<(usize, usize)> {
+    let count = points.len();
+    let edge = |i: usize| -> Option<(Point, Point)> {
+        Some((*points.get(i)?, *points.get((i + 1) % count)?))
+    };
+    for first in 0..count {
+        for second in (first + 2)..count {
+            if first == 0 && second + 1 == count {
+                continue;
+            }
+            let (Some(edge_a), Some(edge_b)) = (edge(first), edge(second)) else {
+                continue;
+            };
+            if segments_cross(edge_a, edge_b) {
+                return Some((first, second));
+            }
+        }
+    }
+    None
+}
+
+fn segments_cross((a, b): (Point, Point), (c, d): (Point, Point)) -> bool {
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+/// Z component of `(b - a) × (c - a)`.
+fn cross(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x).mul_add(c.y - a.y, -((b.y - a.y) * (c.x - a.x)))
+}
+
 fn points_approx_equal(a: Point, b: Point) -> bool {
     (a.x - b.x).abs() <= POINT_EQUALITY_EPSILON && (a.y - b.y).abs() <= POINT_EQUALITY_EPSILON
 }
@@ -112,6 +280,7 @@ fn points_approx_equal(a: Point, b: Point) -> bool {
 #[allow(clippy::indexing_slicing)]
 mod tests {
     use super::*;
+    use crate::geometry::{copper_area, TessellationOptions, TriangulatorKind};
 
     fn fill_and_build(boundary: &[Point]) -> crate::geometry::LayerGeometry {
         let mut builder = GeometryBuilder::new();
@@ -307,4 +476,146 @@ mod tests {
         let geom = builder.build();
         assert!(geom.vertex_count > 0, "best-effort should produce vertices");
     }
+
+    // --- Empty triangulation diagnostics ---
+
+    #[test]
+    fn collinear_boundary_warning_names_collinear_cause() {
+        let boundary = &[
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+            Point { x: 2.0, y: 0.0 },
+            Point { x: 3.0, y: 0.0 },
+        ];
+        let geom = fill_and_build(boundary);
+        assert_eq!(geom.vertex_count, 0);
+        assert!(
+            geom.warnings
+                .iter()
+                .any(|w| w.contains("no triangles") && w.contains("collinear")),
+            "expected collinear diagnosis, got {:?}",
+            geom.warnings
+        );
+    }
+
+    #[test]
+    fn bowtie_diagnosis_names_self_intersection() {
+        let flat = [0.0, 0.0, 2.0, 2.0, 2.0, 0.0, 0.0, 2.0, 0.0, 0.0];
+        let reason = diagnose_empty_triangulation(&flat);
+        assert!(
+            reason.contains("self-intersecting"),
+            "expected self-intersection diagnosis, got {reason}"
+        );
+        assert!(!reason.contains("collinear"));
+
+        let collinear = [0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 0.0, 0.0];
+        let collinear_reason = diagnose_empty_triangulation(&collinear);
+        assert!(collinear_reason.contains("collinear"));
+        assert_ne!(reason, collinear_reason);
+    }
+
+    #[test]
+    fn ut_reg_009_region_metrics_measure_area_and_perimeter() {
+        let boundary = &[
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 2.0, y: 0.0 },
+            Point { x: 2.0, y: 3.0 },
+            Point { x: 0.0, y: 3.0 },
+        ];
+        let geom = fill_and_build(boundary);
+        assert_eq!(geom.region_metrics.len(), 1);
+        let Some(metrics) = geom.region_metrics.first() else {
+            return;
+        };
+        assert!((metrics.area - 6.0).abs() < 1e-9, "area {}", metrics.area);
+        assert!(
+            (metrics.perimeter - 10.0).abs() < 1e-9,
+            "perimeter {}",
+            metrics.perimeter
+        );
+    }
+
+    // --- UT-REG-010: Robust triangulator fills a bowtie without overlap ---
+
+    #[test]
+    fn ut_reg_010_robust_triangulator_fills_bowtie_without_overlap() {
+        let boundary = &[
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 2.0, y: 2.0 },
+            Point { x: 2.0, y: 0.0 },
+            Point { x: 0.0, y: 2.0 },
+        ];
+        let fill_with = |triangulator| {
+            let options = TessellationOptions::new().with_triangulator(triangulator);
+            let mut builder = GeometryBuilder::with_options(options);
+            assert!(fill_region(&mut builder, boundary).is_ok());
+            builder.build()
+        };
+        let summed_area = |geom: &crate::geometry::LayerGeometry| {
+            geom.region_metrics.iter().map(|m| m.area).sum::<f64>()
+        };
+
+        let covers = |geom: &crate::geometry::LayerGeometry, x: f64, y: f64| {
+            geom.indices.chunks_exact(3).any(|tri| {
+                let p = |i: u32| {
+                    let base = i as usize * 2;
+                    Point {
+                        x: f64::from(geom.positions[base]),
+                        y: f64::from(geom.positions[base + 1]),
+                    }
+                };
+                let (a, b, c) = (p(tri[0]), p(tri[1]), p(tri[2]));
+                let probe = Point { x, y };
+                let sides = [cross(a, b, probe), cross(b, c, probe), cross(c, a, probe)];
+                sides.iter().all(|s| *s > 0.0) || sides.iter().all(|s| *s < 0.0)
+            })
+        };
+
+        // The lobes are the unit-area triangles left and right of the
+        // crossing at (1, 1); the space above the crossing is outside.
+        let earclip = fill_with(TriangulatorKind::Earclip);
+        assert!(
+            !covers(&earclip, 0.2, 1.0) || covers(&earclip, 1.0, 1.8),
+            "earclip is expected to mis-fill the bowtie"
+        );
+
+        let robust = fill_with(TriangulatorKind::Robust);
+        assert!(covers(&robust, 0.2, 1.0));
+        assert!(covers(&robust, 1.8, 1.0));
+        assert!(!covers(&robust, 1.0, 1.8));
+        assert!(!covers(&robust, 1.0, 0.2));
+        assert!(
+            (summed_area(&robust) - 2.0).abs() < 1e-9,
+            "robust area {}",
+            summed_area(&robust)
+        );
+        assert!(
+            (copper_area(&robust) - 2.0).abs() < 1e-9,
+            "robust triangles must not overlap"
+        );
+    }
+
+    #[test]
+    fn ut_reg_011_nearly_closed_region_is_not_auto_closed() {
+        let boundary = &[
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 1.0, y: 0.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 0.0, y: 1.0 },
+            Point { x: 1e-7, y: -1e-7 },
+        ];
+        let geom = fill_and_build(boundary);
+        assert!(geom.warnings.is_empty(), "{:?}", geom.warnings);
+        assert_eq!(triangle_count(&geom), 2);
+
+        let mut builder = GeometryBuilder::with_options(
+            crate::geometry::TessellationOptions::new().with_region_close_tolerance(1e-9),
+        );
+        assert!(fill_region(&mut builder, boundary).is_ok());
+        assert!(builder
+            .build()
+            .warnings
+            .iter()
+            .any(|w| w.contains("auto-closing")));
+    }
 }
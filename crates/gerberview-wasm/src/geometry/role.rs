@@ -0,0 +1,205 @@
+//! Layer roles and the display style suggested for each.
+//!
+//! The role comes from the Gerber X2 `%TF.FileFunction` file attribute, read
+//! from the raw source so it does not depend on the parser keeping file
+//! attributes. Front-ends compositing several layers use the suggested color
+//! and render priority; [`set_layer_style`] overrides the built-in palette.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// What a layer is for on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum LayerRole {
+    /// Top copper.
+    TopCopper,
+    /// Inner copper.
+    InnerCopper,
+    /// Bottom copper.
+    BottomCopper,
+    /// Top solder mask.
+    TopMask,
+    /// Bottom solder mask.
+    BottomMask,
+    /// Top legend (silkscreen).
+    TopSilk,
+    /// Bottom legend (silkscreen).
+    BottomSilk,
+    /// Top solder paste.
+    TopPaste,
+    /// Bottom solder paste.
+    BottomPaste,
+    /// Board outline.
+    Profile,
+    /// Drill or route data.
+    Drill,
+    /// No recognized role.
+    #[default]
+    Other,
+}
+
+/// Display style of a layer role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerStyle {
+    /// Suggested fill color as `[r, g, b, a]`.
+    pub color_rgba: [u8; 4],
+    /// Z-order; layers with a higher priority are drawn on top.
+    pub render_priority: i32,
+}
+
+thread_local! {
+    static STYLE_OVERRIDES: RefCell<HashMap<LayerRole, LayerStyle>> = RefCell::new(HashMap::new());
+}
+
+/// Overrides the style of `role` on this thread, or restores the built-in
+/// style when `style` is `None`.
+pub fn set_layer_style(role: LayerRole, style: Option<LayerStyle>) {
+    STYLE_OVERRIDES.with(|overrides| {
+        let mut overrides = overrides.borrow_mut();
+        match style {
+            Some(style) => overrides.insert(role, style),
+            None => overrides.remove(&role),
+        };
+    });
+}
+
+impl LayerRole {
+    /// Detects the role of a Gerber file from its `%TF.FileFunction`
+    /// attribute, returning [`Self::Other`] when there is none.
+    pub fn detect(data: &[u8]) -> Self {
+        const MARKER: &[u8] = b"%TF.FileFunction,";
+        let Some(start) = data
+            .windows(MARKER.len())
+            .position(|window| window == MARKER)
+        else {
+            return Self::Other;
+        };
+        let value = data.get(start + MARKER.len()..).unwrap_or_default();
+        let end = value
+            .iter()
+            .position(|&byte| byte == b'*')
+            .unwrap_or(value.len());
+        let value = String::from_utf8_lossy(value.get(..end).unwrap_or_default());
+        Self::from_file_function(&value)
+    }
+
+    /// Maps the fields of a `.FileFunction` value, such as `Copper,L1,Top`,
+    /// to a role.
+    pub fn from_file_function(value: &str) -> Self {
+        let fields: Vec<&str> = value.split(',').map(str::trim).collect();
+        let side = |index: usize| fields.get(index).copied().unwrap_or_default();
+        match fields.first().copied().unwrap_or_default() {
+            "Copper" => match side(2) {
+                "Top" => Self::TopCopper,
+                "Bot" => Self::BottomCopper,
+                _ => Self::InnerCopper,
+            },
+            "Soldermask" => Self::sided(side(1), Self::TopMask, Self::BottomMask),
+            "Legend" => Self::sided(side(1), Self::TopSilk, Self::BottomSilk),
+            "Paste" => Self::sided(side(1), Self::TopPaste, Self::BottomPaste),
+            "Profile" => Self::Profile,
+            "Plated" | "NonPlated" => Self::Drill,
+            _ => Self::Other,
+        }
+    }
+
+    /// Parses a role from its variant name, such as `TopCopper`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::TopCopper,
+            Self::InnerCopper,
+            Self::BottomCopper,
+            Self::TopMask,
+            Self::BottomMask,
+            Self::TopSilk,
+            Self::BottomSilk,
+            Self::TopPaste,
+            Self::BottomPaste,
+            Self::Profile,
+            Self::Drill,
+            Self::Other,
+        ]
+        .into_iter()
+        .find(|role| format!("{role:?}") == name)
+    }
+
+    fn sided(side: &str, top: Self, bottom: Self) -> Self {
+        match side {
+            "Top" => top,
+            "Bot" => bottom,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns the style of this role, honoring [`set_layer_style`].
+    pub fn style(self) -> LayerStyle {
+        STYLE_OVERRIDES
+            .with(|overrides| overrides.borrow().get(&self).copied())
+            .unwrap_or_else(|| self.default_style())
+    }
+
+    /// Suggested fill color as `[r, g, b, a]`.
+    pub fn suggested_color_rgba(self) -> [u8; 4] {
+        self.style().color_rgba
+    }
+
+    /// Z-order; layers with a higher priority are drawn on top.
+    pub fn render_priority(self) -> i32 {
+        self.style().render_priority
+    }
+
+    /// Built-in palette: bottom layers below top layers, with the outline
+    /// and drills over all copper.
+    const fn default_style(self) -> LayerStyle {
+        let (color_rgba, render_priority) = match self {
+            Self::BottomSilk => ([200, 200, 230, 255], 10),
+            Self::BottomMask => ([20, 50, 30, 160], 20),
+            Self::BottomPaste => ([150, 150, 150, 255], 25),
+            Self::BottomCopper => ([50, 90, 200, 255], 30),
+            Self::InnerCopper => ([200, 160, 40, 255], 40),
+            Self::TopCopper => ([200, 50, 40, 255], 50),
+            Self::TopPaste => ([170, 170, 170, 255], 55),
+            Self::TopMask => ([20, 60, 30, 160], 60),
+            Self::TopSilk => ([245, 245, 245, 255], 70),
+            Self::Drill => ([20, 20, 20, 255], 80),
+            Self::Profile => ([230, 200, 40, 255], 90),
+            Self::Other => ([128, 128, 128, 255], 0),
+        };
+        LayerStyle {
+            color_rgba,
+            render_priority,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_function_attribute_selects_role() {
+        assert_eq!(
+            LayerRole::detect(b"%FSLAX46Y46*%\n%TF.FileFunction,Copper,L2,Bot*%\n"),
+            LayerRole::BottomCopper
+        );
+        assert_eq!(
+            LayerRole::detect(b"%TF.FileFunction,Legend,Top*%\n"),
+            LayerRole::TopSilk
+        );
+        assert_eq!(LayerRole::detect(b"G04 no attributes*\n"), LayerRole::Other);
+    }
+
+    #[test]
+    fn overrides_replace_and_restore_the_palette() {
+        let custom = LayerStyle {
+            color_rgba: [1, 2, 3, 4],
+            render_priority: -5,
+        };
+        set_layer_style(LayerRole::Profile, Some(custom));
+        assert_eq!(LayerRole::Profile.style(), custom);
+        set_layer_style(LayerRole::Profile, None);
+        assert_eq!(LayerRole::Profile.render_priority(), 90);
+    }
+}
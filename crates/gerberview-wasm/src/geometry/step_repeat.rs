@@ -2,11 +2,38 @@
 //!
 //! Duplicates vertex ranges with X/Y offsets for each grid position.
 
+use web_time::Instant;
+
 use crate::error::GeometryError;
 
-use super::types::{GeometryBuilder, LayerGeometry};
+use super::attributes::AttributeRange;
+use super::types::{FanRange, GeometryBuilder, LayerGeometry};
 
 const BC_GBR_020: &str = "BC-GBR-020: step-repeat with zero count in X or Y; skipping block";
+const SR_INSTANCE_LIMIT: &str = "step-repeat instance count exceeds limit";
+
+/// Bounds on the geometry [`apply_step_repeat_within`] may add.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplicationBudget {
+    /// Stop adding instances once the builder holds more triangles than this.
+    pub max_triangles: Option<u32>,
+    /// Stop adding instance rows once this moment has passed.
+    pub deadline: Option<Instant>,
+}
+
+impl ReplicationBudget {
+    /// Returns whether `builder` has outgrown the triangle budget.
+    fn triangles_spent(&self, builder: &GeometryBuilder) -> bool {
+        self.max_triangles
+            .is_some_and(|max_triangles| builder.index_count() / 3 > max_triangles)
+    }
+
+    /// Returns whether the deadline has passed.
+    fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
 
 /// Applies step-repeat by duplicating block geometry at each grid position.
 ///
@@ -21,6 +48,8 @@ const BC_GBR_020: &str = "BC-GBR-020: step-repeat with zero count in X or Y; ski
 ///
 /// - BC-GBR-020: If `repeat_x == 0` or `repeat_y == 0`, warns and returns
 ///   `Ok(())` without adding geometry.
+/// - If `repeat_x * repeat_y` exceeds the builder's
+///   `max_step_repeat_instances` option, warns and skips the block.
 /// - BC-GBR-019: Nested step-repeat is flattened by applying this function
 ///   to already-step-repeated geometry.
 pub fn apply_step_repeat(
@@ -31,38 +60,79 @@ pub fn apply_step_repeat(
     step_x: f64,
     step_y: f64,
 ) -> Result<(), GeometryError> {
+    apply_step_repeat_within(
+        builder,
+        block_geometry,
+        repeat_x,
+        repeat_y,
+        step_x,
+        step_y,
+        ReplicationBudget::default(),
+    )
+    .map(|_| ())
+}
+
+/// Applies step-repeat like [`apply_step_repeat`] until `budget` is spent.
+///
+/// The triangle budget is checked before each instance and the deadline
+/// before each row, so a huge grid is not materialized only to be truncated
+/// afterwards.
+///
+/// Returns whether replication stopped early.
+///
+/// # Errors
+///
+/// Returns an error if geometry is invalid.
+pub fn apply_step_repeat_within(
+    builder: &mut GeometryBuilder,
+    block_geometry: &LayerGeometry,
+    repeat_x: u32,
+    repeat_y: u32,
+    step_x: f64,
+    step_y: f64,
+    budget: ReplicationBudget,
+) -> Result<bool, GeometryError> {
     if repeat_x == 0 || repeat_y == 0 {
         builder.warn(BC_GBR_020.to_string());
-        return Ok(());
+        return Ok(false);
+    }
+
+    let instances = u64::from(repeat_x) * u64::from(repeat_y);
+    let max_instances = builder.options().max_step_repeat_instances;
+    if instances > max_instances {
+        builder.warn(format!(
+            "{SR_INSTANCE_LIMIT} ({repeat_x}x{repeat_y} = {instances} > {max_instances}); skipping block"
+        ));
+        return Ok(false);
     }
 
-    let positions = &block_geometry.positions;
     let indices = &block_geometry.indices;
     let vertex_count = block_geometry.vertex_count as usize;
 
     for iy in 0..repeat_y {
+        if budget.deadline_passed() {
+            return Ok(true);
+        }
         for ix in 0..repeat_x {
+            if budget.triangles_spent(builder) {
+                return Ok(true);
+            }
             let offset_x = f64::from(ix) * step_x;
             let offset_y = f64::from(iy) * step_y;
 
             let base = builder.vertex_count();
 
             for v in 0..vertex_count {
-                let i = v * 2;
-                let x = positions
-                    .get(i)
-                    .and_then(|a| positions.get(i + 1).map(|b| (a, b)));
-                let Some((x, y)) = x else {
+                let Some((x, y)) = block_vertex(block_geometry, v) else {
                     return Err(GeometryError::DegenerateGeometry(
                         "block has incomplete vertex data".to_string(),
                     ));
                 };
-                let x_val = f64::from(*x) + offset_x;
-                let y_val = f64::from(*y) + offset_y;
-                builder.push_vertex(x_val, y_val);
+                builder.push_vertex(x + offset_x, y + offset_y);
             }
 
             let base_u = base;
+            let index_base = builder.index_count();
             for chunk in indices.chunks_exact(3) {
                 let (Some(&a), Some(&b), Some(&c)) = (chunk.first(), chunk.get(1), chunk.get(2))
                 else {
@@ -80,15 +150,54 @@ pub fn apply_step_repeat(
                 }
                 builder.push_triangle(base_u + a, base_u + b, base_u + c);
             }
+            for range in block_geometry.tagged_clear_ranges() {
+                builder.record_clear_range_from(
+                    index_base + range.index_start,
+                    index_base + range.index_end,
+                    range.source,
+                );
+            }
+            for range in &block_geometry.attribute_ranges {
+                builder.record_attribute_range(AttributeRange {
+                    index_start: index_base + range.index_start,
+                    index_end: index_base + range.index_end,
+                    attributes: range.attributes.clone(),
+                });
+            }
+            for &metrics in &block_geometry.region_metrics {
+                builder.record_region_metrics(metrics);
+            }
+            for fan in &block_geometry.fan_ranges {
+                builder.record_fan_range(FanRange {
+                    center: base_u + fan.center,
+                    rim_count: fan.rim_count,
+                    index_start: index_base + fan.index_start,
+                });
+            }
         }
     }
 
-    Ok(())
+    Ok(false)
+}
+
+/// Returns vertex `v` of `block`, preferring retained `f64` positions.
+fn block_vertex(block: &LayerGeometry, v: usize) -> Option<(f64, f64)> {
+    let i = v.checked_mul(2)?;
+    if block.positions_f64.len() == block.positions.len() {
+        Some((
+            *block.positions_f64.get(i)?,
+            *block.positions_f64.get(i + 1)?,
+        ))
+    } else {
+        let (x, y) = (block.positions.get(i)?, block.positions.get(i + 1)?);
+        Some((f64::from(*x), f64::from(*y)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::TessellationOptions;
 
     fn make_simple_block() -> LayerGeometry {
         let mut b = GeometryBuilder::new();
@@ -144,6 +253,20 @@ mod tests {
         assert!((second_y - 0.0).abs() < eps);
     }
 
+    #[test]
+    fn triangle_budget_stops_replication_early() {
+        let block = make_simple_block();
+        let mut builder = GeometryBuilder::new();
+        let budget = ReplicationBudget {
+            max_triangles: Some(10),
+            deadline: None,
+        };
+
+        let stopped = apply_step_repeat_within(&mut builder, &block, 1000, 1000, 1.0, 1.0, budget);
+        assert!(matches!(stopped, Ok(true)));
+        assert_eq!(builder.index_count() / 3, 11);
+    }
+
     #[test]
     fn ut_sr_003_zero_count_in_x_skips_with_warn() {
         let block = make_simple_block();
@@ -174,4 +297,31 @@ mod tests {
         assert_eq!(outer_geom.vertex_count, block.vertex_count * 4);
         assert_eq!(outer_geom.indices.len(), block.indices.len() * 4);
     }
+
+    #[test]
+    fn ut_sr_004_oversized_repeat_count_skips_with_warn() {
+        let block = make_simple_block();
+        let mut builder = GeometryBuilder::new();
+
+        let result = apply_step_repeat(&mut builder, &block, 100_000, 100_000, 1.0, 1.0);
+        assert!(result.is_ok());
+
+        let geom = builder.build();
+        assert_eq!(geom.vertex_count, 0);
+        assert!(geom.warnings.iter().any(|w| w.contains(SR_INSTANCE_LIMIT)));
+    }
+
+    #[test]
+    fn ut_sr_005_instance_cap_is_configurable() {
+        let block = make_simple_block();
+        let options = TessellationOptions::new().with_max_step_repeat_instances(4);
+        let mut builder = GeometryBuilder::with_options(options);
+
+        assert!(apply_step_repeat(&mut builder, &block, 2, 2, 1.0, 1.0).is_ok());
+        assert!(apply_step_repeat(&mut builder, &block, 5, 1, 1.0, 1.0).is_ok());
+
+        let geom = builder.build();
+        assert_eq!(geom.vertex_count, block.vertex_count * 4);
+        assert_eq!(geom.warnings.len(), 1);
+    }
 }
@@ -3,21 +3,39 @@
 //! This module converts a line segment into thick triangle geometry using the
 //! currently selected aperture.
 
-use std::f64::consts::{FRAC_PI_2, PI};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
 
 use gerber_types::{Aperture, Polygon, Rectangular};
 
 use crate::error::GeometryError;
 
-use super::aperture::flash_aperture;
+use super::aperture::{flash_aperture, validate_polygon};
+use super::options::CapStyle;
 use super::types::{GeometryBuilder, Point};
 
 const CIRCLE_ENDCAP_SEGMENTS: u32 = 16;
+/// Maximum miter length as a multiple of the half stroke width before a
+/// join falls back to a bevel (matches the SVG default of 4).
+const MITER_LIMIT: f64 = 4.0;
+const OUTLINE_POINT_EPSILON: f64 = 1e-9;
+
+/// `(cos, sin)` pairs around a unit semicircle.
+type UnitRim = Rc<[(f64, f64)]>;
+
+thread_local! {
+    /// Unit semicircle rims per segment count, shared by every round endcap
+    /// so dense traces do not recompute the same sines and cosines.
+    static ENDCAP_RIMS: RefCell<Vec<(u32, UnitRim)>> = const { RefCell::new(Vec::new()) };
+}
 
 /// Expand a linear D01 draw command into renderable triangles.
 ///
-/// The segment body is emitted as a quad. For circular apertures, rounded
-/// semicircle endcaps are added at both ends.
+/// The segment body is emitted as a quad. For circular apertures, both ends
+/// follow the builder's [`CapStyle`]: semicircle endcaps by default, flat ends
+/// for [`CapStyle::Butt`], or a body extended by half the width for
+/// [`CapStyle::Square`].
 ///
 /// # Errors
 ///
@@ -28,6 +46,21 @@ pub fn draw_linear(
     from: Point,
     to: Point,
     aperture: &Aperture,
+) -> Result<(), GeometryError> {
+    let cap_style = builder.options().cap_style;
+    draw_linear_with_caps(builder, from, to, aperture, (cap_style, cap_style))
+}
+
+/// Expand a linear draw with independent start and end cap styles.
+///
+/// Polylines use this to keep interior joints round while honoring the
+/// configured cap style at the outer ends.
+pub(crate) fn draw_linear_with_caps(
+    builder: &mut GeometryBuilder,
+    from: Point,
+    to: Point,
+    aperture: &Aperture,
+    (start_cap, end_cap): (CapStyle, CapStyle),
 ) -> Result<(), GeometryError> {
     let Some(stroke_width) = resolve_stroke_width(builder, aperture)? else {
         return Ok(());
@@ -47,6 +80,17 @@ pub fn draw_linear(
     let normal_x = -direction_y;
     let normal_y = direction_x;
     let half_width = stroke_width / 2.0;
+    let is_circle = matches!(aperture, Aperture::Circle(_));
+    let start_extension = square_extension(is_circle, start_cap, half_width);
+    let end_extension = square_extension(is_circle, end_cap, half_width);
+    let from = Point {
+        x: (-direction_x).mul_add(start_extension, from.x),
+        y: (-direction_y).mul_add(start_extension, from.y),
+    };
+    let to = Point {
+        x: direction_x.mul_add(end_extension, to.x),
+        y: direction_y.mul_add(end_extension, to.y),
+    };
 
     let start_left = Point {
         x: normal_x.mul_add(half_width, from.x),
@@ -67,29 +111,182 @@ pub fn draw_linear(
 
     push_segment_body(builder, start_left, start_right, end_right, end_left);
 
-    if matches!(aperture, Aperture::Circle(_)) {
-        let direction_angle = direction_y.atan2(direction_x);
-        push_semi_circle(
-            builder,
-            from,
-            half_width,
-            direction_angle + FRAC_PI_2,
-            direction_angle + PI + FRAC_PI_2,
-            CIRCLE_ENDCAP_SEGMENTS,
-        );
-        push_semi_circle(
-            builder,
-            to,
-            half_width,
-            direction_angle - FRAC_PI_2,
-            direction_angle + FRAC_PI_2,
-            CIRCLE_ENDCAP_SEGMENTS,
-        );
+    if is_circle {
+        let endcap_segments = builder.options().curve_segments(CIRCLE_ENDCAP_SEGMENTS);
+        let normal = Point {
+            x: normal_x,
+            y: normal_y,
+        };
+        if start_cap == CapStyle::Round {
+            push_round_endcap(builder, from, half_width, normal, endcap_segments);
+        }
+        if end_cap == CapStyle::Round {
+            let outward = Point {
+                x: -normal_x,
+                y: -normal_y,
+            };
+            push_round_endcap(builder, to, half_width, outward, endcap_segments);
+        }
     }
 
     Ok(())
 }
 
+fn square_extension(is_circle: bool, cap: CapStyle, half_width: f64) -> f64 {
+    if is_circle && cap == CapStyle::Square {
+        half_width
+    } else {
+        0.0
+    }
+}
+
+/// Stroke a closed polyline with mitered joins.
+///
+/// The last point is connected back to the first; a repeated closing point is
+/// ignored. Corners are mitered, falling back to a bevel when the miter would
+/// exceed [`MITER_LIMIT`] half-widths. Only the aperture's stroke width is
+/// used; no endcaps are emitted.
+///
+/// # Errors
+///
+/// Returns an error when aperture parameters are invalid or when the aperture
+/// type is not supported for stroke widening.
+pub fn draw_closed_outline(
+    builder: &mut GeometryBuilder,
+    points: &[Point],
+    aperture: &Aperture,
+) -> Result<(), GeometryError> {
+    let Some(stroke_width) = resolve_stroke_width(builder, aperture)? else {
+        return Ok(());
+    };
+
+    let ring = dedup_closed_ring(points);
+    if ring.len() < 3 {
+        builder.warn(format!(
+            "closed outline needs at least 3 distinct points, got {}; skipping",
+            ring.len()
+        ));
+        return Ok(());
+    }
+
+    let half_width = stroke_width / 2.0;
+    let count = ring.len();
+    let mut joins = Vec::with_capacity(count);
+    for (index, &corner) in ring.iter().enumerate() {
+        let previous = ring
+            .get((index + count - 1) % count)
+            .copied()
+            .unwrap_or(corner);
+        let next = ring.get((index + 1) % count).copied().unwrap_or(corner);
+        joins.push(push_outline_join(
+            builder, previous, corner, next, half_width,
+        ));
+    }
+
+    for (index, join) in joins.iter().enumerate() {
+        let Some(next) = joins.get((index + 1) % count) else {
+            continue;
+        };
+        builder.push_quad(join.out_left, join.out_right, next.in_right, next.in_left);
+    }
+
+    Ok(())
+}
+
+/// Vertex indices where the incoming and outgoing segment bodies attach.
+struct OutlineJoin {
+    in_left: u32,
+    in_right: u32,
+    out_left: u32,
+    out_right: u32,
+}
+
+fn push_outline_join(
+    builder: &mut GeometryBuilder,
+    previous: Point,
+    corner: Point,
+    next: Point,
+    half_width: f64,
+) -> OutlineJoin {
+    let normal_in = unit_normal(previous, corner);
+    let normal_out = unit_normal(corner, next);
+    let offset = |normal: (f64, f64), scale: f64| Point {
+        x: normal.0.mul_add(scale, corner.x),
+        y: normal.1.mul_add(scale, corner.y),
+    };
+
+    let sum = (normal_in.0 + normal_out.0, normal_in.1 + normal_out.1);
+    let sum_length = sum.0.hypot(sum.1);
+    if sum_length > f64::EPSILON {
+        let miter = (sum.0 / sum_length, sum.1 / sum_length);
+        let cosine = miter.0.mul_add(normal_in.0, miter.1 * normal_in.1);
+        let miter_length = half_width / cosine;
+        if cosine > f64::EPSILON && miter_length <= MITER_LIMIT * half_width {
+            let left = offset(miter, miter_length);
+            let right = offset(miter, -miter_length);
+            let left = builder.push_vertex(left.x, left.y);
+            let right = builder.push_vertex(right.x, right.y);
+            return OutlineJoin {
+                in_left: left,
+                in_right: right,
+                out_left: left,
+                out_right: right,
+            };
+        }
+    }
+
+    // Bevel: separate attachment points per segment, with the wedge on each
+    // side filled from the corner point.
+    let center = builder.push_vertex(corner.x, corner.y);
+    let vertices = [
+        offset(normal_in, half_width),
+        offset(normal_in, -half_width),
+        offset(normal_out, half_width),
+        offset(normal_out, -half_width),
+    ]
+    .map(|pt| builder.push_vertex(pt.x, pt.y));
+    let [in_left, in_right, out_left, out_right] = vertices;
+    builder.push_triangle(center, in_left, out_left);
+    builder.push_triangle(center, in_right, out_right);
+    OutlineJoin {
+        in_left,
+        in_right,
+        out_left,
+        out_right,
+    }
+}
+
+fn unit_normal(from: Point, to: Point) -> (f64, f64) {
+    let delta_x = to.x - from.x;
+    let delta_y = to.y - from.y;
+    let length = delta_x.hypot(delta_y);
+    if length <= f64::EPSILON {
+        return (0.0, 0.0);
+    }
+    (-delta_y / length, delta_x / length)
+}
+
+fn dedup_closed_ring(points: &[Point]) -> Vec<Point> {
+    let same = |a: Point, b: Point| {
+        (a.x - b.x).abs() <= OUTLINE_POINT_EPSILON && (a.y - b.y).abs() <= OUTLINE_POINT_EPSILON
+    };
+    let mut ring: Vec<Point> = Vec::with_capacity(points.len());
+    for &point in points {
+        if ring.last().map_or(true, |&last| !same(last, point)) {
+            ring.push(point);
+        }
+    }
+    while ring.len() > 1 {
+        match (ring.first(), ring.last()) {
+            (Some(&first), Some(&last)) if same(first, last) => {
+                ring.pop();
+            }
+            _ => break,
+        }
+    }
+    ring
+}
+
 fn handle_zero_length_segment(
     builder: &mut GeometryBuilder,
     position: Point,
@@ -126,14 +323,7 @@ fn resolve_polygon_width(
     builder: &mut GeometryBuilder,
     polygon: &Polygon,
 ) -> Result<Option<f64>, GeometryError> {
-    if polygon.vertices < 3 {
-        return Err(GeometryError::InvalidAperture(format!(
-            "polygon has {} vertices; expected at least 3",
-            polygon.vertices
-        )));
-    }
-
-    normalize_dimension(builder, polygon.diameter, "polygon diameter")
+    Ok(validate_polygon(builder, polygon)?.map(|(_, radius, _)| radius * 2.0))
 }
 
 fn normalize_rect_like_width(
@@ -174,7 +364,7 @@ fn normalize_dimension(
     }
 
     if normalized <= f64::EPSILON {
-        builder.warn(format!("{label} is zero; skipping stroke widening"));
+        builder.warn_zero_dimension(label);
         return Ok(None);
     }
 
@@ -195,29 +385,44 @@ fn push_segment_body(
     builder.push_quad(a, b, c, d);
 }
 
-fn push_semi_circle(
+/// Fans a counter-clockwise semicircle around `center`, starting in the unit
+/// direction `start` and ending opposite it.
+fn push_round_endcap(
     builder: &mut GeometryBuilder,
     center: Point,
     radius: f64,
-    start_angle: f64,
-    end_angle: f64,
+    start: Point,
     segments: u32,
 ) {
     let center_index = builder.push_vertex(center.x, center.y);
-    let segment_count = segments.max(1);
-    let angle_step = (end_angle - start_angle) / f64::from(segment_count);
-
-    let mut previous_index: Option<u32> = None;
-    for idx in 0..=segment_count {
-        let angle = angle_step.mul_add(f64::from(idx), start_angle);
-        let x = radius.mul_add(angle.cos(), center.x);
-        let y = radius.mul_add(angle.sin(), center.y);
-        let current_index = builder.push_vertex(x, y);
-        if let Some(previous) = previous_index {
-            builder.push_triangle(center_index, previous, current_index);
-        }
-        previous_index = Some(current_index);
+    let segments = segments.max(1);
+    let unit_rim = unit_endcap_rim(segments);
+    for &(cos, sin) in unit_rim.iter() {
+        let x = start.x.mul_add(cos, -(start.y * sin));
+        let y = start.y.mul_add(cos, start.x * sin);
+        builder.push_vertex(radius.mul_add(x, center.x), radius.mul_add(y, center.y));
     }
+    builder.push_fan_range(center_index, center_index + 1, segments + 1);
+}
+
+/// Returns `(cos, sin)` of `k * PI / segments` for `k` in `0..=segments`,
+/// computed once per segment count.
+fn unit_endcap_rim(segments: u32) -> UnitRim {
+    ENDCAP_RIMS.with(|rims| {
+        let mut rims = rims.borrow_mut();
+        if let Some((_, rim)) = rims.iter().find(|(count, _)| *count == segments) {
+            return Rc::clone(rim);
+        }
+        let step = PI / f64::from(segments);
+        let rim: UnitRim = (0..=segments)
+            .map(|k| {
+                let angle = step * f64::from(k);
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        rims.push((segments, Rc::clone(&rim)));
+        rim
+    })
 }
 
 #[cfg(test)]
@@ -226,6 +431,7 @@ mod tests {
     use gerber_types::{Circle, Rectangular};
 
     use super::*;
+    use crate::geometry::TessellationOptions;
 
     const EPSILON: f64 = 1e-6;
 
@@ -356,4 +562,153 @@ mod tests {
         assert_close(geom.bounds.min_y, -1.0);
         assert_close(geom.bounds.max_y, 1.0);
     }
+
+    fn draw_with_cap_style(cap_style: CapStyle) -> crate::geometry::LayerGeometry {
+        let options = TessellationOptions::new().with_cap_style(cap_style);
+        let mut builder = GeometryBuilder::with_options(options);
+        let result = draw_linear(
+            &mut builder,
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            &Aperture::Circle(Circle::new(2.0)),
+        );
+        assert!(result.is_ok(), "expected draw_linear to succeed");
+        builder.build()
+    }
+
+    #[test]
+    fn ut_str_007_butt_caps_emit_only_segment_body() {
+        let geom = draw_with_cap_style(CapStyle::Butt);
+
+        assert_eq!(geom.vertex_count, 4);
+        assert_eq!(geom.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_point(&geom.positions, 0, 0.0, 1.0);
+        assert_point(&geom.positions, 1, 0.0, -1.0);
+        assert_point(&geom.positions, 2, 10.0, -1.0);
+        assert_point(&geom.positions, 3, 10.0, 1.0);
+    }
+
+    #[test]
+    fn ut_str_008_square_caps_extend_body_by_half_width() {
+        let geom = draw_with_cap_style(CapStyle::Square);
+
+        assert_eq!(geom.vertex_count, 4);
+        assert_eq!(geom.indices.len(), 6);
+        assert_close(geom.bounds.min_x, -1.0);
+        assert_close(geom.bounds.max_x, 11.0);
+        assert_close(geom.bounds.min_y, -1.0);
+        assert_close(geom.bounds.max_y, 1.0);
+    }
+
+    #[test]
+    fn ut_str_009_default_options_keep_round_caps() {
+        let round = draw_with_cap_style(CapStyle::Round);
+        let default = draw_and_build(
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            Aperture::Circle(Circle::new(2.0)),
+        );
+
+        assert_eq!(round.positions, default.positions);
+        assert_eq!(round.indices, default.indices);
+    }
+
+    fn covers(geom: &crate::geometry::LayerGeometry, probe_x: f64, probe_y: f64) -> bool {
+        let vertex = |index: u32| {
+            let base = index as usize * 2;
+            (
+                f64::from(geom.positions[base]),
+                f64::from(geom.positions[base + 1]),
+            )
+        };
+        let side = |from: (f64, f64), to: (f64, f64)| {
+            (to.0 - from.0).mul_add(probe_y - from.1, -((to.1 - from.1) * (probe_x - from.0)))
+        };
+        geom.indices.chunks_exact(3).any(|tri| {
+            let corners = [vertex(tri[0]), vertex(tri[1]), vertex(tri[2])];
+            let sides = [
+                side(corners[0], corners[1]),
+                side(corners[1], corners[2]),
+                side(corners[2], corners[0]),
+            ];
+            sides.iter().all(|d| *d >= 0.0) || sides.iter().all(|d| *d <= 0.0)
+        })
+    }
+
+    #[test]
+    fn ut_str_010_closed_square_outline_has_mitered_corners() {
+        let square = [
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            Point { x: 10.0, y: 10.0 },
+            Point { x: 0.0, y: 10.0 },
+            Point { x: 0.0, y: 0.0 },
+        ];
+        let mut builder = GeometryBuilder::new();
+        let result =
+            draw_closed_outline(&mut builder, &square, &Aperture::Circle(Circle::new(1.0)));
+        assert!(result.is_ok(), "expected draw_closed_outline to succeed");
+        let geom = builder.build();
+
+        assert_eq!(geom.vertex_count, 8);
+        assert_eq!(geom.indices.len(), 24);
+        for (x, y) in [
+            (-0.45, -0.45),
+            (10.45, -0.45),
+            (10.45, 10.45),
+            (-0.45, 10.45),
+        ] {
+            assert!(covers(&geom, x, y), "corner ({x}, {y}) has a gap");
+        }
+        assert!(covers(&geom, 5.0, 0.4));
+        assert!(!covers(&geom, 5.0, 5.0));
+        assert_close(geom.bounds.min_x, -0.5);
+        assert_close(geom.bounds.max_y, 10.5);
+    }
+
+    #[test]
+    fn ut_str_011_sharp_outline_corner_falls_back_to_bevel() {
+        let spike = [
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            Point { x: 0.0, y: 0.5 },
+        ];
+        let mut builder = GeometryBuilder::new();
+        let result = draw_closed_outline(&mut builder, &spike, &Aperture::Circle(Circle::new(1.0)));
+        assert!(result.is_ok(), "expected draw_closed_outline to succeed");
+        let geom = builder.build();
+
+        assert!(geom.bounds.max_x < MITER_LIMIT.mul_add(0.5, 10.0) + EPSILON);
+        assert!(covers(&geom, 9.9, 0.0));
+    }
+
+    #[test]
+    fn ut_str_012_cached_endcap_matches_direct_trigonometry() {
+        let center = Point { x: 3.0, y: -2.0 };
+        let radius = 0.75;
+        for direction in [0.0_f64, 0.3, 1.9, -2.7] {
+            let start_angle = direction + PI / 2.0;
+            let start = Point {
+                x: start_angle.cos(),
+                y: start_angle.sin(),
+            };
+            let mut builder = GeometryBuilder::new();
+            push_round_endcap(&mut builder, center, radius, start, 16);
+            let geom = builder.build();
+
+            assert_eq!(geom.vertex_count, 18);
+            assert_eq!(geom.indices.len(), 16 * 3);
+            for k in 0..=16_u32 {
+                let angle = (PI / 16.0).mul_add(f64::from(k), start_angle);
+                let index = k as usize + 1;
+                assert_point(
+                    &geom.positions,
+                    index,
+                    radius.mul_add(angle.cos(), center.x),
+                    radius.mul_add(angle.sin(), center.y),
+                );
+            }
+        }
+        assert!(Rc::ptr_eq(&unit_endcap_rim(16), &unit_endcap_rim(16)));
+    }
 }
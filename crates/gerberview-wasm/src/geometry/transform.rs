@@ -0,0 +1,200 @@
+//! Board-to-viewport transforms.
+//!
+//! Matrices use the 2D affine layout `[a, b, c, d, e, f]` shared by Canvas
+//! `setTransform` and SVG `matrix(...)`: `x' = a*x + c*y + e`,
+//! `y' = b*x + d*y + f`.
+
+use super::types::BoundingBox;
+
+/// Identity affine matrix.
+pub const IDENTITY_TRANSFORM: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Maps board point `(x, y)` to screen space with `matrix`.
+pub fn board_to_screen(matrix: [f64; 6], x: f64, y: f64) -> (f64, f64) {
+    let [scale_x, skew_y, skew_x, scale_y, translate_x, translate_y] = matrix;
+    (
+        scale_x.mul_add(x, skew_x.mul_add(y, translate_x)),
+        skew_y.mul_add(x, scale_y.mul_add(y, translate_y)),
+    )
+}
+
+/// Maps screen pixel `(px, py)` back to board space, inverting `matrix`.
+///
+/// Y-flipped matrices from [`fit_transform`] invert like any other, so a
+/// pixel measured from the top of the viewport lands on the right board Y.
+/// Returns `None` when `matrix` is singular.
+pub fn screen_to_board(matrix: [f64; 6], px: f64, py: f64) -> Option<(f64, f64)> {
+    let [scale_x, skew_y, skew_x, scale_y, translate_x, translate_y] = matrix;
+    let determinant = scale_x.mul_add(scale_y, -(skew_x * skew_y));
+    if determinant.abs() <= f64::EPSILON || !determinant.is_finite() {
+        return None;
+    }
+    let (dx, dy) = (px - translate_x, py - translate_y);
+    Some((
+        scale_y.mul_add(dx, -(skew_x * dy)) / determinant,
+        scale_x.mul_add(dy, -(skew_y * dx)) / determinant,
+    ))
+}
+
+/// Builds a whole-layer transform: an optional mirror of X (`x' = -x`),
+/// then a counter-clockwise rotation by `rotation_deg` about the origin,
+/// then a translation by `(offset_x, offset_y)`.
+///
+/// Mirroring X is the usual way to view a bottom layer from the top.
+pub fn layer_affine(mirror_x: bool, rotation_deg: f64, offset_x: f64, offset_y: f64) -> [f64; 6] {
+    let (sin, cos) = rotation_deg.to_radians().sin_cos();
+    let mirror = if mirror_x { -1.0 } else { 1.0 };
+    [mirror * cos, mirror * sin, -sin, cos, offset_x, offset_y]
+}
+
+/// Computes an affine matrix fitting `bounds` into a viewport.
+///
+/// The board is scaled uniformly to the largest size that fits, then
+/// centered. With `flip_y`, board Y (up) maps to screen Y (down) so the
+/// board's top edge lands at the top of the viewport. Returns
+/// [`IDENTITY_TRANSFORM`] for empty bounds or a non-positive viewport.
+#[allow(clippy::cast_possible_truncation)]
+pub fn fit_transform(
+    bounds: &BoundingBox,
+    viewport_w: f64,
+    viewport_h: f64,
+    flip_y: bool,
+) -> [f32; 6] {
+    fit_affine(bounds, viewport_w, viewport_h, flip_y)
+        .map_or(IDENTITY_TRANSFORM, |matrix| matrix.map(|v| v as f32))
+}
+
+/// Full-precision form of [`fit_transform`]; `None` when no fit exists.
+pub(crate) fn fit_affine(
+    bounds: &BoundingBox,
+    viewport_w: f64,
+    viewport_h: f64,
+    flip_y: bool,
+) -> Option<[f64; 6]> {
+    let board_w = bounds.max_x - bounds.min_x;
+    let board_h = bounds.max_y - bounds.min_y;
+    if !(board_w.is_finite() && board_h.is_finite()) || board_w < 0.0 || board_h < 0.0 {
+        return None;
+    }
+    if !(viewport_w > 0.0 && viewport_h > 0.0) {
+        return None;
+    }
+
+    let scale_x = (board_w > f64::EPSILON).then(|| viewport_w / board_w);
+    let scale_y = (board_h > f64::EPSILON).then(|| viewport_h / board_h);
+    let scale = match (scale_x, scale_y) {
+        (Some(sx), Some(sy)) => sx.min(sy),
+        (Some(s), None) | (None, Some(s)) => s,
+        (None, None) => return None,
+    };
+
+    let translate_x = bounds
+        .min_x
+        .mul_add(-scale, board_w.mul_add(-scale, viewport_w) / 2.0);
+    let translate_y = bounds
+        .min_y
+        .mul_add(-scale, board_h.mul_add(-scale, viewport_h) / 2.0);
+
+    if flip_y {
+        Some([
+            scale,
+            0.0,
+            0.0,
+            -scale,
+            translate_x,
+            viewport_h - translate_y,
+        ])
+    } else {
+        Some([scale, 0.0, 0.0, scale, translate_x, translate_y])
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn square_bounds(min: f64, max: f64) -> BoundingBox {
+        let mut bounds = BoundingBox::new();
+        bounds.update(min, min);
+        bounds.update(max, max);
+        bounds
+    }
+
+    fn apply(matrix: [f32; 6], point: (f32, f32)) -> (f32, f32) {
+        let [scale_x, skew_y, skew_x, scale_y, translate_x, translate_y] = matrix;
+        (
+            scale_x.mul_add(point.0, skew_x.mul_add(point.1, translate_x)),
+            skew_y.mul_add(point.0, scale_y.mul_add(point.1, translate_y)),
+        )
+    }
+
+    fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < EPSILON && (actual.1 - expected.1).abs() < EPSILON,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn ten_mm_board_fills_hundred_pixel_viewport() {
+        let matrix = fit_transform(&square_bounds(5.0, 15.0), 100.0, 100.0, false);
+        assert_close((matrix[0], matrix[3]), (10.0, 10.0));
+        assert_close(apply(matrix, (5.0, 5.0)), (0.0, 0.0));
+        assert_close(apply(matrix, (15.0, 15.0)), (100.0, 100.0));
+    }
+
+    #[test]
+    fn flip_y_maps_board_top_to_screen_top() {
+        let matrix = fit_transform(&square_bounds(0.0, 10.0), 100.0, 100.0, true);
+        assert_close((matrix[0], matrix[3]), (10.0, -10.0));
+        assert_close(apply(matrix, (0.0, 10.0)), (0.0, 0.0));
+        assert_close(apply(matrix, (10.0, 0.0)), (100.0, 100.0));
+    }
+
+    #[test]
+    fn wide_viewport_centers_board_horizontally() {
+        let matrix = fit_transform(&square_bounds(0.0, 10.0), 200.0, 100.0, false);
+        assert_close(apply(matrix, (0.0, 0.0)), (50.0, 0.0));
+        assert_close(apply(matrix, (10.0, 10.0)), (150.0, 100.0));
+    }
+
+    #[test]
+    fn empty_bounds_return_identity() {
+        let empty = fit_transform(&BoundingBox::new(), 100.0, 100.0, true);
+        let zero_viewport = fit_transform(&square_bounds(0.0, 10.0), 0.0, 100.0, true);
+        assert_eq!(
+            empty.map(f32::to_bits),
+            IDENTITY_TRANSFORM.map(f32::to_bits)
+        );
+        assert_eq!(
+            zero_viewport.map(f32::to_bits),
+            IDENTITY_TRANSFORM.map(f32::to_bits)
+        );
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    #[test]
+    fn layer_affine_mirrors_before_rotating() {
+        let matrix = layer_affine(true, 90.0, 10.0, 0.0).map(|v| v as f32);
+        // (1, 0) mirrors to (-1, 0), rotates to (0, -1), then shifts.
+        assert_close(apply(matrix, (1.0, 0.0)), (10.0, -1.0));
+        assert_close(apply(matrix, (0.0, 1.0)), (9.0, 0.0));
+    }
+
+    #[test]
+    fn screen_to_board_inverts_flipped_fit() {
+        let bounds = square_bounds(-20.0, 35.0);
+        let Some(matrix) = fit_affine(&bounds, 640.0, 480.0, true) else {
+            return;
+        };
+        let (px, py) = board_to_screen(matrix, 12.5, -3.25);
+        let Some((x, y)) = screen_to_board(matrix, px, py) else {
+            return;
+        };
+        assert!((x - 12.5).abs() < 1e-9 && (y + 3.25).abs() < 1e-9);
+        assert!(screen_to_board([0.0; 6], 1.0, 1.0).is_none());
+    }
+}
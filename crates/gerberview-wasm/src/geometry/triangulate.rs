@@ -0,0 +1,256 @@
+//! Polygon triangulation backends for regions and macro outlines.
+//!
+//! [`EarclipTriangulator`] wraps `earclip` and is the default: fast, but
+//! best-effort on self-intersecting boundaries and cut-in holes, where it can
+//! emit triangles outside the fill or overlapping each other.
+//! [`RobustTriangulator`] detects those boundaries and decomposes them into
+//! vertical slabs instead, filling by the nonzero winding rule so the
+//! triangles never overlap. Simple boundaries still go through `earclip`.
+
+use super::types::Point;
+
+/// Triangles over a vertex buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Triangulation {
+    /// Interleaved vertex coordinates `[x0, y0, x1, y1, ...]`. May contain
+    /// points not on the input boundary, such as edge intersections.
+    pub vertices: Vec<f64>,
+    /// Triangle-list indices into `vertices`.
+    pub indices: Vec<usize>,
+}
+
+/// Triangulates a closed polygon boundary.
+pub trait Triangulator {
+    /// Triangulates the ring given as interleaved `[x0, y0, x1, y1, ...]`
+    /// coordinates. The ring may or may not repeat its first point at the
+    /// end. An empty result means no triangles could be produced.
+    fn triangulate(&self, ring: &[f64]) -> Triangulation;
+}
+
+/// Which [`Triangulator`] fills regions and outline primitives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriangulatorKind {
+    /// Ear clipping via `earclip` (default).
+    #[default]
+    Earclip,
+    /// [`RobustTriangulator`].
+    Robust,
+}
+
+impl TriangulatorKind {
+    /// Returns the backend for this kind.
+    #[must_use]
+    pub const fn backend(self) -> &'static dyn Triangulator {
+        match self {
+            Self::Earclip => &EarclipTriangulator,
+            Self::Robust => &RobustTriangulator,
+        }
+    }
+}
+
+/// Ear-clipping triangulation via `earclip`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EarclipTriangulator;
+
+impl Triangulator for EarclipTriangulator {
+    fn triangulate(&self, ring: &[f64]) -> Triangulation {
+        Triangulation {
+            vertices: ring.to_vec(),
+            indices: earclip::earcut::earcut(ring, &[], 2),
+        }
+    }
+}
+
+/// Triangulation that stays correct for self-intersecting boundaries and
+/// cut-in holes.
+///
+/// Detecting a non-simple boundary compares every pair of edges, so this
+/// backend costs quadratic time in the boundary length.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RobustTriangulator;
+
+impl Triangulator for RobustTriangulator {
+    fn triangulate(&self, ring: &[f64]) -> Triangulation {
+        let edges = ring_edges(ring);
+        if is_simple(&edges) {
+            EarclipTriangulator.triangulate(ring)
+        } else {
+            slab_triangulation(&edges)
+        }
+    }
+}
+
+/// Non-degenerate edges of the closed ring.
+fn ring_edges(ring: &[f64]) -> Vec<(Point, Point)> {
+    let points: Vec<Point> = ring
+        .chunks_exact(2)
+        .filter_map(|xy| match *xy {
+            [x, y] => Some(Point { x, y }),
+            _ => None,
+        })
+        .collect();
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .filter(|(a, b)| a != b)
+        .map(|(&a, &b)| (a, b))
+        .collect()
+}
+
+/// Returns whether no two non-adjacent edges touch or cross, and no two
+/// adjacent edges fold back onto each other.
+fn is_simple(edges: &[(Point, Point)]) -> bool {
+    let count = edges.len();
+    for (i, &(a, b)) in edges.iter().enumerate() {
+        for (j, &(c, d)) in edges.iter().enumerate().skip(i + 1) {
+            let adjacent = j == i + 1 || (i == 0 && j + 1 == count);
+            if adjacent {
+                let (shared, other_a, other_b) = if j == i + 1 { (b, a, d) } else { (a, b, c) };
+                let folds =
+                    cross(other_a, shared, other_b) == 0.0 && dot(other_a, shared, other_b) > 0.0;
+                if folds {
+                    return false;
+                }
+            } else if segments_touch(a, b, c, d) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Fills the ring by the nonzero winding rule, one vertical slab at a time.
+///
+/// Slab boundaries are placed at every vertex and edge crossing, so inside a
+/// slab the edges keep a fixed vertical order and each filled span between
+/// two edges is a trapezoid.
+fn slab_triangulation(edges: &[(Point, Point)]) -> Triangulation {
+    let mut xs: Vec<f64> = edges.iter().flat_map(|&(a, b)| [a.x, b.x]).collect();
+    for (i, &(a, b)) in edges.iter().enumerate() {
+        for &(c, d) in edges.iter().skip(i + 1) {
+            if let Some(x) = crossing_x(a, b, c, d) {
+                xs.push(x);
+            }
+        }
+    }
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+
+    let mut triangulation = Triangulation::default();
+    for slab in xs.windows(2) {
+        let &[left, right] = slab else {
+            continue;
+        };
+        if right <= left {
+            continue;
+        }
+        let middle = (left + right) / 2.0;
+        let mut crossings: Vec<(f64, f64, f64, i32)> = edges
+            .iter()
+            .filter(|(a, b)| a.x.min(b.x) <= left && a.x.max(b.x) >= right)
+            .map(|&(a, b)| {
+                let winding = if b.x > a.x { 1 } else { -1 };
+                (
+                    y_at(a, b, middle),
+                    y_at(a, b, left),
+                    y_at(a, b, right),
+                    winding,
+                )
+            })
+            .collect();
+        crossings.sort_by(|p, q| p.0.total_cmp(&q.0));
+
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            let &[(_, bottom_left, bottom_right, step), (_, top_left, top_right, _)] = pair else {
+                continue;
+            };
+            winding += step;
+            if winding != 0 {
+                push_trapezoid(
+                    &mut triangulation,
+                    [
+                        Point {
+                            x: left,
+                            y: bottom_left,
+                        },
+                        Point {
+                            x: right,
+                            y: bottom_right,
+                        },
+                        Point {
+                            x: right,
+                            y: top_right,
+                        },
+                        Point {
+                            x: left,
+                            y: top_left,
+                        },
+                    ],
+                );
+            }
+        }
+    }
+    triangulation
+}
+
+/// Appends the counter-clockwise quad as up to two triangles, skipping the
+/// side that collapses where two edges meet at a slab boundary.
+fn push_trapezoid(triangulation: &mut Triangulation, corners: [Point; 4]) {
+    let base = triangulation.vertices.len() / 2;
+    for corner in corners {
+        triangulation.vertices.push(corner.x);
+        triangulation.vertices.push(corner.y);
+    }
+    let [bottom_left, bottom_right, top_right, top_left] = corners;
+    if cross(bottom_left, bottom_right, top_right) > 0.0 {
+        triangulation.indices.extend([base, base + 1, base + 2]);
+    }
+    if cross(bottom_left, top_right, top_left) > 0.0 {
+        triangulation.indices.extend([base, base + 2, base + 3]);
+    }
+}
+
+/// X coordinate where segments `a-b` and `c-d` properly cross, if they do.
+fn crossing_x(a: Point, b: Point, c: Point, d: Point) -> Option<f64> {
+    let denominator = (b.x - a.x).mul_add(d.y - c.y, -((b.y - a.y) * (d.x - c.x)));
+    if denominator == 0.0 {
+        return None;
+    }
+    let along_ab = (c.x - a.x).mul_add(d.y - c.y, -((c.y - a.y) * (d.x - c.x))) / denominator;
+    let along_cd = (c.x - a.x).mul_add(b.y - a.y, -((c.y - a.y) * (b.x - a.x))) / denominator;
+    ((0.0..=1.0).contains(&along_ab) && (0.0..=1.0).contains(&along_cd))
+        .then(|| (b.x - a.x).mul_add(along_ab, a.x))
+}
+
+/// Returns whether closed segments `a-b` and `c-d` share any point.
+fn segments_touch(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && within_box(c, d, a))
+        || (d2 == 0.0 && within_box(c, d, b))
+        || (d3 == 0.0 && within_box(a, b, c))
+        || (d4 == 0.0 && within_box(a, b, d))
+}
+
+fn within_box(a: Point, b: Point, p: Point) -> bool {
+    a.x.min(b.x) <= p.x && p.x <= a.x.max(b.x) && a.y.min(b.y) <= p.y && p.y <= a.y.max(b.y)
+}
+
+fn y_at(a: Point, b: Point, x: f64) -> f64 {
+    (b.y - a.y).mul_add((x - a.x) / (b.x - a.x), a.y)
+}
+
+fn cross(a: Point, b: Point, p: Point) -> f64 {
+    (b.x - a.x).mul_add(p.y - a.y, -((b.y - a.y) * (p.x - a.x)))
+}
+
+/// Dot product of `a - shared` and `b - shared`.
+fn dot(a: Point, shared: Point, b: Point) -> f64 {
+    (a.x - shared.x).mul_add(b.x - shared.x, (a.y - shared.y) * (b.y - shared.y))
+}
@@ -1,7 +1,17 @@
 //! Core geometry types and the `GeometryBuilder` accumulator.
 
+use std::collections::HashMap;
+
 use serde::Serialize;
 
+use super::aperture_report::ApertureInfo;
+use super::attributes::AttributeRange;
+use super::options::{TessellationOptions, WarningCategory};
+use super::polarity::{ClearRange, ClearRangeSource};
+use super::region::RegionMetrics;
+use super::role::LayerRole;
+use crate::error::GeometryError;
+
 /// Saturating conversion from `usize` to `u32`.
 ///
 /// Real-world Gerber/Excellon files cannot produce counts exceeding
@@ -12,6 +22,18 @@ pub(crate) fn saturate_u32(n: usize) -> u32 {
     n.min(u32::MAX as usize) as u32
 }
 
+/// Returns whether triangle-index `offset` lies in one of the `(start, end)`
+/// ranges, which must be sorted by start.
+pub(crate) fn index_in_ranges(sorted_ranges: &[(u32, u32)], offset: usize) -> bool {
+    let Ok(offset) = u32::try_from(offset) else {
+        return false;
+    };
+    let upper = sorted_ranges.partition_point(|&(start, _)| start <= offset);
+    sorted_ranges
+        .get(..upper)
+        .is_some_and(|ranges| ranges.iter().any(|&(_, end)| offset < end))
+}
+
 /// 2D point in board coordinate space.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point {
@@ -80,6 +102,17 @@ pub enum InterpolationMode {
     CounterClockwiseArc,
 }
 
+/// Operation code (`D01`/`D02`/`D03`) of the last explicit operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationCode {
+    /// Interpolate (D01).
+    Interpolate,
+    /// Move (D02).
+    Move,
+    /// Flash (D03).
+    Flash,
+}
+
 /// Mutable state machine tracking the Gerber interpreter state
 /// as commands are processed sequentially.
 #[derive(Debug)]
@@ -98,6 +131,9 @@ pub struct GerberState {
     pub units: Option<gerber_types::Unit>,
     /// Coordinate format from the file header.
     pub format: Option<gerber_types::CoordinateFormat>,
+    /// Last explicit operation code, repeated by deprecated coordinate
+    /// blocks that omit their own.
+    pub modal_operation: Option<OperationCode>,
 }
 
 impl Default for GerberState {
@@ -110,6 +146,7 @@ impl Default for GerberState {
             region_points: Vec::new(),
             units: None,
             format: None,
+            modal_operation: None,
         }
     }
 }
@@ -119,28 +156,497 @@ impl Default for GerberState {
 /// Positions are interleaved `[x0, y0, x1, y1, ...]` as `f32` for WebGL.
 /// Indices reference into the positions array as a triangle list.
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct LayerGeometry {
     /// Interleaved vertex positions `[x0, y0, x1, y1, ...]`.
     pub positions: Vec<f32>,
+    /// Full-precision copy of `positions`; empty unless
+    /// [`TessellationOptions::keep_f64_positions`] is enabled.
+    pub positions_f64: Vec<f64>,
     /// Triangle-list indices into the positions array.
     pub indices: Vec<u32>,
     /// Axis-aligned bounding box of all vertices.
     pub bounds: BoundingBox,
+    /// Bounding box of dark geometry only, ignoring clear-range triangles.
+    pub dark_bounds: BoundingBox,
     /// Number of Gerber commands processed.
     pub command_count: u32,
     /// Number of vertices (`positions.len() / 2`).
     pub vertex_count: u32,
     /// Warning messages generated during conversion.
     pub warnings: Vec<String>,
+    /// Index of the source command that raised each warning, parallel to
+    /// `warnings`; `None` for warnings not tied to a single command.
+    pub warning_commands: Vec<Option<u32>>,
+    /// Strict-mode category of each warning, parallel to `warnings`; `None`
+    /// for warnings strict mode never promotes.
+    pub warning_categories: Vec<Option<WarningCategory>>,
     /// Index ranges for clear-polarity geometry `(start, end)` pairs.
     pub clear_ranges: Vec<(u32, u32)>,
+    /// Source of each entry of `clear_ranges`, in the same order.
+    pub clear_range_sources: Vec<ClearRangeSource>,
+    /// Index ranges created under Gerber X2 object attributes (`%TO`).
+    pub attribute_ranges: Vec<AttributeRange>,
+    /// Area and perimeter of each filled region, in fill order.
+    pub region_metrics: Vec<RegionMetrics>,
+    /// Whether conversion stopped early at its triangle or time budget.
+    pub truncated: bool,
+    /// Features that were skipped as unsupported, without duplicates.
+    pub unsupported_features: Vec<String>,
+    /// Integer and decimal digits of the Gerber coordinate format; `None`
+    /// for geometry not converted from Gerber.
+    pub coordinate_format: Option<(u8, u8)>,
+    /// Whether the file had no `%FS` and the default format was assumed.
+    pub format_assumed: bool,
+    /// Whether the file had no `%MO` and millimeters were assumed.
+    pub units_assumed: bool,
+    /// Role of the layer on the board, when known.
+    pub layer_role: LayerRole,
+    /// Image name from the deprecated `%IN` directive.
+    pub layer_name: Option<String>,
+    /// Whether the deprecated `%IPNEG*%` made the whole image negative.
+    ///
+    /// Geometry is still built as for a positive image: dark objects are in
+    /// the index buffer and clear objects in `clear_ranges`. A renderer
+    /// shows a negative image by filling the layer extent and drawing the
+    /// dark objects in the background color, which also keeps `%LPC`
+    /// objects relative to the image polarity.
+    pub image_negative: bool,
+    /// Fans that can be drawn as `TRIANGLE_FAN` primitives; empty unless
+    /// [`TessellationOptions::record_fans`] is enabled.
+    pub fan_ranges: Vec<FanRange>,
+    /// Aperture table of the converted Gerber file with flash and draw
+    /// counts, ordered by D-code; empty for other geometry.
+    pub apertures: Vec<ApertureInfo>,
+}
+
+impl LayerGeometry {
+    /// Returns the clear ranges tagged with their source. Ranges without a
+    /// recorded source are reported as [`ClearRangeSource::MacroExposure`].
+    pub fn tagged_clear_ranges(&self) -> Vec<ClearRange> {
+        self.clear_ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(index_start, index_end))| ClearRange {
+                index_start,
+                index_end,
+                source: self
+                    .clear_range_sources
+                    .get(i)
+                    .copied()
+                    .unwrap_or(ClearRangeSource::MacroExposure),
+            })
+            .collect()
+    }
+
+    /// Recomputes [`Self::dark_bounds`] from the triangles outside
+    /// `clear_ranges`. Call after changing the clear ranges.
+    pub fn refresh_dark_bounds(&mut self) {
+        let mut clear_ranges = self.clear_ranges.clone();
+        clear_ranges.sort_unstable();
+
+        let mut dark_bounds = BoundingBox::new();
+        for (triangle_index, triangle) in self.indices.chunks_exact(3).enumerate() {
+            if index_in_ranges(&clear_ranges, triangle_index.saturating_mul(3)) {
+                continue;
+            }
+            for &index in triangle {
+                let base = index as usize * 2;
+                if let (Some(&x), Some(&y)) =
+                    (self.positions.get(base), self.positions.get(base + 1))
+                {
+                    dark_bounds.update(f64::from(x), f64::from(y));
+                }
+            }
+        }
+        self.dark_bounds = dark_bounds;
+    }
+
+    /// Drops every triangle past the first `max_triangles`, clipping clear
+    /// and attribute ranges to the remaining index buffer.
+    pub fn truncate_triangles(&mut self, max_triangles: u32) {
+        let limit = max_triangles.saturating_mul(3);
+        if self.indices.len() <= limit as usize {
+            return;
+        }
+        self.indices.truncate(limit as usize);
+
+        let clear_ranges: Vec<ClearRange> = self
+            .tagged_clear_ranges()
+            .into_iter()
+            .filter(|range| range.index_start < limit)
+            .map(|mut range| {
+                range.index_end = range.index_end.min(limit);
+                range
+            })
+            .collect();
+        self.clear_ranges = clear_ranges
+            .iter()
+            .map(|r| (r.index_start, r.index_end))
+            .collect();
+        self.clear_range_sources = clear_ranges.iter().map(|r| r.source).collect();
+        self.attribute_ranges
+            .retain(|range| range.index_start < limit);
+        for range in &mut self.attribute_ranges {
+            range.index_end = range.index_end.min(limit);
+        }
+        self.fan_ranges.retain(|fan| fan.index_end() <= limit);
+        self.refresh_dark_bounds();
+    }
+
+    /// Mirrors the geometry to `y' = axis - y` for Y-down screen space.
+    ///
+    /// Bounds are mirrored with it, and every triangle's winding is reversed
+    /// so triangles keep their orientation on screen. Index ranges are
+    /// unaffected.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn flip_y(&mut self, axis: f64) {
+        for y in self.positions.iter_mut().skip(1).step_by(2) {
+            *y = (axis - f64::from(*y)) as f32;
+        }
+        for y in self.positions_f64.iter_mut().skip(1).step_by(2) {
+            *y = axis - *y;
+        }
+        for triangle in self.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        for bounds in [&mut self.bounds, &mut self.dark_bounds] {
+            if bounds.min_y <= bounds.max_y {
+                (bounds.min_y, bounds.max_y) = (axis - bounds.max_y, axis - bounds.min_y);
+            }
+        }
+    }
+
+    /// Returns only the triangles inside [`Self::clear_ranges`] as a
+    /// standalone mesh, with the vertices they use copied and re-indexed
+    /// from zero.
+    ///
+    /// Meant for debugging clearances: the result can be drawn in its own
+    /// color without cross-referencing index ranges.
+    #[must_use]
+    pub fn clear_geometry(&self) -> Self {
+        self.submesh(self.clear_ranges.iter().flat_map(|&(start, end)| {
+            self.indices
+                .get(start as usize..end as usize)
+                .unwrap_or_default()
+                .chunks_exact(3)
+        }))
+    }
+
+    /// Partitions the triangles into a dark mesh and a clear mesh, each
+    /// with its own vertex buffer re-indexed from zero.
+    ///
+    /// A triangle is clear when its first index lies in
+    /// [`Self::clear_ranges`]. Every triangle lands in exactly one of the
+    /// two meshes, for renderers that draw each polarity as a separate pass.
+    #[must_use]
+    pub fn split_by_polarity(&self) -> (Self, Self) {
+        let mut clear_ranges = self.clear_ranges.clone();
+        clear_ranges.sort_unstable();
+        let is_clear = |(triangle_index, _): &(usize, &[u32])| {
+            index_in_ranges(&clear_ranges, triangle_index * 3)
+        };
+
+        let triangles = || self.indices.chunks_exact(3).enumerate();
+        let dark = self.submesh(
+            triangles()
+                .filter(|triangle| !is_clear(triangle))
+                .map(|(_, triangle)| triangle),
+        );
+        let clear = self.submesh(triangles().filter(is_clear).map(|(_, triangle)| triangle));
+        (dark, clear)
+    }
+
+    /// Builds a standalone mesh from `triangles`, copying the vertices they
+    /// use in first-use order.
+    fn submesh<'a>(&self, triangles: impl Iterator<Item = &'a [u32]>) -> Self {
+        let mut builder = GeometryBuilder::new();
+        let mut remap: HashMap<u32, u32> = HashMap::new();
+        for triangle in triangles {
+            let mut corners = [0; 3];
+            for (corner, &index) in corners.iter_mut().zip(triangle) {
+                *corner = *remap.entry(index).or_insert_with(|| {
+                    let base = index as usize * 2;
+                    let x = self.positions.get(base).copied().unwrap_or_default();
+                    let y = self.positions.get(base + 1).copied().unwrap_or_default();
+                    builder.push_vertex(f64::from(x), f64::from(y))
+                });
+            }
+            let [a, b, c] = corners;
+            builder.push_triangle(a, b, c);
+        }
+        builder.build()
+    }
+
+    /// Returns a copy of the mesh in which every triangle owns its three
+    /// vertices, so the index buffer is `0, 1, 2, ...` and `vertex_count`
+    /// equals the index count.
+    ///
+    /// Meant for debugging triangulation: individual triangles can be
+    /// colored or picked and their winding checked. Index ranges such as
+    /// `clear_ranges` stay valid; `fan_ranges` are dropped since fans no
+    /// longer share a center vertex.
+    #[must_use]
+    pub fn explode(&self) -> Self {
+        fn gather<T: Copy + Default>(values: &[T], indices: &[u32]) -> Vec<T> {
+            indices
+                .iter()
+                .flat_map(|&index| {
+                    let base = index as usize * 2;
+                    [base, base + 1].map(|i| values.get(i).copied().unwrap_or_default())
+                })
+                .collect()
+        }
+
+        let mut exploded = self.clone();
+        exploded.positions = gather(&self.positions, &self.indices);
+        if !self.positions_f64.is_empty() {
+            exploded.positions_f64 = gather(&self.positions_f64, &self.indices);
+        }
+        exploded.indices = (0..saturate_u32(self.indices.len())).collect();
+        exploded.vertex_count = saturate_u32(self.indices.len());
+        exploded.fan_ranges.clear();
+        exploded
+    }
+
+    /// Returns a line-list index buffer with every distinct triangle edge
+    /// once, for wireframe overlays drawn with `gl.LINES` over the same
+    /// positions.
+    ///
+    /// Edges are ordered by their lower then higher vertex index.
+    pub fn wireframe_indices(&self) -> Vec<u32> {
+        let mut edges: Vec<(u32, u32)> = self
+            .indices
+            .chunks_exact(3)
+            .flat_map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| triangle.get(i).copied().unwrap_or_default());
+                [(a, b), (b, c), (c, a)]
+            })
+            .map(|(from, to)| (from.min(to), from.max(to)))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges.into_iter().flat_map(<[u32; 2]>::from).collect()
+    }
+
+    /// Checks that every index references one of the `vertex_count`
+    /// vertices, so a faulty emitter is caught before the buffers reach the
+    /// GPU.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GeometryError::DegenerateGeometry`] naming the first index
+    /// that is out of range.
+    pub fn validate(&self) -> Result<(), GeometryError> {
+        match self
+            .indices
+            .iter()
+            .enumerate()
+            .find(|&(_, &index)| index >= self.vertex_count)
+        {
+            Some((position, index)) => Err(GeometryError::DegenerateGeometry(format!(
+                "index {position} references vertex {index} of {}",
+                self.vertex_count
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the affine `matrix` (`[a, b, c, d, e, f]`, see
+    /// [`super::transform`]) to every vertex.
+    ///
+    /// Bounds are recomputed from the moved vertices, and when the matrix
+    /// mirrors (negative determinant) every triangle's winding is reversed.
+    /// Region metrics are left as they are, which is exact for the rigid
+    /// mirror, rotate and translate transforms this is meant for.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn transform(&mut self, matrix: [f64; 6]) {
+        let [scale_x, skew_y, skew_x, scale_y, translate_x, translate_y] = matrix;
+        let apply = |x: f64, y: f64| {
+            (
+                scale_x.mul_add(x, skew_x.mul_add(y, translate_x)),
+                skew_y.mul_add(x, scale_y.mul_add(y, translate_y)),
+            )
+        };
+
+        let mut bounds = BoundingBox::new();
+        if self.positions_f64.len() == self.positions.len() {
+            for (exact, rounded) in self
+                .positions_f64
+                .chunks_exact_mut(2)
+                .zip(self.positions.chunks_exact_mut(2))
+            {
+                if let ([x, y], [rx, ry]) = (exact, rounded) {
+                    (*x, *y) = apply(*x, *y);
+                    (*rx, *ry) = (*x as f32, *y as f32);
+                    bounds.update(*x, *y);
+                }
+            }
+        } else {
+            for vertex in self.positions.chunks_exact_mut(2) {
+                if let [x, y] = vertex {
+                    let (tx, ty) = apply(f64::from(*x), f64::from(*y));
+                    (*x, *y) = (tx as f32, ty as f32);
+                    bounds.update(tx, ty);
+                }
+            }
+        }
+        if scale_x.mul_add(scale_y, -(skew_y * skew_x)) < 0.0 {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+        self.bounds = bounds;
+        if self.clear_ranges.is_empty() {
+            self.dark_bounds = bounds;
+        } else {
+            self.refresh_dark_bounds();
+        }
+    }
+
+    /// Appends `other` after this geometry as one combined mesh.
+    ///
+    /// Indices and index ranges of `other` are shifted past the existing
+    /// buffers; bounds, warnings, and command counts are merged, and aperture
+    /// tables are concatenated. Retained `f64` positions are kept only when
+    /// both sides have them.
+    pub fn append(&mut self, other: Self) {
+        let vertex_offset = self.vertex_count;
+        let index_offset = saturate_u32(self.indices.len());
+        let other_sources = other.tagged_clear_ranges();
+
+        if self.positions_f64.len() == self.positions.len()
+            && other.positions_f64.len() == other.positions.len()
+        {
+            self.positions_f64.extend(other.positions_f64);
+        } else {
+            self.positions_f64.clear();
+        }
+        self.positions.extend(other.positions);
+        self.indices.extend(
+            other
+                .indices
+                .iter()
+                .map(|i| i.saturating_add(vertex_offset)),
+        );
+        self.clear_range_sources
+            .resize(self.clear_ranges.len(), ClearRangeSource::MacroExposure);
+        self.clear_range_sources
+            .extend(other_sources.iter().map(|r| r.source));
+        self.clear_ranges
+            .extend(other.clear_ranges.iter().map(|&(start, end)| {
+                (
+                    start.saturating_add(index_offset),
+                    end.saturating_add(index_offset),
+                )
+            }));
+        self.attribute_ranges
+            .extend(other.attribute_ranges.into_iter().map(|mut range| {
+                range.index_start = range.index_start.saturating_add(index_offset);
+                range.index_end = range.index_end.saturating_add(index_offset);
+                range
+            }));
+        self.region_metrics.extend(other.region_metrics);
+        self.apertures.extend(other.apertures);
+        self.fan_ranges
+            .extend(other.fan_ranges.iter().map(|fan| FanRange {
+                center: fan.center.saturating_add(vertex_offset),
+                rim_count: fan.rim_count,
+                index_start: fan.index_start.saturating_add(index_offset),
+            }));
+        self.truncated |= other.truncated;
+        self.coordinate_format = self.coordinate_format.or(other.coordinate_format);
+        self.format_assumed |= other.format_assumed;
+        self.units_assumed |= other.units_assumed;
+        if self.layer_role == LayerRole::Other {
+            self.layer_role = other.layer_role;
+        }
+        self.layer_name = self.layer_name.take().or(other.layer_name);
+        self.image_negative |= other.image_negative;
+        for feature in other.unsupported_features {
+            if !self.unsupported_features.contains(&feature) {
+                self.unsupported_features.push(feature);
+            }
+        }
+        if other.vertex_count > 0 {
+            self.bounds.update(other.bounds.min_x, other.bounds.min_y);
+            self.bounds.update(other.bounds.max_x, other.bounds.max_y);
+        }
+        if other.dark_bounds.min_x <= other.dark_bounds.max_x {
+            self.dark_bounds
+                .update(other.dark_bounds.min_x, other.dark_bounds.min_y);
+            self.dark_bounds
+                .update(other.dark_bounds.max_x, other.dark_bounds.max_y);
+        }
+        self.command_count = self.command_count.saturating_add(other.command_count);
+        self.vertex_count = saturate_u32(self.positions.len() / 2);
+        self.warning_commands.resize(self.warnings.len(), None);
+        self.warning_commands.extend(
+            (0..other.warnings.len()).map(|i| other.warning_commands.get(i).copied().flatten()),
+        );
+        self.warning_categories.resize(self.warnings.len(), None);
+        self.warning_categories.extend(
+            (0..other.warnings.len()).map(|i| other.warning_categories.get(i).copied().flatten()),
+        );
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Returns each warning paired with the index of the command that
+    /// raised it, if known.
+    pub fn warning_details(&self) -> Vec<WarningDetail> {
+        self.warnings
+            .iter()
+            .enumerate()
+            .map(|(i, message)| WarningDetail {
+                message: message.clone(),
+                command_index: self.warning_commands.get(i).copied().flatten(),
+            })
+            .collect()
+    }
+}
+
+/// A triangle fan over consecutive vertices, also present in the index
+/// buffer as expanded triangles.
+///
+/// The renderer can draw `rim_count + 1` vertices from `center` with
+/// `gl.TRIANGLE_FAN` instead of the `3 * (rim_count - 1)` indices from
+/// `index_start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FanRange {
+    /// Index of the fan's center vertex; the rim follows it directly.
+    pub center: u32,
+    /// Number of rim vertices after `center`.
+    pub rim_count: u32,
+    /// Start of the fan's expanded triangles in the index buffer.
+    pub index_start: u32,
+}
+
+impl FanRange {
+    /// End (exclusive) of the fan's expanded triangles in the index buffer.
+    #[must_use]
+    pub const fn index_end(&self) -> u32 {
+        self.index_start
+            .saturating_add(self.rim_count.saturating_sub(1).saturating_mul(3))
+    }
+}
+
+/// A warning with the position of the command that raised it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WarningDetail {
+    /// Warning message, as in [`LayerMeta::warnings`].
+    pub message: String,
+    /// Zero-based index of the offending command in the parsed command list.
+    pub command_index: Option<u32>,
 }
 
 /// Metadata returned to JavaScript for a parsed layer.
 #[derive(Debug, Clone, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct LayerMeta {
     /// Axis-aligned bounding box.
     pub bounds: BoundingBox,
+    /// Bounding box of dark (non-clear) geometry only.
+    pub dark_bounds: BoundingBox,
     /// Number of vertices.
     pub vertex_count: u32,
     /// Number of triangle indices.
@@ -151,6 +657,82 @@ pub struct LayerMeta {
     pub warning_count: u32,
     /// Warning messages.
     pub warnings: Vec<String>,
+    /// Warning messages with the command index that raised each one.
+    pub warning_details: Vec<WarningDetail>,
+    /// Whether conversion stopped early and the geometry is partial.
+    pub truncated: bool,
+    /// Area-weighted centroid of the dark geometry as `(x, y)`.
+    pub centroid: (f64, f64),
+    /// Features the layer uses that were not rendered, such as `thermal`.
+    pub unsupported_features: Vec<String>,
+    /// Integer and decimal digits of the coordinate format used.
+    pub coordinate_format: Option<(u8, u8)>,
+    /// Whether the file lacked `%FS` and the default format was assumed.
+    pub format_assumed: bool,
+    /// Whether the file lacked `%MO` and millimeters were assumed.
+    pub units_assumed: bool,
+    /// Role of the layer on the board, from its X2 file function.
+    pub layer_role: LayerRole,
+    /// Suggested fill color for the role as `[r, g, b, a]`.
+    pub suggested_color_rgba: [u8; 4],
+    /// Suggested z-order for the role; higher is drawn on top.
+    pub render_priority: i32,
+    /// Image name from `%IN`, if the file has one.
+    pub layer_name: Option<String>,
+    /// Whether `%IPNEG*%` marked the image negative.
+    pub image_negative: bool,
+}
+
+/// Result of validating a file without building geometry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    /// Number of commands (Gerber) or holes (Excellon) processed.
+    pub command_count: u32,
+    /// Number of warnings raised by the parser and interpreter.
+    pub warning_count: u32,
+    /// Whether the file could not be read at all.
+    pub fatal: bool,
+    /// Description of the fatal error, if any.
+    pub error: Option<String>,
+}
+
+impl ValidationReport {
+    /// Creates a report for a file that failed fatally.
+    pub const fn failed(error: String) -> Self {
+        Self {
+            command_count: 0,
+            warning_count: 0,
+            fatal: true,
+            error: Some(error),
+        }
+    }
+}
+
+impl LayerMeta {
+    /// Summarizes `geometry` for JavaScript.
+    pub fn from_geometry(geometry: &LayerGeometry) -> Self {
+        Self {
+            bounds: geometry.bounds,
+            dark_bounds: geometry.dark_bounds,
+            vertex_count: geometry.vertex_count,
+            index_count: saturate_u32(geometry.indices.len()),
+            command_count: geometry.command_count,
+            warning_count: saturate_u32(geometry.warnings.len()),
+            warnings: geometry.warnings.clone(),
+            warning_details: geometry.warning_details(),
+            truncated: geometry.truncated,
+            centroid: super::measure::centroid(geometry),
+            unsupported_features: geometry.unsupported_features.clone(),
+            coordinate_format: geometry.coordinate_format,
+            format_assumed: geometry.format_assumed,
+            units_assumed: geometry.units_assumed,
+            layer_role: geometry.layer_role,
+            suggested_color_rgba: geometry.layer_role.suggested_color_rgba(),
+            render_priority: geometry.layer_role.render_priority(),
+            layer_name: geometry.layer_name.clone(),
+            image_negative: geometry.image_negative,
+        }
+    }
 }
 
 /// Accumulator for building layer geometry incrementally.
@@ -160,11 +742,24 @@ pub struct LayerMeta {
 #[derive(Debug)]
 pub struct GeometryBuilder {
     positions: Vec<f32>,
+    positions_f64: Vec<f64>,
     indices: Vec<u32>,
     bounds: BoundingBox,
     warnings: Vec<String>,
+    warning_commands: Vec<Option<u32>>,
+    warning_categories: Vec<Option<WarningCategory>>,
+    /// Command index attached to warnings as they are recorded.
+    command_index: Option<u32>,
+    /// D-code and operation kind named in zero-size aperture warnings.
+    aperture_use: Option<(i32, &'static str)>,
     /// Index ranges for clear-polarity geometry, populated by macro evaluator.
     clear_ranges: Vec<(u32, u32)>,
+    clear_range_sources: Vec<ClearRangeSource>,
+    region_metrics: Vec<RegionMetrics>,
+    fan_ranges: Vec<FanRange>,
+    attribute_ranges: Vec<AttributeRange>,
+    unsupported_features: Vec<String>,
+    options: TessellationOptions,
 }
 
 impl GeometryBuilder {
@@ -172,19 +767,45 @@ impl GeometryBuilder {
     pub const fn new() -> Self {
         Self {
             positions: Vec::new(),
+            positions_f64: Vec::new(),
             indices: Vec::new(),
             bounds: BoundingBox::new(),
             warnings: Vec::new(),
+            warning_commands: Vec::new(),
+            warning_categories: Vec::new(),
+            command_index: None,
+            aperture_use: None,
             clear_ranges: Vec::new(),
+            clear_range_sources: Vec::new(),
+            region_metrics: Vec::new(),
+            fan_ranges: Vec::new(),
+            attribute_ranges: Vec::new(),
+            unsupported_features: Vec::new(),
+            options: TessellationOptions::new(),
         }
     }
 
+    /// Creates an empty builder that tessellates with the given options.
+    pub const fn with_options(options: TessellationOptions) -> Self {
+        let mut builder = Self::new();
+        builder.options = options;
+        builder
+    }
+
+    /// Returns the tessellation options in effect for this builder.
+    pub const fn options(&self) -> &TessellationOptions {
+        &self.options
+    }
+
     /// Adds a vertex and returns its index.
     #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
     pub fn push_vertex(&mut self, x: f64, y: f64) -> u32 {
         let idx = self.positions.len() / 2;
         self.positions.push(x as f32);
         self.positions.push(y as f32);
+        if self.options.keep_f64_positions {
+            self.positions_f64.extend_from_slice(&[x, y]);
+        }
         self.bounds.update(x, y);
         idx as u32
     }
@@ -202,6 +823,66 @@ impl GeometryBuilder {
         self.push_triangle(a, c, d);
     }
 
+    /// Adds a quad like [`Self::push_quad`], without relying on the corner
+    /// order.
+    ///
+    /// The four vertices are taken in the given order, or reordered if the
+    /// outline crosses itself, and split along whichever diagonal yields two
+    /// non-degenerate triangles of the same orientation. Both triangles are
+    /// emitted counter-clockwise. A quad with no such split, such as four
+    /// collinear corners, adds nothing and records a warning. Returns whether
+    /// triangles were added.
+    pub fn push_quad_checked(&mut self, a: u32, b: u32, c: u32, d: u32) -> bool {
+        let orders = [[a, b, c, d], [a, b, d, c], [a, c, b, d]];
+        for [p, q, r, s] in orders {
+            for triangles in [[[p, q, r], [p, r, s]], [[p, q, s], [q, r, s]]] {
+                let [Some(first), Some(second)] = triangles.map(|t| self.triangle_area(t)) else {
+                    self.warn(format!(
+                        "quad ({a}, {b}, {c}, {d}) references missing vertices; skipping"
+                    ));
+                    return false;
+                };
+                if first * second > 0.0 {
+                    for [i, j, k] in triangles {
+                        if first > 0.0 {
+                            self.push_triangle(i, j, k);
+                        } else {
+                            self.push_triangle(i, k, j);
+                        }
+                    }
+                    return true;
+                }
+            }
+        }
+        self.warn(format!("degenerate quad ({a}, {b}, {c}, {d}); skipping"));
+        false
+    }
+
+    /// Signed doubled area of a triangle of pushed vertices, positive when
+    /// counter-clockwise.
+    fn triangle_area(&self, [a, b, c]: [u32; 3]) -> Option<f64> {
+        let [a, b, c] = [a, b, c].map(|index| self.vertex_point(index));
+        let (a, b, c) = (a?, b?, c?);
+        Some((b.x - a.x).mul_add(c.y - a.y, -((b.y - a.y) * (c.x - a.x))))
+    }
+
+    fn vertex_point(&self, index: u32) -> Option<Point> {
+        let base = (index as usize).checked_mul(2)?;
+        if self.options.keep_f64_positions {
+            let &[x, y] = self.positions_f64.get(base..base + 2)? else {
+                return None;
+            };
+            return Some(Point { x, y });
+        }
+        let &[x, y] = self.positions.get(base..base + 2)? else {
+            return None;
+        };
+        Some(Point {
+            x: f64::from(x),
+            y: f64::from(y),
+        })
+    }
+
     /// Adds an N-gon centered at `(cx, cy)` with the given `radius` and `segments`.
     ///
     /// Vertices are placed on a circle and fan-triangulated from the first vertex.
@@ -218,16 +899,170 @@ impl GeometryBuilder {
             );
         }
 
-        for i in 1..segments.saturating_sub(1) {
-            self.push_triangle(first, first + i, first + i + 1);
+        self.push_fan_range(first, first + 1, segments.saturating_sub(1));
+
+        first
+    }
+
+    /// Adds an annulus centered at `(cx, cy)` between `inner_radius` and
+    /// `outer_radius`, as a closed triangle strip around the ring.
+    ///
+    /// Returns the index of the first vertex. The center is left uncovered.
+    pub fn push_ring(
+        &mut self,
+        cx: f64,
+        cy: f64,
+        outer_radius: f64,
+        inner_radius: f64,
+        segments: u32,
+    ) -> u32 {
+        let first = self.push_vertex(cx + outer_radius, cy);
+        self.push_vertex(cx + inner_radius, cy);
+        for i in 1..segments {
+            let angle = 2.0 * std::f64::consts::PI * f64::from(i) / f64::from(segments);
+            let (sin, cos) = angle.sin_cos();
+            self.push_vertex(outer_radius.mul_add(cos, cx), outer_radius.mul_add(sin, cy));
+            self.push_vertex(inner_radius.mul_add(cos, cx), inner_radius.mul_add(sin, cy));
         }
 
+        let strip: Vec<u32> = (0..segments * 2).chain([0, 1]).map(|i| first + i).collect();
+        self.push_strip(&strip);
+
         first
     }
 
+    /// Adds a triangle fan `(center, rim[i], rim[i + 1])` for each consecutive
+    /// pair of rim indices.
+    ///
+    /// Indices are reserved once up front. Fewer than two rim indices add nothing.
+    ///
+    /// With [`TessellationOptions::record_fans`], a fan whose rim directly
+    /// follows `center` in the vertex buffer is also recorded as a
+    /// [`FanRange`].
+    pub fn push_fan(&mut self, center: u32, rim: &[u32]) {
+        let triangles = rim.len().saturating_sub(1);
+        if self.options.record_fans && triangles > 0 {
+            let contiguous = rim
+                .iter()
+                .zip(1u32..)
+                .all(|(&index, step)| Some(index) == center.checked_add(step));
+            if contiguous {
+                self.fan_ranges.push(FanRange {
+                    center,
+                    rim_count: saturate_u32(rim.len()),
+                    index_start: self.index_count(),
+                });
+            }
+        }
+        self.indices.reserve(triangles * 3);
+        for pair in rim.windows(2) {
+            if let [b, c] = *pair {
+                self.indices.extend_from_slice(&[center, b, c]);
+            }
+        }
+    }
+
+    /// Adds a triangle fan like [`Self::push_fan`] whose rim is the `count`
+    /// consecutive vertices starting at `first_rim`, without collecting the
+    /// rim indices first.
+    pub fn push_fan_range(&mut self, center: u32, first_rim: u32, count: u32) {
+        let triangles = count.saturating_sub(1);
+        if triangles == 0 {
+            return;
+        }
+        if self.options.record_fans && center.checked_add(1) == Some(first_rim) {
+            self.fan_ranges.push(FanRange {
+                center,
+                rim_count: count,
+                index_start: self.index_count(),
+            });
+        }
+        self.indices.reserve(triangles as usize * 3);
+        for b in first_rim..first_rim.saturating_add(triangles) {
+            self.indices.extend_from_slice(&[center, b, b + 1]);
+        }
+    }
+
+    /// Adds a triangle strip over `strip`.
+    ///
+    /// Triangle `i` uses `strip[i..i + 3]`; odd triangles swap their first two
+    /// indices so every triangle keeps the winding of the first. Indices are
+    /// reserved once up front. Fewer than three indices add nothing.
+    pub fn push_strip(&mut self, strip: &[u32]) {
+        let triangles = strip.len().saturating_sub(2);
+        self.indices.reserve(triangles * 3);
+        for (i, window) in strip.windows(3).enumerate() {
+            if let [a, b, c] = *window {
+                if i % 2 == 0 {
+                    self.indices.extend_from_slice(&[a, b, c]);
+                } else {
+                    self.indices.extend_from_slice(&[b, a, c]);
+                }
+            }
+        }
+    }
+
     /// Records a warning message.
     pub fn warn(&mut self, msg: String) {
+        self.warn_at(msg, self.command_index);
+    }
+
+    /// Adds a warning raised by the command at `command_index`.
+    pub fn warn_at(&mut self, msg: String, command_index: Option<u32>) {
+        self.record_warning(msg, command_index, None);
+    }
+
+    /// Records a warning in a category that strict mode can promote.
+    pub fn warn_in(&mut self, category: WarningCategory, msg: String) {
+        self.record_warning(msg, self.command_index, Some(category));
+    }
+
+    /// Adds a warning with its command index and strict-mode category.
+    pub fn record_warning(
+        &mut self,
+        msg: String,
+        command_index: Option<u32>,
+        category: Option<WarningCategory>,
+    ) {
         self.warnings.push(msg);
+        self.warning_commands.push(command_index);
+        self.warning_categories.push(category);
+    }
+
+    /// Sets the command index attached to subsequent [`Self::warn`] calls.
+    pub fn set_command_index(&mut self, command_index: Option<u32>) {
+        self.command_index = command_index;
+    }
+
+    /// Sets the D-code and operation kind (`flash`, `stroke` or `arc`)
+    /// named by subsequent [`Self::warn_zero_dimension`] calls.
+    pub fn set_aperture_use(&mut self, aperture_use: Option<(i32, &'static str)>) {
+        self.aperture_use = aperture_use;
+    }
+
+    /// Records that the aperture dimension `label` is zero and the
+    /// operation using it is skipped, naming the D-code and operation set
+    /// by [`Self::set_aperture_use`].
+    pub fn warn_zero_dimension(&mut self, label: &str) {
+        let message = match self.aperture_use {
+            Some((dcode, operation)) => {
+                format!("D{dcode} {label} is zero; skipping {operation}")
+            }
+            None => format!("{label} is zero; skipping aperture"),
+        };
+        self.warn(message);
+    }
+
+    /// Returns the warnings recorded so far.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Removes and returns the warnings recorded so far.
+    pub(crate) fn take_warnings(&mut self) -> Vec<String> {
+        self.warning_commands.clear();
+        self.warning_categories.clear();
+        std::mem::take(&mut self.warnings)
     }
 
     /// Records an index range for clear-polarity geometry.
@@ -236,6 +1071,41 @@ impl GeometryBuilder {
     pub fn record_clear_range(&mut self, start: u32, end: u32) {
         if end > start {
             self.clear_ranges.push((start, end));
+            self.clear_range_sources
+                .push(ClearRangeSource::MacroExposure);
+        }
+    }
+
+    /// Records an index range for clear geometry produced by `source`.
+    pub fn record_clear_range_from(&mut self, start: u32, end: u32, source: ClearRangeSource) {
+        if end > start {
+            self.clear_ranges.push((start, end));
+            self.clear_range_sources.push(source);
+        }
+    }
+
+    /// Records a fan copied from other geometry, such as a step-repeat block.
+    pub fn record_fan_range(&mut self, fan: FanRange) {
+        self.fan_ranges.push(fan);
+    }
+
+    /// Records the object attributes of a range of triangles, handed over by
+    /// an [`super::attributes::AttributeTracker`] or copied from a
+    /// step-repeat block.
+    pub fn record_attribute_range(&mut self, range: AttributeRange) {
+        self.attribute_ranges.push(range);
+    }
+
+    /// Records the measurements of a filled region.
+    pub fn record_region_metrics(&mut self, metrics: RegionMetrics) {
+        self.region_metrics.push(metrics);
+    }
+
+    /// Records that `feature` was skipped as unsupported. Each feature is
+    /// listed once.
+    pub fn record_unsupported(&mut self, feature: &str) {
+        if !self.unsupported_features.iter().any(|f| f == feature) {
+            self.unsupported_features.push(feature.to_string());
         }
     }
 
@@ -251,6 +1121,53 @@ impl GeometryBuilder {
         saturate_u32(self.positions.len() / 2)
     }
 
+    /// Clears all recorded geometry, warnings, and ranges while keeping the
+    /// allocated capacity and the options, so one builder can be reused
+    /// across many conversions.
+    pub fn reset(&mut self) {
+        self.positions.clear();
+        self.positions_f64.clear();
+        self.indices.clear();
+        self.bounds = BoundingBox::new();
+        self.warnings.clear();
+        self.warning_commands.clear();
+        self.warning_categories.clear();
+        self.command_index = None;
+        self.aperture_use = None;
+        self.clear_ranges.clear();
+        self.clear_range_sources.clear();
+        self.region_metrics.clear();
+        self.fan_ranges.clear();
+        self.attribute_ranges.clear();
+        self.unsupported_features.clear();
+    }
+
+    /// Produces a [`LayerGeometry`] like [`Self::build`], copying the buffers
+    /// out at their exact size, then [resets](Self::reset) the builder for
+    /// the next conversion.
+    pub fn build_and_reset(&mut self) -> LayerGeometry {
+        let snapshot = Self {
+            positions: self.positions.clone(),
+            positions_f64: self.positions_f64.clone(),
+            indices: self.indices.clone(),
+            bounds: self.bounds,
+            warnings: self.warnings.clone(),
+            warning_commands: self.warning_commands.clone(),
+            warning_categories: self.warning_categories.clone(),
+            command_index: None,
+            aperture_use: None,
+            clear_ranges: self.clear_ranges.clone(),
+            clear_range_sources: self.clear_range_sources.clone(),
+            region_metrics: self.region_metrics.clone(),
+            fan_ranges: self.fan_ranges.clone(),
+            attribute_ranges: self.attribute_ranges.clone(),
+            unsupported_features: self.unsupported_features.clone(),
+            options: self.options,
+        };
+        self.reset();
+        snapshot.build()
+    }
+
     /// Consumes the builder and produces a [`LayerGeometry`].
     ///
     /// `command_count` is set to 0; the caller should update it as needed.
@@ -258,15 +1175,37 @@ impl GeometryBuilder {
     /// [`super::polarity::PolarityTracker`].
     pub fn build(self) -> LayerGeometry {
         let vertex_count = saturate_u32(self.positions.len() / 2);
-        LayerGeometry {
+        let mut geometry = LayerGeometry {
             positions: self.positions,
+            positions_f64: self.positions_f64,
             indices: self.indices,
             bounds: self.bounds,
+            dark_bounds: self.bounds,
             command_count: 0,
             vertex_count,
             warnings: self.warnings,
+            warning_commands: self.warning_commands,
+            warning_categories: self.warning_categories,
             clear_ranges: self.clear_ranges,
+            clear_range_sources: self.clear_range_sources,
+            attribute_ranges: self.attribute_ranges,
+            region_metrics: self.region_metrics,
+            truncated: false,
+            unsupported_features: self.unsupported_features,
+            coordinate_format: None,
+            format_assumed: false,
+            units_assumed: false,
+            layer_role: LayerRole::Other,
+            layer_name: None,
+            image_negative: false,
+            fan_ranges: self.fan_ranges,
+            apertures: Vec::new(),
+        };
+        if !geometry.clear_ranges.is_empty() {
+            geometry.refresh_dark_bounds();
         }
+        debug_assert!(geometry.validate().is_ok(), "{:?}", geometry.validate());
+        geometry
     }
 }
 
@@ -289,6 +1228,30 @@ mod tests {
         assert_eq!(geom.positions.len(), 2);
     }
 
+    #[test]
+    fn reset_builder_matches_fresh_builder() {
+        let mut b = GeometryBuilder::new();
+        let v0 = b.push_vertex(1.0, 2.0);
+        let v1 = b.push_vertex(3.0, 4.0);
+        let v2 = b.push_vertex(5.0, 7.0);
+        b.push_triangle(v0, v1, v2);
+        b.record_clear_range(0, 3);
+        b.record_unsupported("thermal");
+        b.warn("stale".to_string());
+        b.reset();
+
+        let geom = b.build();
+        let fresh = GeometryBuilder::new().build();
+        assert!(geom.positions.is_empty());
+        assert!(geom.indices.is_empty());
+        assert!(geom.warnings.is_empty());
+        assert!(geom.warning_commands.is_empty());
+        assert!(geom.clear_ranges.is_empty());
+        assert!(geom.unsupported_features.is_empty());
+        assert_eq!(geom.vertex_count, 0);
+        assert_eq!(geom.bounds, fresh.bounds);
+    }
+
     #[test]
     fn push_three_vertices_six_floats() {
         let mut b = GeometryBuilder::new();
@@ -332,6 +1295,84 @@ mod tests {
         assert_eq!(geom.indices, vec![0, 1, 2, 0, 2, 3]);
     }
 
+    #[test]
+    fn push_quad_checked_untangles_self_crossing_quad() {
+        let mut b = GeometryBuilder::new();
+        b.push_vertex(0.0, 0.0);
+        b.push_vertex(1.0, 1.0);
+        b.push_vertex(1.0, 0.0);
+        b.push_vertex(0.0, 1.0);
+        assert!(b.push_quad_checked(0, 1, 2, 3));
+
+        let areas: Vec<f64> = b
+            .indices
+            .chunks_exact(3)
+            .filter_map(|t| b.triangle_area([*t.first()?, *t.get(1)?, *t.get(2)?]))
+            .collect();
+        assert_eq!(areas.len(), 2);
+        assert!(
+            areas.iter().all(|&area| (area - 1.0).abs() < 1e-9),
+            "both triangles counter-clockwise with half the square each: {areas:?}"
+        );
+        assert!(b.build().warnings.is_empty());
+    }
+
+    #[test]
+    fn push_quad_checked_skips_collinear_quad_with_warning() {
+        let mut b = GeometryBuilder::new();
+        for x in 0..4 {
+            b.push_vertex(f64::from(x), 0.0);
+        }
+        assert!(!b.push_quad_checked(0, 1, 2, 3));
+        let geom = b.build();
+        assert!(geom.indices.is_empty());
+        assert_eq!(geom.warnings.len(), 1);
+    }
+
+    #[test]
+    fn wireframe_of_square_lists_four_sides_and_diagonal() {
+        let mut b = GeometryBuilder::new();
+        let [v0, v1, v2, v3] =
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| b.push_vertex(x, y));
+        b.push_quad(v0, v1, v2, v3);
+        let wireframe = b.build().wireframe_indices();
+        assert_eq!(wireframe.len(), 10, "five edges as index pairs");
+        assert_eq!(wireframe, [0, 1, 0, 2, 0, 3, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn explode_gives_each_triangle_its_own_vertices() {
+        let mut b = GeometryBuilder::new();
+        let [v0, v1, v2, v3] =
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| b.push_vertex(x, y));
+        b.push_quad(v0, v1, v2, v3);
+        let quad = b.build();
+        let exploded = quad.explode();
+
+        assert_eq!(exploded.indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(exploded.vertex_count as usize, exploded.indices.len());
+        for (slot, &index) in quad.indices.iter().enumerate() {
+            let original = &quad.positions[index as usize * 2..index as usize * 2 + 2];
+            assert_eq!(&exploded.positions[slot * 2..slot * 2 + 2], original);
+        }
+        assert!(exploded.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_index() {
+        let mut b = GeometryBuilder::new();
+        let [v0, v1, v2] = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)].map(|(x, y)| b.push_vertex(x, y));
+        b.push_triangle(v0, v1, v2);
+        let mut geom = b.build();
+        assert!(geom.validate().is_ok());
+
+        geom.indices.extend_from_slice(&[v0, v2, 3]);
+        assert!(matches!(
+            geom.validate(),
+            Err(GeometryError::DegenerateGeometry(message)) if message == "index 5 references vertex 3 of 3"
+        ));
+    }
+
     #[test]
     fn push_ngon_four_creates_four_vertices() {
         let mut b = GeometryBuilder::new();
@@ -378,6 +1419,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn push_ring_closes_strip_around_hollow_center() {
+        let mut builder = GeometryBuilder::new();
+        builder.push_ring(0.0, 0.0, 2.0, 1.0, 4);
+        let geom = builder.build();
+
+        assert_eq!(geom.vertex_count, 8);
+        assert_eq!(geom.indices.len(), 8 * 3);
+        assert_eq!(geom.indices.get(..3), Some(&[0, 1, 2][..]));
+        assert_eq!(geom.indices.get(21..), Some(&[0, 7, 1][..]));
+        for xy in geom.positions.chunks_exact(2) {
+            let radius = xy.iter().map(|v| v * v).sum::<f32>().sqrt();
+            assert!(radius > 0.99, "vertex inside the hole at radius {radius}");
+        }
+    }
+
+    #[test]
+    fn push_fan_reproduces_hexagon_indices() {
+        let mut b = GeometryBuilder::new();
+        let first = b.push_ngon(0.0, 0.0, 1.0, 6);
+        let ngon = b.build();
+
+        let mut fan = GeometryBuilder::new();
+        for _ in 0..6 {
+            fan.push_vertex(0.0, 0.0);
+        }
+        fan.push_fan(
+            first,
+            &[first + 1, first + 2, first + 3, first + 4, first + 5],
+        );
+        let fan = fan.build();
+
+        assert_eq!(fan.indices, ngon.indices);
+        assert_eq!(fan.indices, vec![0, 1, 2, 0, 2, 3, 0, 3, 4, 0, 4, 5]);
+    }
+
+    #[test]
+    fn push_fan_range_matches_push_fan() {
+        let options = TessellationOptions::new().with_fan_ranges(true);
+        let mut range = GeometryBuilder::with_options(options);
+        let mut slice = GeometryBuilder::with_options(options);
+        for b in [&mut range, &mut slice] {
+            for _ in 0..5 {
+                b.push_vertex(0.0, 0.0);
+            }
+        }
+        range.push_fan_range(0, 1, 4);
+        slice.push_fan(0, &[1, 2, 3, 4]);
+        range.push_fan_range(0, 3, 1);
+        let (range, slice) = (range.build(), slice.build());
+
+        assert_eq!(range.indices, slice.indices);
+        assert_eq!(range.fan_ranges, slice.fan_ranges);
+    }
+
+    #[test]
+    fn push_strip_alternates_winding() {
+        let mut b = GeometryBuilder::new();
+        for _ in 0..5 {
+            b.push_vertex(0.0, 0.0);
+        }
+        b.push_strip(&[0, 1, 2, 3, 4]);
+        b.push_strip(&[7, 8]);
+        let geom = b.build();
+        assert_eq!(geom.indices, vec![0, 1, 2, 2, 1, 3, 2, 3, 4]);
+    }
+
     #[test]
     fn build_returns_correct_vertex_count() {
         let mut b = GeometryBuilder::new();
@@ -13,20 +13,62 @@
 
 //! `GerberView` WASM module — Gerber/Excellon parsing and geometry conversion.
 
+#[cfg(feature = "zip")]
+pub mod archive;
 pub mod error;
 pub mod excellon;
 pub mod geometry;
+pub mod peek;
+pub mod preprocess;
 
-use std::cell::RefCell;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::io::{BufReader, Cursor};
+use std::mem::size_of;
+use std::time::Duration;
 
 use wasm_bindgen::prelude::*;
 
 use crate::geometry::types::saturate_u32;
-use crate::geometry::{GeometryBuilder, LayerGeometry, LayerMeta};
+use crate::geometry::{GeometryBuilder, LayerGeometry, LayerMeta, ValidationReport};
 
 thread_local! {
     static LAST_GEOMETRY: RefCell<Option<LayerGeometry>> = const { RefCell::new(None) };
+    static KEEP_F64_POSITIONS: Cell<bool> = const { Cell::new(false) };
+    static FLIP_Y: Cell<bool> = const { Cell::new(false) };
+    static STRICT_MODE: Cell<bool> = const { Cell::new(false) };
+    static RECORD_FANS: Cell<bool> = const { Cell::new(false) };
+    static CONVERSION_BUDGET: Cell<(u32, u32)> = const { Cell::new((0, 0)) };
+    static DRILL_MIRROR: Cell<excellon::DrillMirror> = const { Cell::new(excellon::DrillMirror::new()) };
+    static DRILL_ROTATION: Cell<excellon::DrillRotation> = const { Cell::new(excellon::DrillRotation::new()) };
+    static DRILL_RING_WIDTH: Cell<f64> = const { Cell::new(0.0) };
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+    static LAST_LAYER_RANGES: RefCell<Vec<(u32, u32)>> = const { RefCell::new(Vec::new()) };
+    static LAST_DRILL_TOOL_RANGES: RefCell<Vec<ToolRange>> = const { RefCell::new(Vec::new()) };
+    static SPLIT_LAYERS: RefCell<Vec<LayerGeometry>> = const { RefCell::new(Vec::new()) };
+    #[cfg(feature = "zip")]
+    static ARCHIVE_LAYERS: RefCell<Vec<(String, LayerGeometry)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// `(tool, start, end)` index range of one drill tool.
+type ToolRange = (u32, u32, u32);
+
+/// Layers appended since [`begin_session`], combined into one mesh.
+struct Session {
+    geometry: LayerGeometry,
+    /// Index range `(start, end)` of each appended layer, in append order.
+    layer_ranges: Vec<(u32, u32)>,
+}
+
+impl Session {
+    fn append(&mut self, layer: LayerGeometry) -> LayerMeta {
+        let meta = LayerMeta::from_geometry(&layer);
+        let start = saturate_u32(self.geometry.indices.len());
+        self.geometry.append(layer);
+        self.layer_ranges
+            .push((start, saturate_u32(self.geometry.indices.len())));
+        meta
+    }
 }
 
 fn store_geometry(geom: LayerGeometry) {
@@ -52,7 +94,8 @@ pub fn ping() -> u32 {
 ///
 /// Returns `LayerMeta` as a `JsValue` via `serde-wasm-bindgen`.
 /// Geometry buffers are stored internally; retrieve with
-/// [`get_positions`] and [`get_indices`].
+/// [`get_positions`] and [`get_indices`]. With the `gzip` feature, input
+/// starting with the gzip magic bytes is decompressed first.
 ///
 /// # Errors
 ///
@@ -63,41 +106,120 @@ pub fn parse_gerber(data: &[u8]) -> Result<JsValue, JsValue> {
     serde_wasm_bindgen::to_value(&meta).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+/// Parse a Gerber file like [`parse_gerber`], reporting warnings as they occur.
+///
+/// `on_warning` is called with each warning message (a string) in the order
+/// the conversion records them; exceptions it throws are ignored. The
+/// returned `LayerMeta` still lists every warning.
+///
+/// # Errors
+///
+/// Returns a descriptive error string if parsing fails fatally.
+#[wasm_bindgen]
+pub fn parse_gerber_with_warnings(
+    data: &[u8],
+    on_warning: &js_sys::Function,
+) -> Result<JsValue, JsValue> {
+    let mut forward = |warning: &str| {
+        let _ = on_warning.call1(&JsValue::NULL, &JsValue::from_str(warning));
+    };
+    let meta =
+        parse_gerber_streaming(data, Some(&mut forward)).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&meta).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 /// Internal parse logic shared between the wasm export and native tests.
 #[doc(hidden)]
 pub fn parse_gerber_internal(data: &[u8]) -> Result<LayerMeta, String> {
+    parse_gerber_streaming(data, None)
+}
+
+/// Internal parse logic with an optional per-warning callback.
+#[doc(hidden)]
+pub fn parse_gerber_streaming(
+    data: &[u8],
+    on_warning: Option<&mut dyn FnMut(&str)>,
+) -> Result<LayerMeta, String> {
+    let geom = gerber_geometry(data, on_warning)?;
+    let meta = LayerMeta::from_geometry(&geom);
+    store_geometry(geom);
+    Ok(meta)
+}
+
+/// Leading bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns `data`, gunzipped first when it starts with the gzip magic.
+#[cfg(feature = "gzip")]
+fn decompress_input(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    use std::io::Read;
+
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut decompressed = Vec::new();
+    flate2::read::MultiGzDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|err| format!("invalid gzip input: {err}"))?;
+    Ok(Cow::Owned(decompressed))
+}
+
+/// Returns `data`, rejecting gzip input when built without the `gzip` feature.
+#[cfg(not(feature = "gzip"))]
+fn decompress_input(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    if data.starts_with(&GZIP_MAGIC) {
+        return Err("gzip-compressed input requires the `gzip` feature".to_string());
+    }
+    Ok(Cow::Borrowed(data))
+}
+
+fn gerber_geometry(
+    data: &[u8],
+    on_warning: Option<&mut dyn FnMut(&str)>,
+) -> Result<LayerGeometry, String> {
+    let data = decompress_input(data)?;
+    let data = preprocess::decode_text(&data);
     if data.is_empty() {
         return Err("empty input".to_string());
     }
 
-    let reader = BufReader::new(Cursor::new(data));
+    let reader = BufReader::new(Cursor::new(data.as_ref()));
 
     let doc = match gerber_parser::parse(reader) {
         Ok(doc) => doc,
         Err((doc, _parse_err)) => doc,
     };
 
-    let geom = geometry::convert(&doc).map_err(|e| e.to_string())?;
-
-    let meta = LayerMeta {
-        bounds: geom.bounds,
-        vertex_count: geom.vertex_count,
-        index_count: saturate_u32(geom.indices.len()),
-        command_count: geom.command_count,
-        warning_count: saturate_u32(geom.warnings.len()),
-        warnings: geom.warnings.clone(),
-    };
-
-    store_geometry(geom);
-
-    Ok(meta)
+    let mut options =
+        geometry::TessellationOptions::new().with_f64_positions(KEEP_F64_POSITIONS.with(Cell::get));
+    let (max_triangles, time_budget_ms) = CONVERSION_BUDGET.with(Cell::get);
+    if max_triangles > 0 {
+        options = options.with_max_triangles(max_triangles);
+    }
+    if time_budget_ms > 0 {
+        options = options.with_time_budget(Duration::from_millis(u64::from(time_budget_ms)));
+    }
+    if FLIP_Y.with(Cell::get) {
+        options = options.with_flip_y(geometry::FlipAxis::LayerTop);
+    }
+    if STRICT_MODE.with(Cell::get) {
+        options = options.with_strict(geometry::StrictMode::ALL);
+    }
+    if RECORD_FANS.with(Cell::get) {
+        options = options.with_fan_ranges(true);
+    }
+    let mut geom =
+        geometry::convert_with_options(&doc, &options, on_warning).map_err(|e| e.to_string())?;
+    geom.layer_role = geometry::LayerRole::detect(&data);
+    Ok(geom)
 }
 
 /// Parse an Excellon drill file from raw bytes and generate renderable geometry.
 ///
 /// Returns `LayerMeta` as a `JsValue` via `serde-wasm-bindgen`.
 /// Geometry buffers are stored internally; retrieve with
-/// [`get_positions`] and [`get_indices`].
+/// [`get_positions`] and [`get_indices`]. Gzip input is handled as in
+/// [`parse_gerber`].
 ///
 /// # Errors
 ///
@@ -111,34 +233,400 @@ pub fn parse_excellon(data: &[u8]) -> Result<JsValue, JsValue> {
 /// Internal parse logic shared between the wasm export and native tests.
 #[doc(hidden)]
 pub fn parse_excellon_internal(data: &[u8]) -> Result<LayerMeta, String> {
-    let result = excellon::parser::parse(data).map_err(|err| err.to_string())?;
+    let (geom, tool_ranges) = excellon_geometry_by_tool(data)?;
+    let meta = LayerMeta::from_geometry(&geom);
+    store_geometry(geom);
+    LAST_DRILL_TOOL_RANGES.with(|r| *r.borrow_mut() = tool_ranges);
+    Ok(meta)
+}
+
+fn excellon_geometry(data: &[u8]) -> Result<LayerGeometry, String> {
+    excellon_geometry_by_tool(data).map(|(geom, _)| geom)
+}
 
-    let mut builder = GeometryBuilder::new();
-    for warning in &result.warnings {
-        builder.warn(warning.clone());
+/// Tessellates an Excellon file with its holes grouped by tool, also
+/// returning the `(tool, start, end)` index range of each tool.
+fn excellon_geometry_by_tool(data: &[u8]) -> Result<(LayerGeometry, Vec<ToolRange>), String> {
+    let data = decompress_input(data)?;
+    let result = excellon::parser::parse(&data).map_err(|err| err.to_string())?;
+    if STRICT_MODE.with(Cell::get) {
+        geometry::StrictMode::ALL
+            .check(&result.warnings, &result.warning_categories)
+            .map_err(|err| err.to_string())?;
     }
 
-    for hole in &result.holes {
-        builder.push_ngon(hole.x, hole.y, hole.diameter / 2.0, 32);
+    let mut builder = excellon::ExcellonGeometryBuilder::new()
+        .with_mirror(DRILL_MIRROR.with(Cell::get))
+        .with_rotation(DRILL_ROTATION.with(Cell::get))
+        .with_ring_width(DRILL_RING_WIDTH.with(Cell::get));
+    for (warning, category) in result.warnings.into_iter().zip(result.warning_categories) {
+        builder.record_warning(warning, category);
     }
 
-    let mut geom = builder.build();
-    geom.command_count = saturate_u32(result.holes.len());
+    let mut holes = result.holes;
+    holes.sort_by_key(|hole| hole.tool);
+    for hole in &holes {
+        builder.add_drill_hole(hole);
+    }
+    let tool_ranges = builder.tool_ranges().to_vec();
+    Ok((builder.finish(), tool_ranges))
+}
 
-    let meta = LayerMeta {
-        bounds: geom.bounds,
-        vertex_count: geom.vertex_count,
-        index_count: saturate_u32(geom.indices.len()),
-        command_count: geom.command_count,
-        warning_count: saturate_u32(geom.warnings.len()),
-        warnings: geom.warnings.clone(),
-    };
+/// Start accumulating layers into one combined geometry.
+///
+/// Subsequent [`append_gerber`] and [`append_excellon`] calls add to the
+/// session instead of replacing the last parsed layer. Any session already in
+/// progress is discarded.
+#[wasm_bindgen]
+pub fn begin_session() {
+    SESSION.with(|s| {
+        *s.borrow_mut() = Some(Session {
+            geometry: GeometryBuilder::new().build(),
+            layer_ranges: Vec::new(),
+        });
+    });
+}
 
-    store_geometry(geom);
+/// Parse a Gerber file and append its geometry to the current session.
+///
+/// Returns the appended layer's own `LayerMeta` as a `JsValue`.
+///
+/// # Errors
+///
+/// Returns an error if no session is active or parsing fails fatally; the
+/// session is left unchanged in that case.
+#[wasm_bindgen]
+pub fn append_gerber(data: &[u8]) -> Result<JsValue, JsValue> {
+    let meta = append_gerber_internal(data).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&meta).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal append logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn append_gerber_internal(data: &[u8]) -> Result<LayerMeta, String> {
+    append_to_session(|| gerber_geometry(data, None))
+}
+
+/// Parse an Excellon file and append its geometry to the current session.
+///
+/// Returns the appended layer's own `LayerMeta` as a `JsValue`.
+///
+/// # Errors
+///
+/// Returns an error if no session is active or parsing fails; the session is
+/// left unchanged in that case.
+#[wasm_bindgen]
+pub fn append_excellon(data: &[u8]) -> Result<JsValue, JsValue> {
+    let meta = append_excellon_internal(data).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&meta).map_err(|e| JsValue::from_str(&e.to_string()))
+}
 
+/// Internal append logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn append_excellon_internal(data: &[u8]) -> Result<LayerMeta, String> {
+    append_to_session(|| excellon_geometry(data))
+}
+
+fn append_to_session(
+    layer: impl FnOnce() -> Result<LayerGeometry, String>,
+) -> Result<LayerMeta, String> {
+    SESSION.with(|s| {
+        let mut session = s.borrow_mut();
+        let Some(session) = session.as_mut() else {
+            return Err("no active session; call begin_session first".to_string());
+        };
+        Ok(session.append(layer()?))
+    })
+}
+
+/// End the current session and make its combined geometry the last parsed
+/// layer.
+///
+/// Returns the combined `LayerMeta` as a `JsValue`. Buffers are then available
+/// through [`get_positions`] and [`get_indices`], and each appended layer's
+/// index range through [`get_layer_ranges`].
+///
+/// # Errors
+///
+/// Returns an error if no session is active.
+#[wasm_bindgen]
+pub fn finish_session() -> Result<JsValue, JsValue> {
+    let meta = finish_session_internal().map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&meta).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal finish logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn finish_session_internal() -> Result<LayerMeta, String> {
+    let session = SESSION
+        .with(|s| s.borrow_mut().take())
+        .ok_or_else(|| "no active session; call begin_session first".to_string())?;
+    let meta = LayerMeta::from_geometry(&session.geometry);
+    store_geometry(session.geometry);
+    LAST_LAYER_RANGES.with(|r| *r.borrow_mut() = session.layer_ranges);
     Ok(meta)
 }
 
+/// Split a concatenated job into layers and parse each one.
+///
+/// The input may hold several Gerber images and Excellon drill sections back
+/// to back; see [`preprocess::split_layers`] for how boundaries are found.
+/// Each segment is parsed on its own and a boundary guessed without an
+/// end-of-file code is reported as the first warning of the layer after it.
+/// Returns an array of `LayerMeta` as a `JsValue`. The layers replace those
+/// of the previous split; make one the last parsed layer with
+/// [`select_split_layer`].
+///
+/// # Errors
+///
+/// Returns a descriptive error string if the input holds no layers or a
+/// segment fails to convert.
+#[wasm_bindgen]
+pub fn split_and_parse(data: &[u8]) -> Result<JsValue, JsValue> {
+    let layers = split_and_parse_internal(data).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&layers).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal split logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn split_and_parse_internal(data: &[u8]) -> Result<Vec<LayerMeta>, String> {
+    let data = decompress_input(data)?;
+    let data = preprocess::decode_text(&data);
+    let segments = preprocess::split_layers(&data);
+    if segments.is_empty() {
+        return Err("empty input".to_string());
+    }
+    let mut layers = Vec::with_capacity(segments.len());
+    let mut geometries = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.into_iter().enumerate() {
+        let mut geom = match segment.kind {
+            preprocess::SegmentKind::Gerber => gerber_geometry(segment.data, None),
+            preprocess::SegmentKind::Excellon => excellon_geometry(segment.data),
+        }
+        .map_err(|err| format!("layer {index}: {err}"))?;
+        if let Some(warning) = segment.warning {
+            geom.warnings.insert(0, warning);
+            geom.warning_commands.insert(0, None);
+            geom.warning_categories.insert(0, None);
+        }
+        layers.push(LayerMeta::from_geometry(&geom));
+        geometries.push(geom);
+    }
+    SPLIT_LAYERS.with(|s| *s.borrow_mut() = geometries);
+    Ok(layers)
+}
+
+/// Make layer `index` of the last [`split_and_parse`] call the last parsed
+/// layer, so [`get_positions`] and [`get_indices`] return its buffers.
+///
+/// Returns `false` if there is no such layer.
+#[wasm_bindgen]
+pub fn select_split_layer(index: u32) -> bool {
+    let layer = SPLIT_LAYERS.with(|s| {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| s.borrow().get(index).cloned())
+    });
+    layer.map(store_geometry).is_some()
+}
+
+/// Parse every Gerber and Excellon file in a ZIP archive.
+///
+/// Each entry is detected as Gerber or Excellon and converted; other files
+/// are skipped. Returns an array of `ArchiveLayer` (entry name, kind and
+/// `LayerMeta`) as a `JsValue`. The converted layers replace those of the
+/// previous archive; make one the last parsed layer with
+/// [`select_archive_layer`]. Requires the `zip` feature.
+///
+/// # Errors
+///
+/// Returns a descriptive error string if the archive cannot be read or an
+/// entry fails to convert.
+#[cfg(feature = "zip")]
+#[wasm_bindgen]
+pub fn parse_archive(data: &[u8]) -> Result<JsValue, JsValue> {
+    let layers = parse_archive_internal(data).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&layers).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal archive logic shared between the wasm export and native tests.
+#[cfg(feature = "zip")]
+#[doc(hidden)]
+pub fn parse_archive_internal(data: &[u8]) -> Result<Vec<archive::ArchiveLayer>, String> {
+    let entries = archive::read_zip(data).map_err(|err| err.to_string())?;
+    let mut layers = Vec::new();
+    let mut geometries = Vec::new();
+    for entry in entries {
+        let Some(kind) = archive::detect_kind(&entry.name, &entry.data) else {
+            continue;
+        };
+        let geom = match kind {
+            archive::LayerKind::Gerber => gerber_geometry(&entry.data, None),
+            archive::LayerKind::Excellon => excellon_geometry(&entry.data),
+        }
+        .map_err(|err| format!("{}: {err}", entry.name))?;
+        layers.push(archive::ArchiveLayer {
+            name: entry.name.clone(),
+            kind,
+            meta: LayerMeta::from_geometry(&geom),
+        });
+        geometries.push((entry.name, geom));
+    }
+    ARCHIVE_LAYERS.with(|a| *a.borrow_mut() = geometries);
+    Ok(layers)
+}
+
+/// Make the layer `name` from the last parsed archive the last parsed layer,
+/// so [`get_positions`] and [`get_indices`] return its buffers.
+///
+/// Returns `false` if the archive had no such layer. Requires the `zip`
+/// feature.
+#[cfg(feature = "zip")]
+#[wasm_bindgen]
+pub fn select_archive_layer(name: &str) -> bool {
+    let layer = ARCHIVE_LAYERS.with(|a| {
+        a.borrow()
+            .iter()
+            .find(|(layer_name, _)| layer_name == name)
+            .map(|(_, geom)| geom.clone())
+    });
+    layer.map(store_geometry).is_some()
+}
+
+/// Retrieve the index range of each layer in the last finished session.
+///
+/// Returns a flattened `[start0, end0, start1, end1, ...]` array in append
+/// order. Returns an empty array if no session has been finished.
+#[wasm_bindgen]
+pub fn get_layer_ranges() -> Vec<u32> {
+    LAST_LAYER_RANGES.with(|r| {
+        let ranges = r.borrow();
+        let mut flat = Vec::with_capacity(ranges.len() * 2);
+        for &(start, end) in ranges.iter() {
+            flat.push(start);
+            flat.push(end);
+        }
+        flat
+    })
+}
+
+/// Retrieve the index range of each tool in the last parsed Excellon file.
+///
+/// Returns a flattened `[tool0, start0, end0, tool1, start1, end1, ...]`
+/// array ordered by tool number. Holes are tessellated grouped by tool, so
+/// each tool used has exactly one range. Returns an empty array if no drill
+/// file has been parsed with [`parse_excellon`].
+#[wasm_bindgen]
+pub fn get_drill_tool_ranges() -> Vec<u32> {
+    LAST_DRILL_TOOL_RANGES.with(|r| {
+        r.borrow()
+            .iter()
+            .flat_map(|&range| <[u32; 3]>::from(range))
+            .collect()
+    })
+}
+
+/// Check whether bytes form a readable Gerber file without building geometry.
+///
+/// Returns a `ValidationReport` as a `JsValue`. The last parsed layer is left
+/// untouched.
+///
+/// # Errors
+///
+/// Returns an error if the report cannot be serialized.
+#[wasm_bindgen]
+pub fn validate_gerber(data: &[u8]) -> Result<JsValue, JsValue> {
+    let report = validate_gerber_internal(data);
+    serde_wasm_bindgen::to_value(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal validation logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn validate_gerber_internal(data: &[u8]) -> ValidationReport {
+    let data = match decompress_input(data) {
+        Ok(data) => data,
+        Err(err) => return ValidationReport::failed(err),
+    };
+    let data = preprocess::decode_text(&data);
+    if data.is_empty() {
+        return ValidationReport::failed("empty input".to_string());
+    }
+
+    let reader = BufReader::new(Cursor::new(data.as_ref()));
+    let (doc, parse_warning) = match gerber_parser::parse(reader) {
+        Ok(doc) => (doc, 0),
+        Err((doc, _parse_err)) => (doc, 1),
+    };
+
+    let mut report = geometry::validate(&doc);
+    report.warning_count = report.warning_count.saturating_add(parse_warning);
+    report
+}
+
+/// Read the units, format and aperture count of a Gerber file without
+/// converting it.
+///
+/// Returns a `HeaderInfo` as a `JsValue`. Gzip input is handled as in
+/// [`parse_gerber`]. The last parsed layer is left untouched.
+///
+/// # Errors
+///
+/// Returns an error string if the input cannot be decompressed.
+#[wasm_bindgen]
+pub fn peek_gerber(data: &[u8]) -> Result<JsValue, JsValue> {
+    let data = decompress_input(data).map_err(|e| JsValue::from_str(&e))?;
+    let info = peek::peek_gerber(&data);
+    serde_wasm_bindgen::to_value(&info).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Read the units, format and tool count of an Excellon file without
+/// reading its holes.
+///
+/// Returns a `HeaderInfo` as a `JsValue`. Gzip input is handled as in
+/// [`parse_gerber`]. The last parsed layer is left untouched.
+///
+/// # Errors
+///
+/// Returns an error string if the header cannot be read.
+#[wasm_bindgen]
+pub fn peek_excellon(data: &[u8]) -> Result<JsValue, JsValue> {
+    let data = decompress_input(data).map_err(|e| JsValue::from_str(&e))?;
+    let info = peek::peek_excellon(&data).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&info).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Check whether bytes form a readable Excellon file without building geometry.
+///
+/// Returns a `ValidationReport` as a `JsValue`; `command_count` is the number
+/// of drill holes. The last parsed layer is left untouched.
+///
+/// # Errors
+///
+/// Returns an error if the report cannot be serialized.
+#[wasm_bindgen]
+pub fn validate_excellon(data: &[u8]) -> Result<JsValue, JsValue> {
+    let report = validate_excellon_internal(data);
+    serde_wasm_bindgen::to_value(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Internal validation logic shared between the wasm export and native tests.
+#[doc(hidden)]
+pub fn validate_excellon_internal(data: &[u8]) -> ValidationReport {
+    let data = match decompress_input(data) {
+        Ok(data) => data,
+        Err(err) => return ValidationReport::failed(err),
+    };
+    match excellon::parser::parse(&data) {
+        Ok(result) => ValidationReport {
+            command_count: saturate_u32(result.holes.len()),
+            warning_count: saturate_u32(result.warnings.len()),
+            fatal: false,
+            error: None,
+        },
+        Err(err) => ValidationReport::failed(err.to_string()),
+    }
+}
+
 /// Retrieve the position buffer for the last parsed layer.
 ///
 /// Returns a copy of the interleaved `[x0, y0, x1, y1, ...]` positions.
@@ -152,6 +640,141 @@ pub fn get_positions() -> Vec<f32> {
     })
 }
 
+/// Limit how much work subsequent Gerber parses may do.
+///
+/// A parse stops once it has emitted `max_triangles` triangles or run for
+/// `time_budget_ms` milliseconds, returning the partial geometry with
+/// `truncated` set in its metadata. Pass 0 for either limit to disable it.
+#[wasm_bindgen]
+pub fn set_conversion_budget(max_triangles: u32, time_budget_ms: u32) {
+    CONVERSION_BUDGET.with(|budget| budget.set((max_triangles, time_budget_ms)));
+}
+
+/// Choose whether subsequent Gerber parses retain `f64` positions.
+///
+/// Disabled by default. When enabled, [`get_positions_f64`] returns
+/// full-precision coordinates at the cost of a second position buffer twice
+/// the size of the `f32` one.
+#[wasm_bindgen]
+pub fn set_keep_f64_positions(enabled: bool) {
+    KEEP_F64_POSITIONS.with(|keep| keep.set(enabled));
+}
+
+/// Emit subsequently parsed Gerber layers with Y pointing down.
+///
+/// Positions become `max_y - y` for the layer's maximum Y, so they are
+/// screen-ready without a flip in the shader; bounds are flipped to match and
+/// triangle winding is preserved. Disabled by default.
+#[wasm_bindgen]
+pub fn set_flip_y(enabled: bool) {
+    FLIP_Y.with(|flip| flip.set(enabled));
+}
+
+/// Fail subsequent parses on conditions that are otherwise warnings.
+///
+/// In strict mode, undefined apertures or tools, auto-closed regions and
+/// mixed unit declarations make Gerber and Excellon parses return an error
+/// listing every such condition. Disabled by default.
+#[wasm_bindgen]
+pub fn set_strict_mode(enabled: bool) {
+    STRICT_MODE.with(|strict| strict.set(enabled));
+}
+
+/// Record triangle fans of subsequently parsed Gerber layers.
+///
+/// Disabled by default. When enabled, [`get_fan_ranges`] lists the circles,
+/// polygons and round endcaps that can be drawn as `TRIANGLE_FAN`
+/// primitives; the index buffer still contains them as triangles.
+#[wasm_bindgen]
+pub fn set_record_fans(enabled: bool) {
+    RECORD_FANS.with(|record| record.set(enabled));
+}
+
+/// Override the suggested style of a layer role.
+///
+/// `role` is a `LayerRole` name such as `"TopCopper"`, `rgba` the color as
+/// `0xRRGGBBAA`, and `render_priority` the z-order reported in `LayerMeta`
+/// for layers parsed afterwards. Returns `false` for an unknown role.
+#[wasm_bindgen]
+pub fn set_layer_style(role: &str, rgba: u32, render_priority: i32) -> bool {
+    let Some(role) = geometry::LayerRole::from_name(role) else {
+        return false;
+    };
+    let style = geometry::LayerStyle {
+        color_rgba: rgba.to_be_bytes(),
+        render_priority,
+    };
+    geometry::set_layer_style(role, Some(style));
+    true
+}
+
+/// Restore the built-in style of a layer role. Returns `false` for an
+/// unknown role.
+#[wasm_bindgen]
+pub fn reset_layer_style(role: &str) -> bool {
+    let Some(role) = geometry::LayerRole::from_name(role) else {
+        return false;
+    };
+    geometry::set_layer_style(role, None);
+    true
+}
+
+/// Mirror holes of subsequently parsed Excellon files.
+///
+/// `mirror_x` reflects hole X about the vertical axis `x = origin_x` and
+/// `mirror_y` reflects hole Y about `y = origin_y`; pass `false` for both to
+/// disable mirroring. Hole diameters are unchanged.
+#[wasm_bindgen]
+pub fn set_excellon_mirror(mirror_x: bool, mirror_y: bool, origin_x: f64, origin_y: f64) {
+    DRILL_MIRROR.with(|mirror| {
+        mirror.set(excellon::DrillMirror {
+            mirror_x,
+            mirror_y,
+            origin_x,
+            origin_y,
+        });
+    });
+}
+
+/// Rotate holes of subsequently parsed Excellon files.
+///
+/// Hole centers turn `angle_deg` degrees counter-clockwise about
+/// (`origin_x`, `origin_y`); pass 0 to disable rotation. Rotation is applied
+/// after [`set_excellon_mirror`]. Hole diameters are unchanged.
+#[wasm_bindgen]
+pub fn set_excellon_rotation(angle_deg: f64, origin_x: f64, origin_y: f64) {
+    DRILL_ROTATION.with(|rotation| {
+        rotation.set(excellon::DrillRotation {
+            angle_deg,
+            origin_x,
+            origin_y,
+        });
+    });
+}
+
+/// Draw holes of subsequently parsed Excellon files as rings.
+///
+/// Each hole becomes an annulus of the drill diameter with a ring `width`
+/// wide, leaving its center open so copper underneath stays visible. A width
+/// of zero, or one at least the hole radius, draws filled disks (the default).
+#[wasm_bindgen]
+pub fn set_drill_ring_width(width: f64) {
+    DRILL_RING_WIDTH.with(|ring_width| ring_width.set(width));
+}
+
+/// Retrieve the full-precision position buffer for the last parsed layer.
+///
+/// Same layout as [`get_positions`]. Returns an empty array unless the layer
+/// was parsed with [`set_keep_f64_positions`] enabled.
+#[wasm_bindgen]
+pub fn get_positions_f64() -> Vec<f64> {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow()
+            .as_ref()
+            .map_or_else(Vec::new, |geom| geom.positions_f64.clone())
+    })
+}
+
 /// Retrieve the index buffer for the last parsed layer.
 ///
 /// Returns a copy of the triangle-list indices.
@@ -165,6 +788,138 @@ pub fn get_indices() -> Vec<u32> {
     })
 }
 
+/// Byte length of the position buffer for the last parsed layer.
+///
+/// Equals `get_positions().length * 4` without copying the buffer, so callers
+/// can budget GPU memory before fetching. Returns 0 if no layer has been parsed.
+#[wasm_bindgen]
+pub fn positions_byte_len() -> u32 {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow().as_ref().map_or(0, |geom| {
+            saturate_u32(geom.positions.len().saturating_mul(size_of::<f32>()))
+        })
+    })
+}
+
+/// Byte length of the index buffer for the last parsed layer.
+///
+/// Equals `get_indices().length * 4` without copying the buffer. Returns 0 if
+/// no layer has been parsed.
+#[wasm_bindgen]
+pub fn indices_byte_len() -> u32 {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow().as_ref().map_or(0, |geom| {
+            saturate_u32(geom.indices.len().saturating_mul(size_of::<u32>()))
+        })
+    })
+}
+
+/// Vertex count of the last parsed layer, as in its `LayerMeta`.
+///
+/// This and the other `last_*` accessors read single values without
+/// serializing the warnings. Each returns 0 if no layer has been parsed.
+#[wasm_bindgen]
+pub fn last_vertex_count() -> u32 {
+    LAST_GEOMETRY.with(|g| g.borrow().as_ref().map_or(0, |geom| geom.vertex_count))
+}
+
+/// Triangle count of the last parsed layer, its index count divided by 3.
+#[wasm_bindgen]
+pub fn last_triangle_count() -> u32 {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow()
+            .as_ref()
+            .map_or(0, |geom| saturate_u32(geom.indices.len() / 3))
+    })
+}
+
+/// Command count of the last parsed layer, as in its `LayerMeta`.
+#[wasm_bindgen]
+pub fn last_command_count() -> u32 {
+    LAST_GEOMETRY.with(|g| g.borrow().as_ref().map_or(0, |geom| geom.command_count))
+}
+
+/// Warning count of the last parsed layer, as in its `LayerMeta`.
+#[wasm_bindgen]
+pub fn last_warning_count() -> u32 {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow()
+            .as_ref()
+            .map_or(0, |geom| saturate_u32(geom.warnings.len()))
+    })
+}
+
+/// Retrieve the unique triangle edges of the last parsed layer.
+///
+/// Returns a line-list index buffer `[a0, b0, a1, b1, ...]` into the
+/// [`get_positions`] buffer with each shared edge listed once, for drawing
+/// a wireframe with `gl.LINES`. Returns an empty array if no layer has been
+/// parsed yet.
+#[wasm_bindgen]
+pub fn get_wireframe_indices() -> Vec<u32> {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow()
+            .as_ref()
+            .map_or_else(Vec::new, LayerGeometry::wireframe_indices)
+    })
+}
+
+/// Retrieve only the clear-polarity triangles of the last parsed layer.
+///
+/// Returns `{ positions: Float32Array, indices: Uint32Array }`, a standalone
+/// mesh re-indexed from zero, so clearances can be drawn in their own color.
+/// Both arrays are empty if no layer has been parsed or nothing is clear.
+///
+/// # Errors
+///
+/// Returns an error if the result object cannot be built.
+#[wasm_bindgen]
+pub fn get_clear_geometry() -> Result<JsValue, JsValue> {
+    let clear = LAST_GEOMETRY.with(|g| {
+        g.borrow().as_ref().map_or_else(
+            || GeometryBuilder::new().build(),
+            LayerGeometry::clear_geometry,
+        )
+    });
+    mesh_object(&clear)
+}
+
+/// Retrieve only the dark-polarity triangles of the last parsed layer.
+///
+/// Returns `{ positions: Float32Array, indices: Uint32Array }` like
+/// [`get_clear_geometry`]; together the two meshes hold every triangle of the
+/// layer once. Both arrays are empty if no layer has been parsed.
+///
+/// # Errors
+///
+/// Returns an error if the result object cannot be built.
+#[wasm_bindgen]
+pub fn get_dark_geometry() -> Result<JsValue, JsValue> {
+    let dark = LAST_GEOMETRY.with(|g| {
+        g.borrow().as_ref().map_or_else(
+            || GeometryBuilder::new().build(),
+            |geom| geom.split_by_polarity().0,
+        )
+    });
+    mesh_object(&dark)
+}
+
+/// Builds a `{ positions, indices }` object from a standalone mesh.
+fn mesh_object(mesh: &LayerGeometry) -> Result<JsValue, JsValue> {
+    let result = js_sys::Object::new();
+    js_sys::Reflect::set(
+        &result,
+        &JsValue::from_str("positions"),
+        &js_sys::Float32Array::from(mesh.positions.as_slice()),
+    )?;
+    js_sys::Reflect::set(
+        &result,
+        &JsValue::from_str("indices"),
+        &js_sys::Uint32Array::from(mesh.indices.as_slice()),
+    )?;
+    Ok(result.into())
+}
+
 /// Retrieve the clear-polarity index ranges for the last parsed layer.
 ///
 /// Returns a flattened `[start0, end0, start1, end1, ...]` array of index
@@ -184,6 +939,174 @@ pub fn get_clear_ranges() -> Vec<u32> {
     })
 }
 
+/// Retrieve the net copper area of the last parsed layer.
+///
+/// Overlapping dark geometry is counted once and clear polarity is
+/// subtracted. The area is in squared layer units: mm² for a plain
+/// conversion, but scaled by any `layer_transform`. After a session merge it
+/// is the union of every appended layer, with drill holes counted as copper.
+/// Computing it replays every triangle, so it is only done on request.
+/// Returns 0 if no layer has been parsed.
+#[wasm_bindgen]
+pub fn get_copper_area() -> f64 {
+    LAST_GEOMETRY.with(|g| g.borrow().as_ref().map_or(0.0, geometry::copper_area))
+}
+
+/// Retrieve a stable hash of the last parsed layer's geometry.
+///
+/// Equal hashes mean identical positions, indices and clear ranges, so the
+/// value can key a render cache or flag changes between parses. Returns 0 if
+/// no layer has been parsed.
+#[wasm_bindgen]
+pub fn get_geometry_hash() -> u64 {
+    LAST_GEOMETRY.with(|g| g.borrow().as_ref().map_or(0, geometry::geometry_hash))
+}
+
+/// Retrieve the aperture table of the last parsed layer.
+///
+/// Returns an array of `ApertureInfo` as a `JsValue`, ordered by D-code, with
+/// each aperture's kind, size parameters, macro comments and flash/draw
+/// usage counts. Empty for a drill layer; after a session, the tables of the
+/// appended Gerber layers in append order.
+///
+/// # Errors
+///
+/// Returns an error string if the report cannot be serialized.
+#[wasm_bindgen]
+pub fn get_aperture_report() -> Result<JsValue, JsValue> {
+    LAST_GEOMETRY.with(|g| {
+        let geom = g.borrow();
+        let apertures = geom
+            .as_ref()
+            .map_or(&[][..], |geom| geom.apertures.as_slice());
+        serde_wasm_bindgen::to_value(apertures).map_err(|e| JsValue::from_str(&e.to_string()))
+    })
+}
+
+/// Retrieve the triangle fans of the last parsed layer.
+///
+/// Returns a flattened `[center0, rim_count0, index_start0, ...]` array. Each
+/// fan covers vertices `center..=center + rim_count` and can be drawn with
+/// `gl.TRIANGLE_FAN` in place of its `3 * (rim_count - 1)` indices from
+/// `index_start`. Empty unless enabled with [`set_record_fans`].
+#[wasm_bindgen]
+pub fn get_fan_ranges() -> Vec<u32> {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow().as_ref().map_or_else(Vec::new, |geom| {
+            geom.fan_ranges
+                .iter()
+                .flat_map(|fan| [fan.center, fan.rim_count, fan.index_start])
+                .collect()
+        })
+    })
+}
+
+/// Retrieve the clear ranges of the last parsed layer tagged with their source.
+///
+/// Returns an array of `{ index_start, index_end, source }` objects, where
+/// `source` is `"Polarity"` for `%LPC*%` geometry or `"MacroExposure"` for
+/// aperture macro primitives with exposure off, in the order of
+/// [`get_clear_ranges`]. Returns an empty array if no layer has been parsed.
+///
+/// # Errors
+///
+/// Returns an error if the ranges cannot be serialized.
+#[wasm_bindgen]
+pub fn get_clear_range_sources() -> Result<JsValue, JsValue> {
+    LAST_GEOMETRY.with(|g| {
+        let ranges = g
+            .borrow()
+            .as_ref()
+            .map_or_else(Vec::new, LayerGeometry::tagged_clear_ranges);
+        serde_wasm_bindgen::to_value(&ranges).map_err(|e| JsValue::from_str(&e.to_string()))
+    })
+}
+
+/// Retrieve the object attribute ranges for the last parsed layer.
+///
+/// Returns an array of `{ index_start, index_end, attributes }` objects, where
+/// `attributes` maps X2 attribute names (e.g. `.N`) to their field values.
+/// Returns an empty array if no layer has been parsed.
+///
+/// # Errors
+///
+/// Returns an error if the ranges cannot be serialized.
+#[wasm_bindgen]
+pub fn get_attribute_ranges() -> Result<JsValue, JsValue> {
+    LAST_GEOMETRY.with(|g| {
+        let geom = g.borrow();
+        let ranges = geom
+            .as_ref()
+            .map_or(&[][..], |geom| geom.attribute_ranges.as_slice());
+        serde_wasm_bindgen::to_value(ranges).map_err(|e| JsValue::from_str(&e.to_string()))
+    })
+}
+
+/// Retrieve the area and perimeter of each region in the last parsed layer.
+///
+/// Returns an array of `{ area, perimeter }` objects in millimeters, one per
+/// filled region in fill order. Returns an empty array if no layer has been
+/// parsed.
+///
+/// # Errors
+///
+/// Returns an error if the metrics cannot be serialized.
+#[wasm_bindgen]
+pub fn get_region_metrics() -> Result<JsValue, JsValue> {
+    LAST_GEOMETRY.with(|g| {
+        let geom = g.borrow();
+        let metrics = geom
+            .as_ref()
+            .map_or(&[][..], |geom| geom.region_metrics.as_slice());
+        serde_wasm_bindgen::to_value(metrics).map_err(|e| JsValue::from_str(&e.to_string()))
+    })
+}
+
+/// Retrieve an affine matrix fitting the last parsed layer into a viewport.
+///
+/// Returns `[a, b, c, d, e, f]` for Canvas `setTransform` or SVG `matrix()`.
+/// With `flip_y`, board Y-up maps to screen Y-down. Returns the identity
+/// matrix if no layer has been parsed or the layer is empty.
+#[wasm_bindgen]
+pub fn get_fit_transform(viewport_w: f64, viewport_h: f64, flip_y: bool) -> Vec<f32> {
+    LAST_GEOMETRY.with(|g| {
+        g.borrow()
+            .as_ref()
+            .map_or(geometry::IDENTITY_TRANSFORM, |geom| {
+                geometry::fit_transform(&geom.bounds, viewport_w, viewport_h, flip_y)
+            })
+            .to_vec()
+    })
+}
+
+/// Map a screen pixel back to board coordinates for hit-testing.
+///
+/// `matrix` is the `[a, b, c, d, e, f]` array from [`get_fit_transform`].
+/// Returns `[x, y]` in board units, or an empty array if `matrix` does not
+/// have six entries or cannot be inverted.
+#[wasm_bindgen]
+pub fn screen_to_board(matrix: &[f64], px: f64, py: f64) -> Vec<f64> {
+    <[f64; 6]>::try_from(matrix)
+        .ok()
+        .and_then(|matrix| geometry::screen_to_board(matrix, px, py))
+        .map_or_else(Vec::new, |(x, y)| vec![x, y])
+}
+
+/// Map a board point to screen pixels with a [`get_fit_transform`] matrix.
+///
+/// Returns `[px, py]`, or an empty array if `matrix` does not have six
+/// entries.
+#[wasm_bindgen]
+pub fn board_to_screen(matrix: &[f64], x: f64, y: f64) -> Vec<f64> {
+    <[f64; 6]>::try_from(matrix).map_or_else(
+        |_| Vec::new(),
+        |matrix| {
+            let (px, py) = geometry::board_to_screen(matrix, x, y);
+            vec![px, py]
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,6 +1188,100 @@ mod tests {
         assert_eq!(meta.command_count, 5, "expected five drill commands");
     }
 
+    #[test]
+    fn validate_rectangle_fixture_has_no_fatal_error() {
+        let data = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        let report = validate_gerber_internal(data);
+        assert!(!report.fatal, "unexpected error: {:?}", report.error);
+        assert!(report.command_count > 0);
+    }
+
+    #[test]
+    fn validate_rejects_garbage_and_empty_input() {
+        let garbage: &[u8] = &[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01, 0x02, 0x03];
+        let report = validate_gerber_internal(garbage);
+        assert!(report.fatal || report.command_count == 0);
+        assert!(validate_gerber_internal(&[]).fatal);
+        assert!(validate_excellon_internal(&[]).fatal);
+
+        let drill = include_bytes!("../tests/fixtures/minimal/drill.drl");
+        let report = validate_excellon_internal(drill);
+        assert!(!report.fatal);
+        assert_eq!(report.command_count, 5);
+    }
+
+    #[test]
+    fn session_combines_appended_layers() {
+        let gerber = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        let drill = include_bytes!("../tests/fixtures/minimal/drill.drl");
+        let (Ok(gerber_meta), Ok(drill_meta)) = (
+            parse_gerber_internal(gerber),
+            parse_excellon_internal(drill),
+        ) else {
+            return;
+        };
+
+        assert!(append_gerber_internal(gerber).is_err(), "no session yet");
+        begin_session();
+        assert!(append_gerber_internal(gerber).is_ok());
+        assert!(append_excellon_internal(drill).is_ok());
+        let combined = finish_session_internal();
+        assert!(combined.is_ok(), "session should finish");
+        let Ok(combined) = combined else {
+            return;
+        };
+
+        assert_eq!(
+            combined.vertex_count,
+            gerber_meta.vertex_count + drill_meta.vertex_count
+        );
+        assert_eq!(
+            combined.index_count,
+            gerber_meta.index_count + drill_meta.index_count
+        );
+        let ranges = get_layer_ranges();
+        assert_eq!(
+            ranges,
+            vec![
+                0,
+                gerber_meta.index_count,
+                gerber_meta.index_count,
+                combined.index_count
+            ]
+        );
+        let max_index = get_indices().into_iter().max().unwrap_or(0);
+        assert!(max_index < combined.vertex_count);
+        assert!(
+            finish_session_internal().is_err(),
+            "session already finished"
+        );
+    }
+
+    #[test]
+    fn aperture_table_follows_the_last_parsed_layer() {
+        let gerber = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        let drill = include_bytes!("../tests/fixtures/minimal/drill.drl");
+        let apertures = || {
+            LAST_GEOMETRY.with(|g| {
+                g.borrow()
+                    .as_ref()
+                    .map_or_else(Vec::new, |geom| geom.apertures.clone())
+            })
+        };
+
+        assert!(parse_gerber_internal(gerber).is_ok());
+        let table = apertures();
+        assert!(table.iter().any(|info| info.flash_count > 0));
+        assert!(parse_excellon_internal(drill).is_ok());
+        assert!(apertures().is_empty(), "drill layer has no aperture table");
+
+        begin_session();
+        assert!(append_gerber_internal(gerber).is_ok());
+        assert!(append_excellon_internal(drill).is_ok());
+        assert!(finish_session_internal().is_ok());
+        assert_eq!(apertures(), table);
+    }
+
     #[test]
     fn get_buffers_empty_without_parse() {
         LAST_GEOMETRY.with(|g| {
@@ -273,6 +1290,8 @@ mod tests {
         let positions = get_positions();
         let indices = get_indices();
         let clear_ranges = get_clear_ranges();
+        assert_eq!(positions_byte_len(), 0);
+        assert_eq!(indices_byte_len(), 0);
         assert!(positions.is_empty(), "no parse yet => empty positions");
         assert!(indices.is_empty(), "no parse yet => empty indices");
         assert!(
@@ -281,16 +1300,372 @@ mod tests {
         );
     }
 
+    #[test]
+    fn byte_lengths_match_buffer_sizes() {
+        let data = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        assert!(parse_gerber_internal(data).is_ok());
+        let positions = get_positions();
+        let indices = get_indices();
+        assert!(!positions.is_empty());
+        assert_eq!(positions_byte_len() as usize, positions.len() * 4);
+        assert_eq!(indices_byte_len() as usize, indices.len() * 4);
+    }
+
+    #[test]
+    fn silk_and_copper_layers_get_distinct_styles() {
+        let body = "%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\nM02*\n";
+        let silk =
+            parse_gerber_internal(format!("%TF.FileFunction,Legend,Top*%\n{body}").as_bytes());
+        let copper =
+            parse_gerber_internal(format!("%TF.FileFunction,Copper,L1,Top*%\n{body}").as_bytes());
+        let (Ok(silk), Ok(copper)) = (silk, copper) else {
+            return;
+        };
+        assert_eq!(silk.layer_role, geometry::LayerRole::TopSilk);
+        assert_eq!(copper.layer_role, geometry::LayerRole::TopCopper);
+        assert_ne!(silk.suggested_color_rgba, copper.suggested_color_rgba);
+        assert!(silk.render_priority > copper.render_priority);
+
+        assert!(set_layer_style("TopSilk", 0x0102_03ff, 1));
+        let overridden =
+            parse_gerber_internal(format!("%TF.FileFunction,Legend,Top*%\n{body}").as_bytes());
+        assert!(reset_layer_style("TopSilk"));
+        assert!(!set_layer_style("Nonsense", 0, 0));
+        assert!(overridden.is_ok_and(|meta| meta.suggested_color_rgba == [1, 2, 3, 255]));
+    }
+
+    #[test]
+    fn scalar_stats_match_layer_meta() {
+        let data = include_bytes!("../tests/fixtures/minimal/undefined_aperture.gbr");
+        let Ok(meta) = parse_gerber_internal(data) else {
+            return;
+        };
+        assert_eq!(last_vertex_count(), meta.vertex_count);
+        assert_eq!(last_triangle_count(), meta.index_count / 3);
+        assert_eq!(last_command_count(), meta.command_count);
+        assert_eq!(last_warning_count(), meta.warning_count);
+        assert!(meta.warning_count > 0);
+    }
+
+    #[test]
+    fn f64_positions_retained_when_enabled() {
+        let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10R,1.000000X1.000000*%\nD10*\n\
+            X123456789Y0D03*\nM02*\n";
+        set_keep_f64_positions(true);
+        let result = parse_gerber_internal(data);
+        set_keep_f64_positions(false);
+        assert!(result.is_ok());
+
+        let precise = get_positions_f64();
+        let single = get_positions();
+        assert_eq!(precise.len(), single.len());
+        let max_x = precise.iter().step_by(2).fold(f64::MIN, |a, &b| a.max(b));
+        assert!((max_x - 123.956_789).abs() < 1e-9, "max x was {max_x}");
+        let max_x_f32 = single.iter().step_by(2).fold(f32::MIN, |a, &b| a.max(b));
+        assert!((f64::from(max_x_f32) - 123.956_789).abs() > 1e-9);
+
+        assert!(parse_gerber_internal(data).is_ok());
+        assert!(get_positions_f64().is_empty(), "disabled by default");
+    }
+
+    #[test]
+    fn excellon_mirror_reflects_holes_about_origin() {
+        let data = b"M48\nMETRIC\nT1C1.0\n%\nT1\nX10.0Y5.0\nM30\n";
+        set_excellon_mirror(true, false, 0.0, 0.0);
+        let result = parse_excellon_internal(data);
+        set_excellon_mirror(false, false, 0.0, 0.0);
+        assert!(result.is_ok());
+
+        let positions = get_positions();
+        let xs = positions.iter().step_by(2);
+        let center_x = xs.clone().sum::<f32>() / 32.0;
+        assert!((center_x + 10.0).abs() < 1e-4, "center x was {center_x}");
+        let width =
+            xs.clone().fold(f32::MIN, |a, &b| a.max(b)) - xs.fold(f32::MAX, |a, &b| a.min(b));
+        assert!(
+            (width - 1.0).abs() < 1e-3,
+            "diameter unchanged, was {width}"
+        );
+        let center_y = positions.iter().skip(1).step_by(2).sum::<f32>() / 32.0;
+        assert!((center_y - 5.0).abs() < 1e-4, "y untouched, was {center_y}");
+    }
+
+    #[test]
+    fn excellon_rotation_turns_holes_after_mirroring() {
+        let data = b"M48\nMETRIC\nT1C1.0\n%\nT1\nX10.0Y0.0\nM30\n";
+        let center = || {
+            let positions = get_positions();
+            let x = positions.iter().step_by(2).sum::<f32>() / 32.0;
+            let y = positions.iter().skip(1).step_by(2).sum::<f32>() / 32.0;
+            (x, y)
+        };
+
+        set_excellon_rotation(90.0, 0.0, 0.0);
+        let rotated = parse_excellon_internal(data).map(|_| center());
+        set_excellon_mirror(true, false, 0.0, 0.0);
+        let mirrored = parse_excellon_internal(data).map(|_| center());
+        set_excellon_mirror(false, false, 0.0, 0.0);
+        set_excellon_rotation(0.0, 0.0, 0.0);
+
+        let Ok((x, y)) = rotated else {
+            return;
+        };
+        assert!(
+            x.abs() < 1e-4 && (y - 10.0).abs() < 1e-4,
+            "rotated to ({x}, {y})"
+        );
+        let Ok((x, y)) = mirrored else {
+            return;
+        };
+        assert!(
+            x.abs() < 1e-4 && (y + 10.0).abs() < 1e-4,
+            "mirrored to ({x}, {y})"
+        );
+    }
+
+    #[test]
+    fn drill_ring_width_leaves_hole_centers_open() {
+        let data = b"M48\nMETRIC\nT1C1.0\n%\nT1\nX10.0Y5.0\nX20.0Y5.0\nM30\n";
+        set_drill_ring_width(0.1);
+        let result = parse_excellon_internal(data);
+        set_drill_ring_width(0.0);
+        assert!(result.is_ok());
+
+        let positions = get_positions();
+        assert_eq!(positions.len(), 2 * 2 * 64);
+        for (hole, xy) in positions.chunks_exact(2).enumerate() {
+            let center_x = if hole < 64 { 10.0 } else { 20.0 };
+            let (dx, dy) = (
+                xy.first().map_or(0.0, |x| x - center_x),
+                xy.get(1).map_or(0.0, |y| y - 5.0),
+            );
+            let radius = dx.hypot(dy);
+            assert!(
+                radius > 0.39,
+                "vertex inside the open center at radius {radius}"
+            );
+        }
+    }
+
+    /// Builds a ZIP archive of stored (uncompressed) entries.
+    #[cfg(feature = "zip")]
+    fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let le16 = |v: usize| u16::try_from(v).unwrap_or(u16::MAX).to_le_bytes();
+        let le32 = |v: usize| u32::try_from(v).unwrap_or(u32::MAX).to_le_bytes();
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data) in files {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let offset = archive.len();
+            let fields = [
+                &[20, 0, 0, 0, 0, 0, 0, 0, 0, 0][..],
+                &crc.sum().to_le_bytes(),
+                &le32(data.len()),
+                &le32(data.len()),
+                &le16(name.len()),
+                &[0, 0],
+            ]
+            .concat();
+            archive.extend_from_slice(&0x0403_4b50_u32.to_le_bytes());
+            archive.extend_from_slice(&fields);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(data);
+            directory.extend_from_slice(&0x0201_4b50_u32.to_le_bytes());
+            directory.extend_from_slice(&[20, 0]);
+            directory.extend_from_slice(&fields);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&le32(offset));
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = archive.len();
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&0x0605_4b50_u32.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]);
+        archive.extend_from_slice(&le16(files.len()));
+        archive.extend_from_slice(&le16(files.len()));
+        archive.extend_from_slice(&le32(directory.len()));
+        archive.extend_from_slice(&le32(directory_offset));
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    /// Builds a ZIP archive of one deflated entry whose headers declare
+    /// `declared_size` uncompressed bytes.
+    #[cfg(feature = "zip")]
+    fn deflated_zip(name: &str, data: &[u8], declared_size: u32) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        let compressed = encoder
+            .write_all(data)
+            .and_then(|()| encoder.finish())
+            .unwrap_or_default();
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+
+        let mut archive = stored_zip(&[(name, &compressed)]);
+        let directory = archive
+            .len()
+            .checked_sub(22)
+            .and_then(|end| archive.get(end + 16..end + 20))
+            .map_or(0, |offset| {
+                u32::from_le_bytes(offset.try_into().unwrap_or_default()) as usize
+            });
+        for header in [4, directory + 6] {
+            for (field, bytes) in [
+                (header + 4, 8_u16.to_le_bytes().to_vec()),
+                (header + 10, crc.sum().to_le_bytes().to_vec()),
+                (header + 18, declared_size.to_le_bytes().to_vec()),
+            ] {
+                if let Some(slot) = archive.get_mut(field..field + bytes.len()) {
+                    slot.copy_from_slice(&bytes);
+                }
+            }
+        }
+        archive
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn archive_entries_with_lying_sizes_are_rejected() {
+        let data = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        let size = u32::try_from(data.len()).unwrap_or(u32::MAX);
+
+        let honest = archive::read_zip(&deflated_zip("rectangle.gbr", data, size));
+        assert!(honest.is_ok_and(|entries| entries.first().is_some_and(|entry| entry.data == data)));
+
+        for declared in [u32::MAX, size / 2, size + 1] {
+            let result = archive::read_zip(&deflated_zip("rectangle.gbr", data, declared));
+            assert!(
+                matches!(result, Err(error::GeometryError::ParseError(ref message)) if message.contains("rectangle.gbr")),
+                "declared {declared}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_and_parse_separates_gerber_and_drill_sections() {
+        let mut job = include_bytes!("../tests/fixtures/minimal/rectangle.gbr").to_vec();
+        job.extend_from_slice(include_bytes!("../tests/fixtures/minimal/drill.drl"));
+
+        let layers = split_and_parse_internal(&job).unwrap_or_default();
+        let roles: Vec<geometry::LayerRole> = layers.iter().map(|layer| layer.layer_role).collect();
+        assert_eq!(
+            roles,
+            [geometry::LayerRole::Other, geometry::LayerRole::Drill]
+        );
+        assert!(layers.iter().all(|layer| layer.vertex_count > 0));
+
+        assert!(select_split_layer(1));
+        assert_eq!(
+            get_positions().len() / 2,
+            layers.get(1).map_or(0, |layer| layer.vertex_count as usize)
+        );
+        assert!(!select_split_layer(2));
+    }
+
+    #[test]
+    fn split_and_parse_detects_drill_section_after_byte_order_mark() {
+        let mut job = b"\xEF\xBB\xBF".to_vec();
+        job.extend_from_slice(include_bytes!("../tests/fixtures/minimal/drill.drl"));
+        job.extend_from_slice(include_bytes!("../tests/fixtures/minimal/rectangle.gbr"));
+
+        let layers = split_and_parse_internal(&job).unwrap_or_default();
+        let roles: Vec<geometry::LayerRole> = layers.iter().map(|layer| layer.layer_role).collect();
+        assert_eq!(
+            roles,
+            [geometry::LayerRole::Drill, geometry::LayerRole::Other]
+        );
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn archive_layers_parse_with_detected_kinds() {
+        let archive = stored_zip(&[
+            (
+                "board/rectangle.gbr",
+                include_bytes!("../tests/fixtures/minimal/rectangle.gbr"),
+            ),
+            ("README.txt", b"Fabrication notes"),
+            (
+                "board/drill.drl",
+                include_bytes!("../tests/fixtures/minimal/drill.drl"),
+            ),
+        ]);
+
+        let layers = parse_archive_internal(&archive).unwrap_or_default();
+        let kinds: Vec<(&str, archive::LayerKind)> = layers
+            .iter()
+            .map(|layer| (layer.name.as_str(), layer.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("board/rectangle.gbr", archive::LayerKind::Gerber),
+                ("board/drill.drl", archive::LayerKind::Excellon),
+            ]
+        );
+        assert!(layers.iter().all(|layer| layer.meta.vertex_count > 0));
+
+        assert!(select_archive_layer("board/drill.drl"));
+        assert_eq!(
+            get_positions().len() / 2,
+            layers
+                .get(1)
+                .map_or(0, |layer| layer.meta.vertex_count as usize)
+        );
+        assert!(!select_archive_layer("missing.gbr"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_input_parses_like_uncompressed() {
+        use std::io::Write;
+
+        let data = include_bytes!("../tests/fixtures/minimal/rectangle.gbr");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        assert!(encoder.write_all(data).is_ok());
+        let Ok(compressed) = encoder.finish() else {
+            return;
+        };
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+
+        assert!(parse_gerber_internal(data).is_ok());
+        let (plain_positions, plain_indices) = (get_positions(), get_indices());
+        assert!(parse_gerber_internal(&compressed).is_ok());
+        assert_eq!(get_positions(), plain_positions);
+        assert_eq!(get_indices(), plain_indices);
+    }
+
     #[test]
     fn get_clear_ranges_returns_flattened_pairs() {
         let mut geom = LayerGeometry {
             positions: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            positions_f64: Vec::new(),
             indices: vec![0, 1, 2],
             bounds: geometry::BoundingBox::new(),
+            dark_bounds: geometry::BoundingBox::new(),
             command_count: 1,
             vertex_count: 3,
             warnings: Vec::new(),
+            warning_commands: Vec::new(),
+            warning_categories: Vec::new(),
             clear_ranges: vec![(0, 3), (6, 12)],
+            clear_range_sources: Vec::new(),
+            attribute_ranges: Vec::new(),
+            region_metrics: Vec::new(),
+            truncated: false,
+            unsupported_features: Vec::new(),
+            coordinate_format: None,
+            format_assumed: false,
+            units_assumed: false,
+            layer_role: geometry::LayerRole::Other,
+            layer_name: None,
+            image_negative: false,
+            fan_ranges: Vec::new(),
+            apertures: Vec::new(),
         };
         geom.bounds.update(0.0, 0.0);
         geom.bounds.update(1.0, 1.0);
@@ -0,0 +1,124 @@
+//! Quick header inspection of Gerber and Excellon files.
+//!
+//! Reads units, coordinate format and aperture or tool definitions without
+//! running the parser or building geometry, for file-browser previews.
+
+use serde::Serialize;
+
+use crate::error::GeometryError;
+use crate::excellon::{self, ExcellonUnits};
+use crate::geometry::LayerRole;
+
+/// Unit system declared by a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Units {
+    /// Millimeters (`%MOMM*%`, `METRIC`).
+    Millimeters,
+    /// Inches (`%MOIN*%`, `INCH`).
+    Inches,
+}
+
+/// Header summary of a Gerber or Excellon file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeaderInfo {
+    /// Declared units; `None` when the file does not declare them.
+    pub units: Option<Units>,
+    /// Integer and decimal coordinate digits; `None` for a Gerber file
+    /// without `%FS`.
+    pub format: Option<(u8, u8)>,
+    /// Number of `%AD` aperture definitions (Gerber).
+    pub aperture_count: u32,
+    /// Number of tool definitions in the header (Excellon).
+    pub tool_count: u32,
+    /// `%TF` file attributes as written, without the `TF` prefix, such as
+    /// `.FileFunction,Copper,L1,Top`.
+    pub attributes: Vec<String>,
+    /// Role detected from the `.FileFunction` attribute; [`LayerRole::Drill`]
+    /// for Excellon files.
+    pub layer_role: LayerRole,
+}
+
+/// Reads the header of a Gerber file.
+///
+/// Only extended (`%...%`) commands are inspected, so the scan is a single
+/// pass over the bytes. Aperture definitions are counted across the whole
+/// file since `%AD` may follow the first drawing operation.
+pub fn peek_gerber(data: &[u8]) -> HeaderInfo {
+    let text = String::from_utf8_lossy(data);
+    let mut info = HeaderInfo {
+        units: None,
+        format: None,
+        aperture_count: 0,
+        tool_count: 0,
+        attributes: Vec::new(),
+        layer_role: LayerRole::Other,
+    };
+
+    let extended = text.split('%').skip(1).step_by(2);
+    for command in extended.flat_map(|block| block.split('*')) {
+        let command = command.trim();
+        if let Some(attribute) = command.strip_prefix("TF") {
+            info.attributes.push(attribute.to_string());
+            if let Some(function) = attribute.strip_prefix(".FileFunction,") {
+                info.layer_role = LayerRole::from_file_function(function);
+            }
+        } else if command.starts_with("AD") {
+            info.aperture_count = info.aperture_count.saturating_add(1);
+        } else if command.starts_with("FS") {
+            info.format = format_spec(command).or(info.format);
+        } else if command == "MOMM" {
+            info.units = Some(Units::Millimeters);
+        } else if command == "MOIN" {
+            info.units = Some(Units::Inches);
+        }
+    }
+    info
+}
+
+/// Reads the `M48` header of an Excellon drill file.
+///
+/// # Errors
+///
+/// Returns [`GeometryError::ParseError`] for empty or non-UTF-8 input and
+/// malformed tool definitions.
+pub fn peek_excellon(data: &[u8]) -> Result<HeaderInfo, GeometryError> {
+    let header = excellon::parser::parse_header(data)?;
+    Ok(HeaderInfo {
+        units: header.declared_units.then_some(match header.units {
+            ExcellonUnits::Metric => Units::Millimeters,
+            ExcellonUnits::Imperial => Units::Inches,
+        }),
+        format: Some(header.format),
+        aperture_count: 0,
+        tool_count: crate::geometry::types::saturate_u32(header.tools.len()),
+        attributes: Vec::new(),
+        layer_role: LayerRole::Drill,
+    })
+}
+
+/// Parses the X digits of `FSLAX36Y36`.
+fn format_spec(command: &str) -> Option<(u8, u8)> {
+    let (_, x) = command.split_once('X')?;
+    let mut digits = x.chars().filter_map(|ch| ch.to_digit(10));
+    let integer = u8::try_from(digits.next()?).ok()?;
+    let decimal = u8::try_from(digits.next()?).ok()?;
+    Some((integer, decimal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gerber_header_reports_units_format_and_apertures() {
+        let info = peek_gerber(
+            b"%TF.FileFunction,Copper,L1,Top*%\n%FSLAX36Y36*%\n%MOMM*%\n\
+%ADD10C,0.5*%\n%ADD11R,1X2*%\nD10*\nX0Y0D03*\nM02*\n",
+        );
+        assert_eq!(info.units, Some(Units::Millimeters));
+        assert_eq!(info.format, Some((3, 6)));
+        assert_eq!(info.aperture_count, 2);
+        assert_eq!(info.attributes, [".FileFunction,Copper,L1,Top"]);
+        assert_eq!(info.layer_role, LayerRole::TopCopper);
+    }
+}
@@ -0,0 +1,182 @@
+//! Fixes applied to Gerber source text before it reaches `gerber_parser`.
+
+use std::borrow::Cow;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Removes a leading byte-order mark, transcoding UTF-16 input to UTF-8.
+///
+/// Files saved by some Windows tools start with a UTF-8 BOM, which would
+/// otherwise become part of the first command, or are UTF-16 encoded.
+/// UTF-16 is only recognized by its BOM; unpaired surrogates decode to
+/// U+FFFD. Input without a BOM is returned unchanged.
+pub fn decode_text(data: &[u8]) -> Cow<'_, [u8]> {
+    if let Some(rest) = data.strip_prefix(UTF8_BOM) {
+        return Cow::Borrowed(rest);
+    }
+    let from_bytes: fn([u8; 2]) -> u16 = if data.starts_with(UTF16_LE_BOM) {
+        u16::from_le_bytes
+    } else if data.starts_with(UTF16_BE_BOM) {
+        u16::from_be_bytes
+    } else {
+        return Cow::Borrowed(data);
+    };
+    let units = data
+        .get(2..)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|pair| {
+            from_bytes([
+                pair.first().copied().unwrap_or_default(),
+                pair.last().copied().unwrap_or_default(),
+            ])
+        });
+    let text: String = char::decode_utf16(units)
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    Cow::Owned(text.into_bytes())
+}
+
+/// Kind of a segment found by [`split_layers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// Gerber image.
+    Gerber,
+    /// Excellon drill section.
+    Excellon,
+}
+
+/// One layer cut from a concatenated job by [`split_layers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<'a> {
+    /// Detected file kind.
+    pub kind: SegmentKind,
+    /// Source bytes of the layer.
+    pub data: &'a [u8],
+    /// Set when the segment starts at a guessed boundary rather than after
+    /// an `M02`/`M30` end-of-file code.
+    pub warning: Option<String>,
+}
+
+/// Splits a job made of several concatenated files into per-layer segments.
+///
+/// A Gerber image ends at `M02*` and a drill section at `M30`; an `M48` line
+/// starts a drill section. A new `%FS` or `%IN` after drawing operations, or
+/// a drill header inside a Gerber image, also starts a new layer, but since
+/// the previous one was never terminated that boundary is a guess and the
+/// new segment carries a warning. Blank lines between layers are dropped.
+pub fn split_layers(data: &[u8]) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut current: Option<(SegmentKind, usize, Option<String>)> = None;
+    let mut has_operations = false;
+    let mut offset = 0;
+    for (number, line) in data.split_inclusive(|&byte| byte == b'\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let text = String::from_utf8_lossy(line);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let drill_header = text.eq_ignore_ascii_case("M48");
+        let image_header = text.starts_with("%FS") || text.starts_with("%IN");
+        let boundary = match current {
+            Some((SegmentKind::Gerber, ..)) => drill_header || (image_header && has_operations),
+            Some((SegmentKind::Excellon, ..)) => image_header,
+            None => false,
+        };
+        if boundary {
+            if let Some((kind, first, warning)) = current.take() {
+                push_segment(&mut segments, kind, data.get(first..start), warning);
+            }
+        }
+        let kind = current
+            .get_or_insert_with(|| {
+                has_operations = false;
+                let kind = if drill_header {
+                    SegmentKind::Excellon
+                } else {
+                    SegmentKind::Gerber
+                };
+                let warning = boundary.then(|| {
+                    format!(
+                        "line {}: layer boundary without a preceding end-of-file code",
+                        number + 1
+                    )
+                });
+                (kind, start, warning)
+            })
+            .0;
+        let end_of_file = match kind {
+            SegmentKind::Gerber => {
+                has_operations |= ["D01", "D02", "D03"].iter().any(|code| text.contains(code));
+                text.contains("M02*")
+            }
+            SegmentKind::Excellon => text.starts_with("M30"),
+        };
+        if end_of_file {
+            if let Some((kind, first, warning)) = current.take() {
+                push_segment(&mut segments, kind, data.get(first..offset), warning);
+            }
+        }
+    }
+    if let Some((kind, first, warning)) = current {
+        push_segment(&mut segments, kind, data.get(first..), warning);
+    }
+    segments
+}
+
+fn push_segment<'a>(
+    segments: &mut Vec<Segment<'a>>,
+    kind: SegmentKind,
+    data: Option<&'a [u8]>,
+    warning: Option<String>,
+) {
+    if let Some(data) = data {
+        segments.push(Segment {
+            kind,
+            data,
+            warning,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_order_marks_are_stripped_and_utf16_transcoded() {
+        assert_eq!(decode_text(b"\xEF\xBB\xBFM48\n").as_ref(), b"M48\n");
+        assert_eq!(decode_text(b"\xFF\xFEM\x004\x008\x00").as_ref(), b"M48");
+        assert_eq!(decode_text(b"\xFE\xFF\x00M\x004\x008").as_ref(), b"M48");
+        assert!(matches!(decode_text(b"M48\n"), Cow::Borrowed(b"M48\n")));
+    }
+
+    #[test]
+    fn concatenated_job_splits_at_end_of_file_codes() {
+        let segments =
+            split_layers(b"%FSLAX26Y26*%\nD10*\nX0Y0D03*\nM02*\n\nM48\nT1C0.8\n%\nT1\nX0Y0\nM30\n");
+        let kinds: Vec<SegmentKind> = segments.iter().map(|segment| segment.kind).collect();
+        assert_eq!(kinds, [SegmentKind::Gerber, SegmentKind::Excellon]);
+        assert!(segments.iter().all(|segment| segment.warning.is_none()));
+        assert!(segments
+            .last()
+            .is_some_and(|segment| segment.data.starts_with(b"M48\n")));
+    }
+
+    #[test]
+    fn unterminated_image_is_split_with_a_warning() {
+        let segments = split_layers(b"%FSLAX26Y26*%\nX0Y0D03*\n%FSLAX26Y26*%\nX1Y1D03*\nM02*\n");
+        assert_eq!(segments.len(), 2);
+        assert!(segments
+            .first()
+            .is_some_and(|segment| segment.warning.is_none()));
+        assert!(segments
+            .last()
+            .and_then(|segment| segment.warning.as_deref())
+            .is_some_and(|warning| warning.starts_with("line 3:")));
+    }
+}
@@ -1,6 +1,7 @@
 //! Integration tests for Excellon drill parsing.
 
-use gerberview_wasm::parse_excellon_internal;
+use gerberview_wasm::{excellon, get_drill_tool_ranges, get_indices, parse_excellon_internal};
+use std::collections::BTreeSet;
 
 /// Parse Excellon drill fixture → correct hole count, positions.
 #[test]
@@ -40,3 +41,83 @@ fn excellon_arduino_drill_bounds_match_expected() {
         "Arduino drill bounds should be within ~100mm"
     );
 }
+
+/// Two parses of the same drill file produce identical tools, holes, and warnings.
+#[test]
+#[allow(clippy::expect_used)]
+fn excellon_parse_is_deterministic_across_runs() {
+    let data = include_bytes!("fixtures/kicad-sample/board.drl");
+    let first = gerberview_wasm::excellon::parser::parse(data).expect("parse should succeed");
+    let second = gerberview_wasm::excellon::parser::parse(data).expect("parse should succeed");
+    assert_eq!(format!("{first:?}"), format!("{second:?}"));
+    assert!(first
+        .tools
+        .windows(2)
+        .all(|pair| pair[0].number < pair[1].number));
+}
+
+/// A two-hole pattern drilled once and repeated once yields four holes.
+#[test]
+#[allow(clippy::expect_used)]
+fn excellon_pattern_repeat_replays_holes_at_offset() {
+    let data = include_bytes!("fixtures/minimal/pattern.drl");
+    let parsed = gerberview_wasm::excellon::parser::parse(data).expect("parse should succeed");
+    let positions: Vec<(f64, f64)> = parsed.holes.iter().map(|hole| (hole.x, hole.y)).collect();
+    assert_eq!(
+        positions,
+        vec![(1.0, 1.0), (2.0, 1.0), (11.0, 6.0), (12.0, 6.0)]
+    );
+    assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+}
+
+/// Peeking the Arduino drill header → imperial units and three tools.
+#[test]
+#[allow(clippy::expect_used)]
+fn peek_excellon_reads_arduino_header() {
+    let data = include_bytes!("fixtures/arduino-uno/arduino-uno.drl");
+    let info = gerberview_wasm::peek::peek_excellon(data).expect("header should be readable");
+    assert_eq!(info.units, Some(gerberview_wasm::peek::Units::Inches));
+    assert_eq!(info.tool_count, 3);
+    assert_eq!(info.aperture_count, 0);
+}
+
+/// Parse Arduino drill file → one index range per tool used, tiling the mesh.
+#[test]
+#[allow(clippy::expect_used)]
+fn excellon_arduino_tool_ranges_cover_each_tool_once() {
+    let data = include_bytes!("fixtures/arduino-uno/arduino-uno.drl");
+    let parsed = excellon::parser::parse(data).expect("parse should succeed");
+    let used: BTreeSet<u32> = parsed.holes.iter().map(|hole| hole.tool).collect();
+
+    parse_excellon_internal(data).expect("parse should succeed");
+    let flat = get_drill_tool_ranges();
+    let ranges: Vec<&[u32]> = flat.chunks(3).collect();
+    assert_eq!(ranges.len(), used.len());
+
+    let tools: Vec<u32> = ranges.iter().map(|range| range[0]).collect();
+    assert_eq!(tools, used.into_iter().collect::<Vec<_>>());
+    let mut expected_start = 0;
+    for range in &ranges {
+        assert_eq!(range[1], expected_start, "ranges are contiguous");
+        assert!(range[2] > range[1]);
+        expected_start = range[2];
+    }
+    assert_eq!(expected_start as usize, get_indices().len());
+}
+
+/// A UTF-8 byte-order mark before `M48` does not change the parse.
+#[test]
+#[allow(clippy::expect_used)]
+fn excellon_bom_prefixed_drill_parses_like_plain() {
+    let plain = include_bytes!("fixtures/minimal/drill.drl");
+    let mut with_bom = b"\xEF\xBB\xBF".to_vec();
+    with_bom.extend_from_slice(plain);
+
+    let expected = excellon::parser::parse(plain).expect("plain file parses");
+    let parsed = excellon::parser::parse(&with_bom).expect("BOM-prefixed file parses");
+    assert_eq!(parsed.holes, expected.holes);
+    assert_eq!(parsed.warnings, expected.warnings);
+
+    let meta = parse_excellon_internal(&with_bom).expect("parse should succeed");
+    assert_eq!(meta.command_count, 5);
+}
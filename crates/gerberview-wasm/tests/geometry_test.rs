@@ -84,3 +84,1320 @@ fn convert_region_produces_geometry() {
         "region fixture should produce non-empty geometry"
     );
 }
+
+/// Filled pixels in the 70x40 reference render of the rectangle fixture.
+const RECTANGLE_REFERENCE_FILLED: usize = 400;
+
+/// Reference render of the rectangle fixture at 10 px/mm: two 20x10 blocks
+/// in opposite corners of a 70x40 image.
+fn rectangle_reference_rgba() -> Vec<u8> {
+    let (width, height) = (70_usize, 40_usize);
+    let mut pixels = geometry::BACKGROUND_RGBA.repeat(width * height);
+    let blocks = [(0..20, 30..40), (50..70, 0..10)];
+    for (columns, rows) in blocks {
+        for row in rows {
+            for column in columns.clone() {
+                let offset = (row * width + column) * 4;
+                if let Some(pixel) = pixels.get_mut(offset..offset + 4) {
+                    pixel.copy_from_slice(&geometry::FILL_RGBA);
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Rasterize minimal rectangle → filled-pixel count and buffer match the reference.
+#[test]
+#[allow(clippy::expect_used)]
+fn rasterize_rectangle_matches_reference() {
+    let data = include_bytes!("fixtures/minimal/rectangle.gbr");
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let geom = geometry::convert(&doc).expect("convert should succeed");
+    let pixels = geometry::rasterize_layer(&geom, 70, 40);
+
+    let filled = geometry::count_filled_pixels(&pixels);
+    let tolerance = RECTANGLE_REFERENCE_FILLED / 50;
+    assert!(
+        filled.abs_diff(RECTANGLE_REFERENCE_FILLED) <= tolerance,
+        "filled {filled} pixels, expected {RECTANGLE_REFERENCE_FILLED} ± {tolerance}"
+    );
+
+    let differing = geometry::count_differing_pixels(&pixels, &rectangle_reference_rgba(), 0);
+    assert!(
+        differing <= tolerance,
+        "{differing} pixels differ from the reference render"
+    );
+}
+
+/// Parse inline Gerber source and convert it to geometry.
+#[allow(clippy::expect_used)]
+fn convert_source(data: &[u8]) -> geometry::LayerGeometry {
+    let reader = BufReader::new(Cursor::new(data));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    geometry::convert(&doc).expect("convert should succeed")
+}
+
+/// Reopening SR without a close finalizes the first block before the second begins.
+#[test]
+fn sr_reopen_implicitly_closes_previous_block() {
+    let single =
+        convert_source(b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\nX0Y0D03*\nM02*\n");
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\n\
+        %SRX2Y1I5.0J0*%\nX0Y0D03*\n\
+        %SRX1Y3I0J5.0*%\nX20000000Y0D03*\n\
+        %SR*%\nM02*\n",
+    );
+
+    assert_eq!(
+        geom.vertex_count,
+        single.vertex_count * 5,
+        "expected 2 copies from the first block and 3 from the second"
+    );
+    assert!((geom.bounds.max_x - 20.5).abs() < 1e-6);
+    assert!((geom.bounds.max_y - 10.5).abs() < 1e-6);
+}
+
+/// Operations iterator over minimal rectangle → two D10 flashes at absolute positions.
+#[test]
+fn operations_rectangle_yields_flash_sequence() {
+    let data = include_bytes!("fixtures/minimal/rectangle.gbr");
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+
+    let flashes: Vec<(i32, f64, f64)> = geometry::operations(&doc)
+        .map(|op| match op {
+            geometry::ResolvedOp::Flash {
+                point,
+                dcode,
+                aperture,
+            } => {
+                assert!(matches!(aperture, gerber_types::Aperture::Rectangle(_)));
+                (dcode, point.x, point.y)
+            }
+            other => panic!("unexpected operation {other:?}"),
+        })
+        .collect();
+
+    assert_eq!(flashes.len(), 2);
+    assert_eq!(flashes.first(), Some(&(10, 0.0, 0.0)));
+    let (dcode, x, y) = flashes.get(1).copied().unwrap_or_default();
+    assert_eq!(dcode, 10);
+    assert!((x - 5.0).abs() < 1e-9 && (y - 3.0).abs() < 1e-9);
+}
+
+/// Two conversions of the same fixture serialize identically.
+#[test]
+#[allow(clippy::expect_used)]
+fn convert_is_deterministic_across_runs() {
+    let data = include_bytes!("fixtures/arduino-uno/arduino-uno.GTL");
+    let first = convert_source(data);
+    let second = convert_source(data);
+    assert_eq!(format!("{first:?}"), format!("{second:?}"));
+
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let dcodes: Vec<i32> = geometry::sorted_apertures(&doc)
+        .into_iter()
+        .map(|(dcode, _)| dcode)
+        .collect();
+    assert!(dcodes.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+/// Drawing with an undefined macro aperture warns and skips instead of failing the layer.
+#[test]
+fn undefined_macro_aperture_stroke_warns_and_skips() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\n%ADD11MISSING*%\n\
+        D10*\nX0Y0D03*\n\
+        D11*\nX0Y0D02*\nX5000000Y0D01*\nX5000000Y0D03*\nM02*\n",
+    );
+
+    assert!(geom.vertex_count > 0, "the circle flash should survive");
+    assert!(
+        geom.warnings
+            .iter()
+            .any(|w| w.contains("`MISSING` not defined; skipping draw")),
+        "expected a skipped-draw warning, got {:?}",
+        geom.warnings
+    );
+    assert!(geom
+        .warnings
+        .iter()
+        .any(|w| w.contains("`MISSING` not defined; skipping flash")));
+}
+
+/// A net attribute set with TO and deleted with TD applies only to the flash in between.
+#[test]
+fn object_attribute_scoped_by_delete() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\n\
+        %TO.N,GND*%\nX0Y0D03*\n%TD.N*%\nX5000000Y0D03*\nM02*\n",
+    );
+
+    assert_eq!(geom.attribute_ranges.len(), 1);
+    let range = &geom.attribute_ranges[0];
+    assert_eq!(range.index_start, 0);
+    assert_eq!(range.index_end as usize * 2, geom.indices.len());
+    assert_eq!(
+        range.attributes.get(".N"),
+        Some(&vec!["GND".to_string()]),
+        "the first flash carries the net name"
+    );
+}
+
+/// Attributes set inside a step-repeat block cover each replicated copy of
+/// the attributed flash, and nothing drawn after the block.
+#[test]
+fn object_attribute_scoped_inside_step_repeat() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\n\
+        %SRX2Y1I10.0J0*%\n%TO.N,GND*%\nX0Y0D03*\n%TD.N*%\nX5000000Y0D03*\n%SR*%\n\
+        X0Y20000000D03*\nM02*\n",
+    );
+
+    let flash = u32::try_from(geom.indices.len() / 5).expect("index count fits u32");
+    let spans: Vec<(u32, u32)> = geom
+        .attribute_ranges
+        .iter()
+        .map(|range| (range.index_start, range.index_end))
+        .collect();
+    assert_eq!(spans, [(0, flash), (2 * flash, 3 * flash)]);
+    assert!(geom
+        .attribute_ranges
+        .iter()
+        .all(|range| range.attributes.get(".N") == Some(&vec!["GND".to_string()])));
+}
+
+/// The warning callback fires once per accumulated warning, in order.
+#[test]
+#[allow(clippy::expect_used)]
+fn warning_callback_streams_each_warning_in_order() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\n\
+        X0Y0D03*\nD99*\nX1000000Y0D03*\n%SR*%\nD10*\nX0Y0D03*\nM02*\n";
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+
+    let mut streamed: Vec<String> = Vec::new();
+    let mut collect = |warning: &str| streamed.push(warning.to_string());
+    let geom = geometry::convert_with_options(
+        &doc,
+        &geometry::TessellationOptions::new(),
+        Some(&mut collect),
+    )
+    .expect("convert should succeed");
+
+    assert_eq!(geom.warnings.len(), 3, "warnings: {:?}", geom.warnings);
+    assert_eq!(streamed, geom.warnings);
+}
+
+/// A region opened with a D02 keeps the move target as its first vertex.
+#[test]
+fn region_move_establishes_start_vertex() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\nG01*\nG36*\nX0Y0D02*\n\
+        X10000000Y0D01*\nX10000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nG37*\nM02*\n",
+    );
+
+    let area: f64 = geom
+        .indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let p = |i: u32| {
+                let base = i as usize * 2;
+                (
+                    f64::from(geom.positions[base]),
+                    f64::from(geom.positions[base + 1]),
+                )
+            };
+            let ((ax, ay), (bx, by), (cx, cy)) = (p(tri[0]), p(tri[1]), p(tri[2]));
+            ((bx - ax) * (cy - ay) - (by - ay) * (cx - ax)).abs() / 2.0
+        })
+        .sum();
+    assert!((area - 100.0).abs() < 1e-6, "area was {area}");
+    assert!(
+        !geom.warnings.iter().any(|w| w.contains("not closed")),
+        "contour should close on its start vertex: {:?}",
+        geom.warnings
+    );
+}
+
+/// A region left open across an SR close is closed inside the block with a warning.
+#[test]
+fn region_open_across_sr_close_is_closed_with_warning() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\nG01*\n%SRX2Y1I20.0J0*%\nG36*\nX0Y0D02*\n\
+        X10000000Y0D01*\nX10000000Y10000000D01*\nX0Y0D01*\n%SR*%\nG37*\nM02*\n",
+    );
+
+    assert!(
+        geom.warnings
+            .iter()
+            .any(|w| w.contains("region open at step-repeat boundary")),
+        "warnings: {:?}",
+        geom.warnings
+    );
+    assert_eq!(geom.indices.len(), 6, "one triangle per block copy");
+    assert!(geom.indices.iter().all(|&i| i < geom.vertex_count));
+    assert!((geom.bounds.max_x - 30.0).abs() < 1e-6);
+}
+
+/// A `G74` inside an SR block stays in effect after the block closes.
+#[test]
+fn quadrant_mode_set_in_sr_block_persists_after_close() {
+    let source = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nG75*\n\
+        %SRX2Y1I20.0J0*%\nG74*\nX1000000Y0D02*\nG03X0Y1000000I1000000J0D01*\n%SR*%\n\
+        X1000000Y0D02*\nG03X0Y1000000I1000000J0D01*\n\
+        G75*\nX1000000Y0D02*\nG03X0Y1000000I-1000000J0D01*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&source[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let modes: Vec<geometry::ArcQuadrantMode> = geometry::operations(&doc)
+        .filter_map(|op| match op {
+            geometry::ResolvedOp::Arc { quadrant_mode, .. } => Some(quadrant_mode),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        modes,
+        [
+            geometry::ArcQuadrantMode::SingleQuadrant,
+            geometry::ArcQuadrantMode::SingleQuadrant,
+            geometry::ArcQuadrantMode::MultiQuadrant,
+        ]
+    );
+}
+
+/// A clear primitive larger than the dark one only widens `bounds`, not `dark_bounds`.
+#[test]
+fn dark_bounds_exclude_clear_geometry() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%AMPUNCH*1,1,1.0,0,0*1,0,3.0,0,0*%\n\
+        %ADD10PUNCH*%\nD10*\nX0Y0D03*\nM02*\n",
+    );
+
+    assert!(!geom.clear_ranges.is_empty());
+    assert!((geom.bounds.max_x - 1.5).abs() < 1e-3, "{:?}", geom.bounds);
+    assert!(
+        (geom.dark_bounds.max_x - 0.5).abs() < 1e-3,
+        "{:?}",
+        geom.dark_bounds
+    );
+    assert!(geom.dark_bounds.min_x > geom.bounds.min_x);
+    assert!(geom.dark_bounds.max_y < geom.bounds.max_y);
+}
+
+/// Preview mode tessellates the Arduino top copper with fewer vertices.
+#[test]
+#[allow(clippy::expect_used)]
+fn preview_mode_reduces_vertex_count() {
+    let data = include_bytes!("fixtures/arduino-uno/arduino-uno.GTL");
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+
+    let full = geometry::convert(&doc).expect("convert should succeed");
+    let preview_options = geometry::TessellationOptions::new().with_preview(0.02);
+    let preview = geometry::convert_with_options(&doc, &preview_options, None)
+        .expect("preview convert should succeed");
+
+    assert!(
+        preview.vertex_count < full.vertex_count,
+        "preview {} should be below full {}",
+        preview.vertex_count,
+        full.vertex_count
+    );
+    assert!(preview.indices.iter().all(|&i| i < preview.vertex_count));
+
+    let huge_threshold = geometry::TessellationOptions::new().with_preview(f64::MAX);
+    let skipped = geometry::convert_with_options(&doc, &huge_threshold, None)
+        .expect("preview convert should succeed");
+    assert!(
+        skipped
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("preview mode skipped")),
+        "skipped flashes should be reported: {:?}",
+        skipped.warnings
+    );
+}
+
+/// A units declaration after the first flash is reported.
+#[test]
+fn late_units_declaration_warns() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\nX0Y0D03*\n%MOIN*%\n\
+        X1000000Y0D03*\nM02*\n",
+    );
+
+    assert!(
+        geom.warnings
+            .iter()
+            .any(|w| w.contains("declared after geometry")),
+        "warnings: {:?}",
+        geom.warnings
+    );
+}
+
+/// Every instance of a multi-feature SR block is an identical, offset copy,
+/// including the block's clear-polarity ranges.
+#[test]
+fn sr_block_instances_are_identical_offset_copies() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.500000*%\n%ADD11R,1.000000X1.000000*%\n\
+        G01*\nG75*\nX-5000000Y-5000000D02*\n%SRX2Y1I10.0J0*%\n\
+        D10*\nX0Y0D03*\nD11*\nX2000000Y0D03*\n\
+        %LPC*%\nD10*\nX2000000Y0D03*\n%LPD*%\n\
+        X3000000Y0D02*\nG03*\nX4000000Y1000000I0J1000000D01*\nG01*\n\
+        %SR*%\nM02*\n",
+    );
+
+    assert!(geom.warnings.is_empty(), "warnings: {:?}", geom.warnings);
+    let half_vertices = geom.vertex_count as usize / 2;
+    let half_indices = geom.indices.len() / 2;
+    assert_eq!(half_vertices * 2, geom.vertex_count as usize);
+    assert_eq!(half_indices * 2, geom.indices.len());
+
+    let (first, second) = geom.positions.split_at(half_vertices * 2);
+    for (a, b) in first.chunks_exact(2).zip(second.chunks_exact(2)) {
+        let (Some(&ax), Some(&ay), Some(&bx), Some(&by)) =
+            (a.first(), a.get(1), b.first(), b.get(1))
+        else {
+            return;
+        };
+        assert!((bx - ax - 10.0).abs() < 1e-4, "x offset {ax} -> {bx}");
+        assert!((by - ay).abs() < 1e-6, "y offset {ay} -> {by}");
+    }
+
+    let (first_idx, second_idx) = geom.indices.split_at(half_indices);
+    let vertex_offset = u32::try_from(half_vertices).unwrap_or(u32::MAX);
+    assert!(first_idx
+        .iter()
+        .zip(second_idx)
+        .all(|(a, b)| *b == *a + vertex_offset));
+
+    let index_offset = u32::try_from(half_indices).unwrap_or(u32::MAX);
+    assert_eq!(geom.clear_ranges.len(), 2, "{:?}", geom.clear_ranges);
+    if let [(s0, e0), (s1, e1)] = geom.clear_ranges.as_slice() {
+        assert_eq!((*s1, *e1), (s0 + index_offset, e0 + index_offset));
+        assert!(*e0 <= index_offset);
+    }
+}
+
+/// A warning raised by a flash carries the index of that flash command.
+#[test]
+#[allow(clippy::expect_used)]
+fn undefined_aperture_warning_carries_command_index() {
+    use gerber_types::{Command, DCode, FunctionCode};
+
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\nX0Y0D03*\n\
+        D99*\nX1000000Y0D03*\nM02*\n";
+    let reader = BufReader::new(Cursor::new(data.as_slice()));
+    let doc = match gerber_parser::parse(reader) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let select_99 = doc
+        .commands
+        .iter()
+        .position(|cmd| {
+            matches!(
+                cmd,
+                Ok(Command::FunctionCode(FunctionCode::DCode(
+                    DCode::SelectAperture(99)
+                )))
+            )
+        })
+        .expect("D99 selection should be parsed");
+
+    let geom = geometry::convert(&doc).expect("convert should succeed");
+    let details = geom.warning_details();
+    let detail = details
+        .iter()
+        .find(|d| d.message.contains("D99 not defined"))
+        .expect("undefined aperture warning");
+    let expected = u32::try_from(select_99 + 1).ok();
+    assert_eq!(detail.command_index, expected);
+}
+
+/// Aperture changes during clear polarity keep the clear range aligned with
+/// exactly the flashes drawn while clear.
+#[test]
+fn aperture_change_inside_clear_polarity_keeps_ranges_aligned() {
+    // Circle flashes emit 30 triangles (90 indices), rectangles 2 (6 indices).
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\n%ADD11R,1.000000X1.000000*%\n\
+        D10*\nX0Y0D03*\n%LPC*%\nD11*\nX0Y0D03*\nD10*\nX1000000Y0D03*\n%LPD*%\n\
+        D11*\nX2000000Y0D03*\nM02*\n",
+    );
+
+    assert_eq!(geom.indices.len(), 90 + 6 + 90 + 6);
+    assert_eq!(geom.clear_ranges, vec![(90, 90 + 6 + 90)]);
+    assert!(geom
+        .clear_ranges
+        .iter()
+        .all(|&(start, end)| start % 3 == 0 && end % 3 == 0));
+}
+
+/// Clear ranges from `%LPC*%` and from macro exposure-off primitives are
+/// tagged with distinct sources.
+#[test]
+fn clear_ranges_are_tagged_by_source() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%AMPUNCH*1,1,2.0,0,0*1,0,1.0,0,0*%\n\
+        %ADD10PUNCH*%\n%ADD11C,1.000000*%\nD10*\nX0Y0D03*\n\
+        %LPC*%\nD11*\nX5000000Y0D03*\n%LPD*%\nM02*\n",
+    );
+
+    let tagged = geom.tagged_clear_ranges();
+    assert_eq!(tagged.len(), 2, "{tagged:?}");
+    let sources: Vec<geometry::ClearRangeSource> = tagged.iter().map(|r| r.source).collect();
+    assert_eq!(
+        sources,
+        vec![
+            geometry::ClearRangeSource::MacroExposure,
+            geometry::ClearRangeSource::Polarity
+        ]
+    );
+}
+
+/// A macro defined after the flash that uses it still produces geometry.
+#[test]
+fn macro_defined_after_flash_is_resolved() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10LATE*%\nD10*\nX0Y0D03*\n\
+        %AMLATE*1,1,1.0,0,0*%\nM02*\n",
+    );
+
+    assert!(
+        !geom.warnings.iter().any(|w| w.contains("not defined")),
+        "warnings: {:?}",
+        geom.warnings
+    );
+    assert!(geom.vertex_count > 0);
+}
+
+/// The convex hull of an L-shaped region skips the reflex corner and encloses every vertex.
+#[test]
+fn l_shaped_region_convex_hull() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\nG01*\nG36*\nX0Y0D02*\nX2000000Y0D01*\n\
+        X2000000Y1000000D01*\nX1000000Y1000000D01*\nX1000000Y2000000D01*\n\
+        X0Y2000000D01*\nX0Y0D01*\nG37*\nM02*\n",
+    );
+
+    let hull = geometry::convex_hull(&geom);
+    assert_eq!(hull.len(), 5, "hull: {hull:?}");
+    assert!(!hull
+        .iter()
+        .any(|p| (p.x - 1.0).abs() < 1e-6 && (p.y - 1.0).abs() < 1e-6));
+
+    for (i, a) in hull.iter().enumerate() {
+        let b = hull[(i + 1) % hull.len()];
+        for xy in geom.positions.chunks_exact(2) {
+            let (px, py) = (f64::from(xy[0]), f64::from(xy[1]));
+            let side = (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x);
+            assert!(side >= -1e-9, "({px}, {py}) lies outside hull edge {i}");
+        }
+    }
+}
+
+/// A KiCad `RoundRect` flash has overlapping primitives; the merged outline has the rounded-rectangle area.
+#[test]
+fn roundrect_macro_merged_area_excludes_overlap() {
+    let geom = convert_source(
+        b"%FSLAX46Y46*%\n%MOMM*%\n%AMRoundRect*\n\
+        0 Rectangle with rounded corners*\n\
+        4,1,4,$2,$3,$4,$5,$6,$7,$8,$9,$2,$3,0*\n\
+        1,1,$1+$1,$2,$3*\n1,1,$1+$1,$4,$5*\n1,1,$1+$1,$6,$7*\n1,1,$1+$1,$8,$9*\n\
+        20,1,$1+$1,$2,$3,$4,$5,0*\n20,1,$1+$1,$4,$5,$6,$7,0*\n\
+        20,1,$1+$1,$6,$7,$8,$9,0*\n20,1,$1+$1,$8,$9,$2,$3,0*%\n\
+        %ADD10RoundRect,0.25X-0.75X-0.5X0.75X-0.5X0.75X0.5X-0.75X0.5*%\n\
+        D10*\nX0Y0D03*\nM02*\n",
+    );
+
+    assert!(geom.vertex_count > 0, "warnings: {:?}", geom.warnings);
+    assert!(geom.positions.iter().all(|v| v.is_finite()));
+
+    let ring_area = |ring: &[geometry::Point]| {
+        let n = ring.len();
+        (0..n)
+            .map(|i| {
+                let (a, b) = (ring[i], ring[(i + 1) % n]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f64>()
+            / 2.0
+    };
+    let polygons = geometry::to_polygons(&geom);
+    assert_eq!(polygons.len(), 1, "{polygons:?}");
+    let area: f64 = polygons
+        .iter()
+        .map(|p| ring_area(&p.outer) + p.holes.iter().map(|h| ring_area(h)).sum::<f64>())
+        .sum();
+    let expected = 2.0 * 1.5 - (4.0 - std::f64::consts::PI) * 0.25 * 0.25;
+    assert!(
+        (area - expected).abs() < expected * 0.01,
+        "area {area} should approximate {expected}"
+    );
+}
+
+/// A triangle budget stops a huge step-repeat block while it is replicated.
+#[test]
+fn triangle_budget_bounds_step_repeat_replication() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.5*%\n\
+        %SRX1000Y1000I1.0J1.0*%\nD10*\nX0Y0D03*\n%SR*%\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&data[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let options = geometry::TessellationOptions::new().with_max_triangles(1000);
+    let (geom, _) = geometry::convert_with_debug(&doc, &options).expect("convert should succeed");
+
+    assert!(geom.truncated);
+    assert!(!geom.indices.is_empty());
+    assert!(
+        geom.indices.len() <= 1000 * 3,
+        "{} indices",
+        geom.indices.len()
+    );
+    assert!(geom.vertex_count < 2000, "{} vertices", geom.vertex_count);
+}
+
+/// A tiny triangle budget stops conversion early and keeps at most that many triangles.
+#[test]
+fn triangle_budget_truncates_conversion() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\n\
+        X0Y0D03*\nX2000000Y0D03*\nX4000000Y0D03*\nX6000000Y0D03*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&data[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let options = geometry::TessellationOptions::new().with_max_triangles(40);
+    let geom =
+        geometry::convert_with_options(&doc, &options, None).expect("convert should succeed");
+
+    assert!(geom.truncated);
+    assert!(
+        geom.indices.len() <= 40 * 3,
+        "{} indices",
+        geom.indices.len()
+    );
+    assert!(!geom.indices.is_empty());
+    assert!(
+        geom.warnings
+            .iter()
+            .any(|w| w == "conversion truncated: triangle budget of 40 reached"),
+        "warnings: {:?}",
+        geom.warnings
+    );
+
+    let unlimited = convert_source(data);
+    assert!(!unlimited.truncated);
+    assert!(unlimited.indices.len() > 40 * 3);
+}
+
+/// A bare `D03*` flashes the current aperture at the point set by the preceding move.
+#[test]
+fn flash_without_coordinates_uses_current_point() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.000000*%\nD10*\nX5000000Y3000000D02*\nD03*\nM02*\n",
+    );
+
+    assert!(geom.vertex_count > 0, "warnings: {:?}", geom.warnings);
+    assert!((geom.bounds.min_x - 4.5).abs() < 1e-3, "{:?}", geom.bounds);
+    assert!((geom.bounds.max_x - 5.5).abs() < 1e-3, "{:?}", geom.bounds);
+    assert!((geom.bounds.min_y - 2.5).abs() < 1e-3, "{:?}", geom.bounds);
+    assert!((geom.bounds.max_y - 3.5).abs() < 1e-3, "{:?}", geom.bounds);
+}
+
+/// Copper area of a dark square with a centered clear square is outer minus inner.
+#[test]
+fn copper_area_subtracts_clear_square() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10R,4.0X4.0*%\n%ADD11R,2.0X2.0*%\n\
+        D10*\nX0Y0D03*\n%LPC*%\nD11*\nX0Y0D03*\n%LPD*%\nM02*\n",
+    );
+
+    let area = geometry::copper_area(&geom);
+    assert!((area - 12.0).abs() < 1e-6, "area was {area}");
+}
+
+/// A thermal primitive is skipped with a warning and listed as unsupported.
+#[test]
+fn thermal_macro_is_reported_unsupported() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%AMTHERMAL*1,1,0.5,0,0*7,0,0,2.0,1.5,0.3,45*%\n\
+        %ADD10THERMAL*%\nD10*\nX0Y0D03*\nX5000000Y0D03*\nM02*\n",
+    );
+
+    assert_eq!(geom.unsupported_features, vec!["thermal".to_string()]);
+    assert!(geom.vertex_count > 0, "other primitives are still drawn");
+    let meta = geometry::LayerMeta::from_geometry(&geom);
+    assert_eq!(meta.unsupported_features, vec!["thermal".to_string()]);
+}
+
+/// Converting into a reused builder gives the same geometry as a fresh conversion.
+#[test]
+fn convert_into_reused_builder_matches_fresh_conversion() {
+    let parse = |data: &[u8]| match gerber_parser::parse(BufReader::new(Cursor::new(data.to_vec())))
+    {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let first =
+        parse(b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10R,4.0X4.0*%\nD10*\nX0Y0D03*\nD11*\nX0Y0D03*\nM02*\n");
+    let second = parse(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.0*%\n%ADD11R,2.0X2.0*%\nD10*\nX0Y0D02*\n\
+        X5000000Y0D01*\n%LPC*%\nD11*\nX0Y0D03*\nM02*\n",
+    );
+
+    let mut builder = geometry::GeometryBuilder::new();
+    let reused_first = geometry::convert_into(&first, &mut builder).expect("convert");
+    let reused = geometry::convert_into(&second, &mut builder).expect("convert");
+    let fresh = geometry::convert(&second).expect("convert");
+
+    assert!(!reused_first.warnings.is_empty());
+    assert_eq!(reused.positions, fresh.positions);
+    assert_eq!(reused.indices, fresh.indices);
+    assert_eq!(reused.warnings, fresh.warnings);
+    assert_eq!(reused.clear_ranges, fresh.clear_ranges);
+    assert_eq!(reused.bounds, fresh.bounds);
+    assert_eq!(reused.command_count, fresh.command_count);
+    assert_eq!(builder.vertex_count(), 0);
+}
+
+/// An incremental G91 arc tessellates to the same points as its absolute form.
+#[test]
+fn incremental_arc_matches_absolute_arc() {
+    let absolute = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.5*%\nD10*\nG75*\nX5000000Y0D02*\nG03*\n\
+        X0Y5000000I-5000000J0D01*\nM02*\n",
+    );
+    let incremental = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.5*%\nD10*\nG75*\nG91*\nX5000000Y0D02*\nG03*\n\
+        X-5000000Y5000000I-5000000J0D01*\nM02*\n",
+    );
+
+    assert!(absolute.vertex_count > 0);
+    assert_eq!(incremental.positions, absolute.positions);
+    assert_eq!(incremental.indices, absolute.indices);
+}
+
+/// Apertures referencing an undefined macro are reported once up front.
+#[test]
+fn undefined_macro_references_are_aggregated() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10MISSING*%\n%ADD11C,0.5*%\nD10*\nX0Y0D03*\n\
+        X1000000Y0D03*\nM02*\n",
+    );
+
+    let aggregated: Vec<&String> = geom
+        .warnings
+        .iter()
+        .filter(|w| w.starts_with("undefined macro referenced by D"))
+        .collect();
+    assert_eq!(
+        aggregated,
+        vec!["undefined macro referenced by D10 (`MISSING`)"]
+    );
+    let skips = geom
+        .warnings
+        .iter()
+        .filter(|w| w.contains("not defined; skipping flash"))
+        .count();
+    assert_eq!(skips, 2);
+}
+
+/// A file without `%FS` reports the assumed default coordinate format.
+#[test]
+fn missing_format_spec_reports_assumed_default() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/no_format.gbr"));
+    let meta = geometry::LayerMeta::from_geometry(&geom);
+    assert!(meta.format_assumed);
+    assert_eq!(meta.coordinate_format, Some((2, 6)));
+
+    let declared = convert_source(include_bytes!("fixtures/minimal/circle.gbr"));
+    assert!(!declared.format_assumed);
+    assert_eq!(declared.coordinate_format, Some((3, 6)));
+}
+
+/// A file without `%FS` or `%MO` warns about each assumption.
+#[test]
+fn missing_format_and_units_warn_and_set_flags() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/no_header.gbr"));
+    let meta = geometry::LayerMeta::from_geometry(&geom);
+    assert!(meta.format_assumed);
+    assert!(meta.units_assumed);
+    assert_eq!(
+        meta.warnings,
+        [
+            "no %FS format specification; assuming 2.6 with leading zeros omitted",
+            "no %MO unit specification; assuming millimeters",
+        ]
+    );
+    assert!((geom.bounds.max_x - 3.0).abs() < 1e-6, "2.6 millimeters");
+
+    let declared = convert_source(include_bytes!("fixtures/minimal/circle.gbr"));
+    assert!(!declared.format_assumed && !declared.units_assumed);
+    assert!(declared.warnings.is_empty());
+}
+
+/// Probing a flashed rectangle reports copper inside and edge distances.
+#[test]
+fn probe_flashed_rectangle() {
+    let geom = convert_source(b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10R,4.0X2.0*%\nD10*\nX0Y0D03*\nM02*\n");
+
+    assert!(geometry::point_in_copper(&geom, 0.5, 0.2));
+    assert!(!geometry::point_in_copper(&geom, 3.0, 0.0));
+    let inside = geometry::distance_to_nearest_edge(&geom, 0.5, 0.2);
+    assert!((inside - 0.8).abs() < 1e-6, "inside distance was {inside}");
+    let outside = geometry::distance_to_nearest_edge(&geom, 3.0, 0.0);
+    assert!(
+        (outside - 1.0).abs() < 1e-6,
+        "outside distance was {outside}"
+    );
+}
+
+/// Flipped output mirrors Y below the layer top and keeps triangle winding.
+#[test]
+#[allow(clippy::expect_used)]
+fn flip_y_mirrors_positions_and_keeps_winding() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10R,4.0X2.0*%\nD10*\nX0Y5000000D03*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let plain = geometry::convert(&doc).expect("convert should succeed");
+    let options = geometry::TessellationOptions::new().with_flip_y(geometry::FlipAxis::LayerTop);
+    let flipped = geometry::convert_with_options(&doc, &options, None).expect("convert");
+
+    let top = plain.bounds.max_y;
+    for (original, mirrored) in plain
+        .positions
+        .iter()
+        .zip(&flipped.positions)
+        .skip(1)
+        .step_by(2)
+    {
+        assert!((f64::from(*mirrored) - (top - f64::from(*original))).abs() < 1e-5);
+    }
+    assert!(flipped.bounds.min_y.abs() < 1e-9);
+    assert!((flipped.bounds.max_y - (top - plain.bounds.min_y)).abs() < 1e-9);
+
+    let orientations = |geom: &geometry::LayerGeometry| -> Vec<bool> {
+        geom.indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let corner = |i: usize| {
+                    let base = *triangle.get(i)? as usize * 2;
+                    Some((*geom.positions.get(base)?, *geom.positions.get(base + 1)?))
+                };
+                let (a, b, c) = (corner(0)?, corner(1)?, corner(2)?);
+                Some((b.0 - a.0).mul_add(c.1 - a.1, -((b.1 - a.1) * (c.0 - a.0))) > 0.0)
+            })
+            .collect()
+    };
+    assert_eq!(orientations(&flipped), orientations(&plain));
+    assert!(!orientations(&plain).is_empty());
+}
+
+/// Command filters keep only pads or only traces of a mixed layer.
+#[test]
+#[allow(clippy::expect_used)]
+fn command_filter_separates_flashes_from_traces() {
+    let data = include_bytes!("fixtures/minimal/mixed.gbr");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let convert = |filter| {
+        let options = geometry::TessellationOptions::new().with_command_filter(filter);
+        geometry::convert_with_options(&doc, &options, None).expect("convert should succeed")
+    };
+    let all = convert(geometry::CommandFilter::ALL);
+    let flashes = convert(geometry::CommandFilter::FLASHES_ONLY);
+    let traces = convert(geometry::CommandFilter::TRACES_ONLY);
+
+    assert!(flashes.vertex_count > 0 && traces.vertex_count > 0);
+    assert_eq!(flashes.vertex_count + traces.vertex_count, all.vertex_count);
+    assert_eq!(
+        flashes.indices.len() + traces.indices.len(),
+        all.indices.len()
+    );
+    // The pads are 1 mm circles at (0, 0) and (20, 0); only the trace
+    // apex at (5, 5) reaches above them.
+    assert!((flashes.bounds.max_y - 0.5).abs() < 1e-6);
+    assert!((flashes.bounds.max_x - 20.5).abs() < 1e-6);
+    assert!(traces.bounds.max_y > 5.0 && traces.bounds.max_y < 5.1 + 1e-6);
+    assert!(traces.bounds.max_x > 20.0 && traces.bounds.max_x < 20.1 + 1e-6);
+}
+
+/// Regenerated Gerber re-parses to the same bounds and copper area.
+#[test]
+fn layer_to_gerber_round_trips_bounds_and_area() {
+    let original = convert_source(include_bytes!("fixtures/minimal/polarity.gbr"));
+    let gerber = geometry::layer_to_gerber(&original);
+    assert!(gerber.contains("%LPC*%"), "expected the cleared hole");
+
+    let regenerated = convert_source(gerber.as_bytes());
+    for (a, b) in [
+        (original.bounds.min_x, regenerated.bounds.min_x),
+        (original.bounds.min_y, regenerated.bounds.min_y),
+        (original.bounds.max_x, regenerated.bounds.max_x),
+        (original.bounds.max_y, regenerated.bounds.max_y),
+    ] {
+        assert!((a - b).abs() < 1e-5, "bounds differ: {a} vs {b}");
+    }
+    let area = geometry::copper_area(&original);
+    let regenerated_area = geometry::copper_area(&regenerated);
+    assert!(
+        (area - regenerated_area).abs() < area * 1e-3,
+        "area {area} became {regenerated_area}"
+    );
+}
+
+/// Strict mode turns undefined aperture warnings into one aggregated error.
+#[test]
+fn strict_mode_rejects_undefined_aperture() {
+    let data = include_bytes!("fixtures/minimal/undefined_aperture.gbr");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+
+    let lenient = geometry::convert(&doc);
+    assert!(lenient.is_ok_and(|geom| geom.vertex_count > 0 && geom.warnings.len() == 2));
+
+    let options = geometry::TessellationOptions::new().with_strict(geometry::StrictMode::ALL);
+    match geometry::convert_with_options(&doc, &options, None) {
+        Err(gerberview_wasm::error::GeometryError::StrictViolation(violations)) => {
+            assert_eq!(violations.len(), 2, "{violations:?}");
+        }
+        other => panic!("expected a strict violation, got {other:?}"),
+    }
+}
+
+/// Strict mode promotes a Gerber unit change after geometry as mixed units.
+#[test]
+fn strict_mode_rejects_late_unit_change() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\n\
+        %MOIN*%\nX1000000Y0D03*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&data[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+
+    let lenient = geometry::convert(&doc).expect("lenient conversion should succeed");
+    assert_eq!(
+        lenient.warning_categories,
+        [Some(geometry::WarningCategory::MixedUnits)],
+        "{:?}",
+        lenient.warnings
+    );
+
+    let strict = geometry::StrictMode {
+        mixed_units: true,
+        ..geometry::StrictMode::OFF
+    };
+    let options = geometry::TessellationOptions::new().with_strict(strict);
+    match geometry::convert_with_options(&doc, &options, None) {
+        Err(gerberview_wasm::error::GeometryError::StrictViolation(violations)) => {
+            assert_eq!(violations.len(), 1, "{violations:?}");
+            assert!(violations[0].starts_with("units %MOIN*%"), "{violations:?}");
+        }
+        other => panic!("expected a strict violation, got {other:?}"),
+    }
+}
+
+/// Converting the same source twice hashes equal; moving one flash does not.
+#[test]
+fn geometry_hash_is_stable_and_detects_changes() {
+    let data = include_bytes!("fixtures/minimal/polarity.gbr");
+    let first = geometry::geometry_hash(&convert_source(data));
+    let second = geometry::geometry_hash(&convert_source(data));
+    assert_eq!(first, second);
+
+    let source =
+        String::from_utf8_lossy(data).replace("X7500000Y2500000D03", "X7500001Y2500000D03");
+    let perturbed = geometry::geometry_hash(&convert_source(source.as_bytes()));
+    assert_ne!(first, perturbed);
+}
+
+/// A leading `%LPC*%` with no closing `%LPD*%` clears exactly the whole layer.
+#[test]
+fn leading_clear_polarity_covers_first_triangle() {
+    let geom = convert_source(
+        b"%FSLAX36Y36*%\n%MOMM*%\n%LPD*%\n%LPC*%\n%ADD10R,1.0X1.0*%\nD10*\nX0Y0D03*\nX5000000Y0D03*\nM02*\n",
+    );
+    let index_count = u32::try_from(geom.indices.len()).unwrap_or(u32::MAX);
+    assert!(index_count > 0);
+    assert_eq!(geom.clear_ranges, vec![(0, index_count)]);
+}
+
+/// Consecutive D01 segments keep the aperture selected for each one.
+#[test]
+fn aperture_change_between_draws_strokes_each_segment_with_its_width() {
+    let data = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.2*%\n%ADD11C,1.0*%\n\
+        D10*\nX0Y0D02*\nX10000000Y0D01*\nD11*\nX20000000Y0D01*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let draws: Vec<(i32, f64, f64)> = geometry::operations(&doc)
+        .filter_map(|op| match op {
+            geometry::ResolvedOp::Draw {
+                from, to, dcode, ..
+            } => Some((dcode, from.x, to.x)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(draws, vec![(10, 0.0, 10.0), (11, 10.0, 20.0)]);
+
+    let geom = convert_source(data);
+    assert!(geometry::point_in_copper(&geom, 5.0, 0.09));
+    assert!(!geometry::point_in_copper(&geom, 5.0, 0.3));
+    assert!(geometry::point_in_copper(&geom, 15.0, 0.3));
+    assert!(!geometry::point_in_copper(&geom, 15.0, 0.6));
+}
+
+/// Flashed circles are recorded as fans over their own vertices.
+#[test]
+#[allow(clippy::expect_used)]
+fn fan_ranges_describe_flashed_circles() {
+    let data = include_bytes!("fixtures/minimal/circle.gbr");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let plain = geometry::convert(&doc).expect("convert should succeed");
+    assert!(plain.fan_ranges.is_empty());
+
+    let options = geometry::TessellationOptions::new().with_fan_ranges(true);
+    let geom = geometry::convert_with_options(&doc, &options, None).expect("convert");
+    assert_eq!(geom.indices, plain.indices);
+    assert_eq!(geom.fan_ranges.len(), 4);
+
+    let per_flash = geom.vertex_count / 4;
+    for (i, fan) in (0u32..).zip(&geom.fan_ranges) {
+        assert_eq!(fan.center, i * per_flash);
+        assert_eq!(fan.rim_count, per_flash - 1);
+        assert_eq!(fan.index_start, i * 3 * (per_flash - 2));
+        let start = fan.index_start as usize;
+        let end = fan.index_end() as usize;
+        assert!(geom.indices[start..end]
+            .iter()
+            .all(|&index| (fan.center..=fan.center + fan.rim_count).contains(&index)));
+    }
+}
+
+/// The aperture report covers every D-code the copper layer draws with.
+#[test]
+fn aperture_report_lists_used_apertures() {
+    let data = include_bytes!("fixtures/arduino-uno/arduino-uno.GTL");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let report = geometry::aperture_report(&doc);
+    assert_eq!(report.len(), doc.apertures.len());
+    let geom = geometry::convert(&doc).expect("convert should succeed");
+    assert_eq!(geom.apertures, report, "conversion collects the same table");
+
+    for op in geometry::operations(&doc) {
+        let (dcode, flash) = match op {
+            geometry::ResolvedOp::Flash { dcode, .. }
+            | geometry::ResolvedOp::MacroFlash { dcode, .. } => (dcode, true),
+            geometry::ResolvedOp::Draw { dcode, .. } | geometry::ResolvedOp::Arc { dcode, .. } => {
+                (dcode, false)
+            }
+            geometry::ResolvedOp::RegionFill { .. } => continue,
+        };
+        let info = report
+            .iter()
+            .find(|info| info.dcode == dcode)
+            .unwrap_or_else(|| panic!("D{dcode} missing from report"));
+        let count = if flash {
+            info.flash_count
+        } else {
+            info.draw_count
+        };
+        assert!(count > 0, "D{dcode} used but not counted");
+    }
+    assert!(report
+        .iter()
+        .any(|info| info.flash_count > 0 && !info.parameters.is_empty()));
+}
+
+/// A region bounded by a single full-circle arc fills as a disk.
+#[test]
+fn full_circle_region_fills_disk() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/circular_region.gbr"));
+    assert!(geom.warnings.is_empty(), "{:?}", geom.warnings);
+    assert_eq!(geom.region_metrics.len(), 1);
+
+    let expected = std::f64::consts::PI * 2.5 * 2.5;
+    let area = geom.region_metrics[0].area;
+    assert!(
+        (area - expected).abs() / expected < 0.01,
+        "area {area} vs {expected}"
+    );
+    assert!((geom.bounds.min_x - 2.5).abs() < 1e-3);
+    assert!((geom.bounds.max_x - 7.5).abs() < 1e-3);
+    assert!((geom.bounds.min_y - 2.5).abs() < 1e-3);
+    assert!((geom.bounds.max_y - 7.5).abs() < 1e-3);
+
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(
+        include_bytes!("fixtures/minimal/circular_region.gbr").as_slice(),
+    ))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let Some(geometry::ResolvedOp::RegionFill { points }) = geometry::operations(&doc).next()
+    else {
+        panic!("expected a region fill");
+    };
+    assert!(points.len() > 8);
+    assert_eq!(points.first(), points.last(), "ring should close exactly");
+}
+
+/// `%IN` names the layer and `%IPPOS` keeps the image positive.
+#[test]
+#[allow(clippy::expect_used)]
+fn image_name_and_polarity_directives_are_captured() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/image_name.gbr"));
+    assert_eq!(geom.layer_name.as_deref(), Some("TopCopper"));
+    assert!(!geom.image_negative);
+    assert!(geom.vertex_count > 0);
+
+    let meta = parse_gerber_internal(include_bytes!("fixtures/minimal/image_name.gbr"))
+        .expect("parse should succeed");
+    assert_eq!(meta.layer_name.as_deref(), Some("TopCopper"));
+
+    let negative =
+        convert_source(b"%FSLAX36Y36*%\n%MOMM*%\n%IPNEG*%\n%ADD10C,1*%\nD10*\nX0Y0D03*\nM02*\n");
+    assert!(negative.image_negative);
+    assert!(negative.clear_ranges.is_empty());
+}
+
+/// A mirror-X layer transform negates every X and keeps triangles wound
+/// the same way.
+#[test]
+#[allow(clippy::expect_used)]
+fn layer_transform_mirrors_rectangle_about_y_axis() {
+    let data = include_bytes!("fixtures/minimal/rectangle.gbr");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let plain = geometry::convert(&doc).expect("convert should succeed");
+    let options = geometry::TessellationOptions::new()
+        .with_layer_transform(geometry::layer_affine(true, 0.0, 0.0, 0.0));
+    let mirrored = geometry::convert_with_options(&doc, &options, None).expect("convert");
+
+    assert!((mirrored.bounds.min_x + plain.bounds.max_x).abs() < 1e-6);
+    assert!((mirrored.bounds.max_x + plain.bounds.min_x).abs() < 1e-6);
+    assert!((mirrored.bounds.min_y - plain.bounds.min_y).abs() < 1e-6);
+    assert!((mirrored.bounds.max_y - plain.bounds.max_y).abs() < 1e-6);
+    assert!((mirrored.bounds.min_x + 6.0).abs() < 1e-6);
+
+    for (original, moved) in plain
+        .positions
+        .chunks_exact(2)
+        .zip(mirrored.positions.chunks_exact(2))
+    {
+        assert_eq!(moved[0], -original[0]);
+        assert_eq!(moved[1], original[1]);
+    }
+
+    let signed_area = |geom: &geometry::LayerGeometry, triangle: &[u32]| {
+        let vertex = |i: u32| {
+            let i = i as usize * 2;
+            (
+                f64::from(geom.positions[i]),
+                f64::from(geom.positions[i + 1]),
+            )
+        };
+        let ((ax, ay), (bx, by), (cx, cy)) = (
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2]),
+        );
+        (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+    };
+    for (before, after) in plain
+        .indices
+        .chunks_exact(3)
+        .zip(mirrored.indices.chunks_exact(3))
+    {
+        let (before, after) = (signed_area(&plain, before), signed_area(&mirrored, after));
+        assert!(before == 0.0 || before.signum() == after.signum());
+    }
+}
+
+/// The clear-only mesh holds exactly the triangles of the clear ranges.
+#[test]
+fn clear_geometry_extracts_clear_range_triangles() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/macro_clear.gbr"));
+    assert!(geom.warnings.is_empty(), "{:?}", geom.warnings);
+    assert!(geom.clear_ranges.len() >= 3);
+
+    let clear = geom.clear_geometry();
+    let clear_indices: u32 = geom
+        .clear_ranges
+        .iter()
+        .map(|&(start, end)| end - start)
+        .sum();
+    assert_eq!(clear.indices.len() as u32 / 3, clear_indices / 3);
+    assert!(clear.vertex_count < geom.vertex_count);
+    assert!(clear
+        .indices
+        .iter()
+        .all(|&index| index < clear.vertex_count));
+    assert!(clear.clear_ranges.is_empty());
+
+    // Clear circles of 2 mm in each donut and the 1 mm clear flash between.
+    assert!((clear.bounds.min_x + 1.0).abs() < 1e-6);
+    assert!((clear.bounds.max_x - 11.0).abs() < 1e-6);
+}
+
+/// Splitting by polarity puts every triangle in exactly one of two meshes.
+#[test]
+fn split_by_polarity_partitions_triangles() {
+    let geom = convert_source(include_bytes!("fixtures/minimal/macro_clear.gbr"));
+    assert!(!geom.clear_ranges.is_empty());
+
+    let (dark, clear) = geom.split_by_polarity();
+    assert!(!dark.indices.is_empty());
+    assert!(!clear.indices.is_empty());
+    assert_eq!(dark.indices.len() + clear.indices.len(), geom.indices.len());
+    assert_eq!(clear.indices.len(), geom.clear_geometry().indices.len());
+    for mesh in [&dark, &clear] {
+        assert!(mesh.clear_ranges.is_empty());
+        assert!(mesh.indices.iter().all(|&index| index < mesh.vertex_count));
+    }
+    assert!((dark.bounds.min_x - geom.dark_bounds.min_x).abs() < 1e-6);
+    assert!((dark.bounds.max_x - geom.dark_bounds.max_x).abs() < 1e-6);
+}
+
+/// Features below the minimum size are dropped and reported per D-code.
+#[test]
+#[allow(clippy::expect_used)]
+fn min_feature_size_skips_and_reports_tiny_flashes() {
+    let source = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.0001*%\n%ADD11C,1.0*%\n\
+D10*\nX0Y0D03*\nD11*\nX5000000Y0D03*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&source[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let plain = geometry::convert(&doc).expect("convert should succeed");
+    let options = geometry::TessellationOptions::new().with_min_feature_size(0.001);
+    let geom = geometry::convert_with_options(&doc, &options, None).expect("convert");
+
+    assert_eq!(geom.vertex_count * 2, plain.vertex_count);
+    assert!(
+        geom.bounds.min_x > 4.0,
+        "the tiny flash at the origin is gone"
+    );
+    assert_eq!(
+        geom.warnings,
+        ["skipped 1 features smaller than 0.001 (D10: 1)"]
+    );
+}
+
+/// A file ending inside G36 reports the open region and its pending points.
+#[test]
+#[allow(clippy::expect_used)]
+fn debug_state_reports_region_left_open() {
+    let source = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0.5*%\nD10*\nX0Y0D03*\n\
+%LPC*%\nX1000000Y0D02*\nX2000000Y0D01*\nG36*\nX0Y0D02*\nX5000000Y0D01*\nX5000000Y5000000D01*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&source[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let (_, debug) =
+        geometry::convert_with_debug(&doc, &geometry::TessellationOptions::new()).expect("convert");
+
+    assert!(debug.region_open);
+    assert_eq!(debug.region_point_count, 3);
+    assert_eq!(debug.final_point, geometry::Point { x: 5.0, y: 5.0 });
+    assert_eq!(debug.last_aperture, Some(10));
+    assert_eq!(debug.final_polarity, geometry::Polarity::Clear);
+    assert_eq!(debug.step_repeat_depth, 0);
+    assert_eq!(
+        (
+            debug.flash_count,
+            debug.draw_count,
+            debug.arc_count,
+            debug.region_count
+        ),
+        (1, 1, 0, 0)
+    );
+}
+
+/// `G01`/`G02`/`G03` on the same line as a D01 set the mode and draw.
+#[test]
+#[allow(clippy::expect_used)]
+fn interpolation_mode_combined_with_draw_on_one_line() {
+    let data = include_bytes!("fixtures/minimal/combined_modes.gbr");
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(data.as_slice()))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let (geom, debug) =
+        geometry::convert_with_debug(&doc, &geometry::TessellationOptions::new()).expect("convert");
+
+    assert_eq!((debug.draw_count, debug.arc_count), (2, 2));
+    assert_eq!(debug.final_point, geometry::Point { x: 0.0, y: 0.0 });
+    assert!(geom.vertex_count > 0);
+    assert!(geom.warnings.is_empty(), "{:?}", geom.warnings);
+    // Both arcs bulge toward +x: the G03 arc past x = 10, the clockwise
+    // G02 arc into the square, leaving only half the trace width at x < 0.
+    assert!(geom.bounds.max_x > 15.0 && geom.bounds.max_x < 15.2);
+    assert!((geom.bounds.min_x + 0.127).abs() < 1e-3);
+}
+
+/// Zero-size aperture warnings name the D-code and the skipped operation.
+#[test]
+#[allow(clippy::expect_used)]
+fn zero_size_aperture_warnings_name_dcode_and_operation() {
+    let source = b"%FSLAX36Y36*%\n%MOMM*%\n%ADD10C,0*%\n%ADD11R,0X1*%\n\
+D10*\nX0Y0D03*\nX1000000Y0D01*\nD11*\nX0Y0D02*\nG03*\nX1000000Y0I500000J0D01*\nM02*\n";
+    let doc = match gerber_parser::parse(BufReader::new(Cursor::new(&source[..]))) {
+        Ok(d) | Err((d, _)) => d,
+    };
+    let geom = geometry::convert(&doc).expect("convert should succeed");
+    assert_eq!(
+        geom.warnings,
+        [
+            "D10 circle diameter is zero; skipping flash",
+            "D10 circle diameter is zero; skipping stroke",
+            "D11 rectangle width is zero; skipping arc",
+        ]
+    );
+}
+
+/// Coordinate blocks without a D-code repeat the last operation, D01 here,
+/// and each one warns at its own command.
+#[test]
+#[allow(clippy::expect_used)]
+fn coordinate_block_without_dcode_repeats_modal_d01() {
+    let header = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX1000000Y0D01*\n";
+    let convert = |body: &str| {
+        let source = format!("{header}{body}M02*\n");
+        let doc = match gerber_parser::parse(BufReader::new(Cursor::new(source.as_bytes()))) {
+            Ok(d) | Err((d, _)) => d,
+        };
+        geometry::convert_with_debug(&doc, &geometry::TessellationOptions::new()).expect("convert")
+    };
+
+    let (modal, debug) = convert("X1000000Y1000000*\nG01X0*\n");
+    let (explicit, _) = convert("X1000000Y1000000D01*\nG01X0D01*\n");
+    assert_eq!(debug.draw_count, 3);
+    assert_eq!(modal.vertex_count, explicit.vertex_count);
+    assert_eq!(modal.bounds, explicit.bounds);
+
+    let details = modal.warning_details();
+    assert_eq!(details.len(), 2, "{:?}", modal.warnings);
+    assert!(details
+        .iter()
+        .all(|d| d.message.contains("without D-code repeats D01") && d.command_index.is_some()));
+    assert!(details[0].command_index < details[1].command_index);
+}